@@ -1,54 +1,79 @@
-use crate::client::Client;
-use crate::send_message;
-use crate::settings::{NodeCheckSettings, Settings};
+use crate::checkers::{Checker, ValidatorContext};
+use crate::events::{Account, Event};
+use crate::settings::NodeCheckSettings;
+use crate::storage::Metric;
+use async_trait::async_trait;
 use std::collections::HashMap;
-use std::sync::{Arc, RwLock};
-use std::thread;
-use std::thread::{sleep, JoinHandle};
+use std::sync::Mutex;
+use std::time::Duration;
 
-pub fn run(settings: &Settings) -> JoinHandle<()> {
-    let nodes_check_list: Arc<RwLock<Vec<NodeCheckSettings>>> =
-        Arc::new(RwLock::new(settings.nodes.clone()));
-    let balance_period = settings.timeouts.balance_check_period;
-    let balance_check_list = nodes_check_list.clone();
-    let telegram_settings = settings.telegram.clone();
+/// Reports identity and vote balance changes between checks.
+#[derive(Default)]
+pub struct BalanceCheck {
+    nodes_map: Mutex<HashMap<String, (f64, f64)>>,
+}
 
-    thread::spawn(move || {
-        tracing::info!("Start balance check thread");
-        let mut nodes_map: HashMap<String, (f64, f64)> = HashMap::new();
-        loop {
-            for validator in balance_check_list.read().unwrap().iter() {
-                tracing::trace!("Check balance for {}", validator.validator.name);
-                let client = Client::new(&validator.validator);
-                let identity_balance = client.get_identity_balance();
-                let vote_balance = client.get_vote_balance();
-                if nodes_map.contains_key(&client.validator.name) {
-                    let prev_value = nodes_map.get(&client.validator.name).unwrap();
-                    if (prev_value.0 - identity_balance).abs() > 0.05 && identity_balance >= 0. {
-                        send_message(format!("<b>{}</b>\npubkey -> {}\n<b>Identity balance changed!!! {:.3};{:.3};{:.3}</b>!!!", client.validator.name.as_str(), &client.validator.identity[..16], prev_value.0, identity_balance, identity_balance - prev_value.0), telegram_settings.token.as_str(), telegram_settings.alert_chat_id).expect("Send alert message error");
-                        tracing::info!(
-                            "identity: {:.3};{:.3};{:.3}",
-                            prev_value.0,
-                            identity_balance,
-                            identity_balance - prev_value.0
-                        );
-                    }
-                    if (prev_value.1 - vote_balance).abs() > 0. && vote_balance >= 0. {
-                        send_message(format!("<b>{}</b>\npubkey -> {}\n<b>Vote balance changed!!! {:.3};{:.3};{:.3}</b>!!!", client.validator.name.as_str(), &client.validator.identity[..16], prev_value.1, vote_balance, vote_balance - prev_value.1), telegram_settings.token.as_str(), telegram_settings.alert_chat_id).expect("Send alert message error");
-                        tracing::info!(
-                            "vote: {:.3};{:.3};{:.3}",
-                            prev_value.1,
-                            vote_balance,
-                            vote_balance - prev_value.1
-                        );
-                    }
-                }
-                if identity_balance >= 0. && vote_balance >= 0. {
-                    nodes_map.insert(client.validator.name, (identity_balance, vote_balance));
+#[async_trait]
+impl Checker for BalanceCheck {
+    fn name(&self) -> &'static str {
+        "balance_check"
+    }
+
+    fn interval(&self, node: &NodeCheckSettings) -> Duration {
+        node.balance_check_period()
+    }
+
+    async fn check(&self, context: &ValidatorContext) -> Vec<Event> {
+        let (validator, client) = (&context.node, &context.client);
+        let name = &client.validator.name;
+        tracing::trace!("Check balance for {}", name);
+        let mut events = Vec::new();
+        let (identity_balance, vote_balance) =
+            tokio::join!(client.get_identity_balance(), client.get_vote_balance());
+        let previous = self.nodes_map.lock().unwrap().get(name).copied();
+        if let Some(prev_value) = previous {
+            for (account, previous, current, delta) in [
+                (
+                    Account::Identity,
+                    prev_value.0,
+                    identity_balance,
+                    validator.identity_balance_delta(),
+                ),
+                (
+                    Account::Vote,
+                    prev_value.1,
+                    vote_balance,
+                    validator.vote_balance_delta(),
+                ),
+            ] {
+                if (previous - current).abs() > delta && current >= 0. {
+                    events.push(Event::BalanceChanged {
+                        validator: client.validator.clone(),
+                        account,
+                        previous,
+                        current,
+                    });
                 }
             }
-            tracing::trace!("Sleep balance thread on {:?}", balance_period);
-            sleep(balance_period);
         }
-    })
+        if identity_balance >= 0. && vote_balance >= 0. {
+            events.push(Event::Measured {
+                validator: name.clone(),
+                samples: vec![
+                    (Metric::IdentityBalance, identity_balance),
+                    (Metric::VoteBalance, vote_balance),
+                ],
+            });
+            events.push(Event::BalanceChecked {
+                validator: name.clone(),
+                identity: identity_balance,
+                vote: vote_balance,
+            });
+            self.nodes_map
+                .lock()
+                .unwrap()
+                .insert(name.clone(), (identity_balance, vote_balance));
+        }
+        events
+    }
 }
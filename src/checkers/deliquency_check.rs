@@ -1,48 +1,58 @@
-use crate::client::Client;
-use crate::send_message;
-use crate::settings::{NodeCheckSettings, Settings};
-use std::sync::{Arc, RwLock};
-use std::thread;
-use std::thread::{sleep, JoinHandle};
+use crate::checkers::{Checker, ValidatorContext};
+use crate::events::Event;
+use crate::settings::NodeCheckSettings;
+use crate::status::AlertKind;
+use crate::storage::Metric;
+use async_trait::async_trait;
+use std::time::Duration;
 
-pub fn run(settings: &Settings) -> JoinHandle<()> {
-    let nodes_check_list: Arc<RwLock<Vec<NodeCheckSettings>>> =
-        Arc::new(RwLock::new(settings.nodes.clone()));
-    let delinq_list = nodes_check_list.clone();
-    let delinquency_period = settings.timeouts.deliquency_check_period;
-    let telegram_settings = settings.telegram.clone();
+/// Watches the vote account of every validator for delinquency.
+#[derive(Default)]
+pub struct DelinquencyCheck;
 
-    thread::spawn(move || {
-        tracing::info!("Start delinquency thread");
-        loop {
-            for validator in delinq_list.read().unwrap().iter() {
-                tracing::trace!("Check delinquent for {}", validator.validator.name);
-                let client = Client::new(&validator.validator);
-                match client.is_delinquent() {
-                    None => {
-                        tracing::trace!("Validator {} is healthy", client.validator.name);
-                    }
-                    Some(value) => {
-                        if value {
-                            send_message(
-                                format!(
-                                    "<b>{}</b>\npubkey -> {}\n<b>DELINQUENT!!!</b>!!!",
-                                    client.validator.name.as_str(),
-                                    &client.validator.identity[..16]
-                                ),
-                                telegram_settings.token.as_str(),
-                                telegram_settings.alert_chat_id,
-                            )
-                            .expect("Send alert message error");
-                            tracing::error!("Validator {} is delinquent", client.validator.name);
-                        } else {
-                            tracing::trace!("Validator {} is healthy", client.validator.name);
-                        }
-                    }
-                }
+#[async_trait]
+impl Checker for DelinquencyCheck {
+    fn name(&self) -> &'static str {
+        "delinquency_check"
+    }
+
+    fn interval(&self, node: &NodeCheckSettings) -> Duration {
+        node.deliquency_check_period()
+    }
+
+    async fn check(&self, context: &ValidatorContext) -> Vec<Event> {
+        let (client, state) = (&context.client, &context.state);
+        let name = &client.validator.name;
+        tracing::trace!("Check delinquent for {}", name);
+        let (delinquent, slot_lag) = tokio::join!(client.is_delinquent(), client.get_slot_lag());
+        let mut samples = Vec::new();
+        if let Some(value) = delinquent {
+            samples.push((Metric::Delinquent, if value { 1. } else { 0. }));
+        }
+        if let Some(lag) = slot_lag {
+            samples.push((Metric::SlotLag, lag as f64));
+        }
+        let mut events = vec![
+            Event::Measured {
+                validator: name.clone(),
+                samples,
+            },
+            Event::DelinquencyChecked {
+                validator: name.clone(),
+                delinquent,
+                slot_lag,
+            },
+        ];
+        match delinquent {
+            Some(true) => events.push(Event::Delinquent(client.validator.clone())),
+            Some(false) if state.status.is_active(name, AlertKind::Delinquent) => {
+                events.push(Event::Recovered {
+                    validator: name.clone(),
+                    kind: AlertKind::Delinquent,
+                })
             }
-            tracing::trace!("Sleep delinquency thread on {:?}", delinquency_period);
-            sleep(delinquency_period);
+            _ => tracing::trace!("Validator {} is healthy", name),
         }
-    })
+        events
+    }
 }
@@ -0,0 +1,216 @@
+use crate::checkers::node_stats::chart_events;
+use crate::checkers::{Checker, ValidatorContext};
+use crate::client::RpcLimits;
+use crate::cluster::ClusterCache;
+use crate::events::Event;
+use crate::report::chart;
+use crate::report::epoch::{EpochBaseline, EpochSummary};
+use crate::rewards::{self, EpochRewards, RewardsHistory, RewardsTrend};
+use crate::settings::{NodeCheckSettings, RewardsSettings, Settings, Validator};
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Tracks epoch boundaries: sends the summary of every finished epoch, keeps the
+/// fee revenue ledgers up to date and reports inflation rewards.
+pub struct EpochCheck {
+    rewards_settings: RewardsSettings,
+    charts: bool,
+    baselines: Mutex<HashMap<String, EpochBaseline>>,
+    rewards_history: Mutex<RewardsHistory>,
+    /// Finished epoch whose rewards were looked for in vain, and how often, per validator.
+    reward_attempts: Mutex<HashMap<String, (u64, u32)>>,
+    clusters: ClusterCache,
+}
+
+impl EpochCheck {
+    pub fn new(settings: &Settings, limits: &RpcLimits) -> Self {
+        EpochCheck {
+            rewards_settings: settings.rewards.clone(),
+            charts: settings.reports.charts,
+            baselines: Mutex::new(HashMap::new()),
+            rewards_history: Mutex::new(RewardsHistory::load(Path::new(
+                &settings.rewards.history_path,
+            ))),
+            reward_attempts: Mutex::new(HashMap::new()),
+            clusters: ClusterCache::new(&settings.clusters, limits),
+        }
+    }
+}
+
+impl EpochCheck {
+    /// False once the rewards of `epoch` were missing `max_attempts` times.
+    fn may_look_for_rewards(&self, name: &str, epoch: u64) -> bool {
+        match self.reward_attempts.lock().unwrap().get(name) {
+            Some((missing, attempts)) if *missing == epoch => {
+                *attempts < self.rewards_settings.max_attempts
+            }
+            _ => true,
+        }
+    }
+
+    fn record_missing_rewards(&self, name: &str, epoch: u64) {
+        let mut reward_attempts = self.reward_attempts.lock().unwrap();
+        let entry = reward_attempts
+            .entry(name.to_string())
+            .or_insert((epoch, 0));
+        if entry.0 != epoch {
+            *entry = (epoch, 0);
+        }
+        entry.1 += 1;
+        if entry.1 == self.rewards_settings.max_attempts {
+            tracing::warn!(
+                "No rewards for epoch {} of {} after {} attempts, giving up",
+                epoch,
+                name,
+                entry.1
+            );
+        }
+    }
+}
+
+#[async_trait]
+impl Checker for EpochCheck {
+    fn name(&self) -> &'static str {
+        "epoch_check"
+    }
+
+    fn interval(&self, node: &NodeCheckSettings) -> Duration {
+        node.epoch_check_period()
+    }
+
+    async fn check(&self, context: &ValidatorContext) -> Vec<Event> {
+        let (client, state) = (&context.client, &context.state);
+        let mut events = Vec::new();
+        let Some(epoch_info) = client.get_raw_epoch_info().await else {
+            return events;
+        };
+        state
+            .revenue
+            .update(client, epoch_info.epoch, epoch_info.absolute_slot)
+            .await;
+        let baseline = self
+            .baselines
+            .lock()
+            .unwrap()
+            .get(&client.validator.name)
+            .cloned();
+        match baseline {
+            None => {
+                let partial = epoch_info.slot_index > 0;
+                let baseline = EpochBaseline::observe(client, epoch_info.epoch, partial).await;
+                self.baselines
+                    .lock()
+                    .unwrap()
+                    .insert(client.validator.name.clone(), baseline);
+            }
+            Some(baseline) if baseline.epoch < epoch_info.epoch => {
+                tracing::info!(
+                    "Epoch {} finished for {}",
+                    baseline.epoch,
+                    client.validator.name
+                );
+                let next = EpochBaseline::observe(client, epoch_info.epoch, false).await;
+                let cluster = self
+                    .clusters
+                    .get(&client.validator, Some(baseline.epoch))
+                    .await;
+                let mut summary = EpochSummary::collect(client, &cluster, &baseline, &next).await;
+                let mut remaining = usize::MAX;
+                loop {
+                    let left = state.revenue.update(client, baseline.epoch, u64::MAX).await;
+                    if left == 0 || left >= remaining {
+                        break;
+                    }
+                    remaining = left;
+                }
+                summary.fee_revenue = state
+                    .revenue
+                    .epoch_revenue(&client.validator.name, baseline.epoch)
+                    .await;
+                events.push(Event::ReportReady(summary.to_html()));
+                if self.charts {
+                    if let Some(epoch_start) = client.get_epoch_start_time(1).await {
+                        events.extend(chart_events(chart::validator_charts(
+                            &state.store,
+                            &client.validator.name,
+                            epoch_start,
+                        )));
+                    }
+                }
+                self.baselines
+                    .lock()
+                    .unwrap()
+                    .insert(client.validator.name.clone(), next);
+            }
+            Some(_) => {
+                tracing::trace!("Epoch {} in progress", epoch_info.epoch);
+            }
+        }
+
+        let finished_epoch = epoch_info.epoch.saturating_sub(1);
+        let reported = self
+            .rewards_history
+            .lock()
+            .unwrap()
+            .contains(&client.validator.name, finished_epoch);
+        if !reported && self.may_look_for_rewards(&client.validator.name, finished_epoch) {
+            let before = client.calls();
+            let rewards =
+                EpochRewards::collect(client, finished_epoch, epoch_info.slots_in_epoch).await;
+            if rewards.is_none() && !client.calls().since(before).all_failed() {
+                self.record_missing_rewards(&client.validator.name, finished_epoch);
+            }
+            if let Some(rewards) = rewards {
+                let mut history = self.rewards_history.lock().unwrap();
+                events.extend(report_rewards(
+                    &client.validator,
+                    &rewards,
+                    &history,
+                    &self.rewards_settings,
+                ));
+                history.record(&client.validator.name, rewards);
+                let history_path = Path::new(&self.rewards_settings.history_path);
+                if let Err(e) = history.save(history_path) {
+                    tracing::error!("Failed to save rewards history: {}", e);
+                }
+            }
+        }
+        events
+    }
+}
+
+fn report_rewards(
+    validator: &Validator,
+    rewards: &EpochRewards,
+    history: &RewardsHistory,
+    rewards_settings: &RewardsSettings,
+) -> Vec<Event> {
+    let previous = history.previous(
+        &validator.name,
+        rewards.epoch,
+        rewards_settings.trend_epochs,
+    );
+    let trend = RewardsTrend::from_history(previous);
+    let mut events = vec![Event::ReportReady(rewards::to_html(
+        &validator.label(),
+        rewards,
+        trend.as_ref(),
+    ))];
+
+    if let Some(trend) = trend {
+        if trend.is_dropped(rewards, rewards_settings.alert_drop_percent) {
+            events.push(Event::RewardsBelowTrend {
+                validator: validator.clone(),
+                epoch: rewards.epoch,
+                apy: rewards.apy(),
+                trend_apy: trend.apy,
+                income: rewards.commission_income,
+                trend_income: trend.commission_income,
+            });
+        }
+    }
+    events
+}
@@ -0,0 +1,90 @@
+use crate::checkers::node_stats;
+use crate::checkers::{Checker, ValidatorContext};
+use crate::client::RpcLimits;
+use crate::cluster::ClusterCache;
+use crate::events::Event;
+use crate::report::{Health, ValidatorReport};
+use crate::settings::{NodeCheckSettings, Settings};
+use crate::status::{AlertKind, StatusRegistry};
+use async_trait::async_trait;
+use std::time::Duration;
+
+/// Raises the skip rate and minimum balance alerts. Runs on its own interval, so
+/// alerts keep coming whatever reports are scheduled or disabled.
+pub struct HealthCheck {
+    clusters: ClusterCache,
+}
+
+impl HealthCheck {
+    pub fn new(settings: &Settings, limits: &RpcLimits) -> Self {
+        // Shared by the validators checked on the same tick, fetched again on the next one.
+        let shortest = settings
+            .nodes
+            .iter()
+            .map(NodeCheckSettings::health_check_period)
+            .min()
+            .unwrap_or(settings.timeouts.health_check_period);
+        HealthCheck {
+            clusters: ClusterCache::with_ttl(&settings.clusters, limits, shortest / 2),
+        }
+    }
+}
+
+#[async_trait]
+impl Checker for HealthCheck {
+    fn name(&self) -> &'static str {
+        "health_check"
+    }
+
+    fn interval(&self, node: &NodeCheckSettings) -> Duration {
+        node.health_check_period()
+    }
+
+    async fn check(&self, context: &ValidatorContext) -> Vec<Event> {
+        let (node, client, state) = (&context.node, &context.client, &context.state);
+        tracing::trace!("Check health of {}", node.validator.name);
+        let report = node_stats::collect(client, node, &self.clusters, state).await;
+        let mut events = vec![Event::Measured {
+            validator: report.name.clone(),
+            samples: report.metrics(),
+        }];
+        events.extend(alerts(&report, node, &state.status));
+        events.push(Event::ReportCollected(Box::new(report)));
+        events
+    }
+}
+
+fn alerts(
+    report: &ValidatorReport,
+    node: &NodeCheckSettings,
+    status: &StatusRegistry,
+) -> Vec<Event> {
+    let mut events = Vec::new();
+    let recovered = |kind| Event::Recovered {
+        validator: report.name.clone(),
+        kind,
+    };
+    if report.health == Health::CriticalSkipRate {
+        events.push(Event::SkipRateCritical {
+            validator: node.validator.clone(),
+            skip_rate: report.skip_rate.skip_rate,
+            cluster_skip_rate: report.skip_rate.cluster_skip_rate,
+        });
+    } else if status.is_active(&report.name, AlertKind::CriticalSkipRate) {
+        events.push(recovered(AlertKind::CriticalSkipRate));
+    }
+
+    let identity_balance = report.balances.identity;
+    if identity_balance < 0. {
+        // The balance call failed, so the balance is neither low nor recovered.
+    } else if identity_balance < node.min_balance_amount() {
+        events.push(Event::LowBalance {
+            validator: node.validator.clone(),
+            balance: identity_balance,
+            minimum: node.min_balance_amount(),
+        });
+    } else if status.is_active(&report.name, AlertKind::LowBalance) {
+        events.push(recovered(AlertKind::LowBalance));
+    }
+    events
+}
@@ -1,3 +1,50 @@
 pub mod balance_check;
 pub mod deliquency_check;
+pub mod epoch_check;
+pub mod health_check;
 pub mod node_stats;
+
+use crate::client::Client;
+use crate::events::Event;
+use crate::settings::NodeCheckSettings;
+use crate::state::SharedState;
+use async_trait::async_trait;
+use std::time::Duration;
+
+/// Everything a check of one validator needs. Built once per validator and
+/// checker, so the RPC client keeps its connections between runs.
+pub struct ValidatorContext {
+    pub node: NodeCheckSettings,
+    pub client: Client,
+    pub state: SharedState,
+}
+
+impl ValidatorContext {
+    pub fn new(node: &NodeCheckSettings, state: &SharedState) -> Self {
+        ValidatorContext {
+            node: node.clone(),
+            client: Client::new(&node.validator, &state.rpc),
+            state: state.clone(),
+        }
+    }
+}
+
+/// A check run periodically for every validator by the [`Scheduler`](crate::scheduler::Scheduler).
+#[async_trait]
+pub trait Checker: Send + Sync + 'static {
+    /// Name in logs and in the `/health` endpoint.
+    fn name(&self) -> &'static str;
+
+    /// Time between two checks of `node`.
+    fn interval(&self, node: &NodeCheckSettings) -> Duration;
+
+    async fn check(&self, context: &ValidatorContext) -> Vec<Event>;
+}
+
+/// The shortest check period of `nodes`, used to tell when a checker is stale.
+pub fn shortest_period(
+    nodes: &[NodeCheckSettings],
+    period: impl Fn(&NodeCheckSettings) -> Duration,
+) -> Option<Duration> {
+    nodes.iter().map(period).min()
+}
@@ -1,159 +1,195 @@
-use crate::client::Client;
-use crate::send_message;
+use crate::client::{CallCount, Client};
+use crate::cluster::ClusterCache;
+use crate::events::Event;
+use crate::report::chart;
+use crate::report::schedule::{ReportKind, ReportScheduler};
+use crate::report::{render, DailyStats, ReportFormat, ValidatorReport};
+use crate::scheduler::Shutdown;
 use crate::settings::{NodeCheckSettings, Settings};
-use chrono::Timelike;
-use std::sync::{Arc, RwLock};
-use std::thread;
-use std::thread::{sleep, JoinHandle};
-use std::time::Duration;
+use crate::state::SharedState;
+use futures::future::join_all;
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::Path;
 
-pub fn run(settings: &Settings) -> JoinHandle<()> {
-    let nodes_check_list: Arc<RwLock<Vec<NodeCheckSettings>>> =
-        Arc::new(RwLock::new(settings.nodes.clone()));
-    let telegram_settings = settings.telegram.clone();
-    thread::spawn(move || {
-        tracing::info!("Start node stats check thread");
+/// Sends the scheduled reports until shutdown. Their timing follows the report
+/// schedules rather than a check interval, so this runs as a task of its own.
+pub fn run(
+    settings: &Settings,
+    state: SharedState,
+    mut shutdown: Shutdown,
+) -> impl Future<Output = ()> {
+    let nodes = settings.nodes.clone();
+    let report_export = settings.reports.export.clone();
+    let scheduler = ReportScheduler::new(&settings.reports);
+    let charts = settings.reports.charts;
+    let cluster_settings = settings.clusters.clone();
+    async move {
+        if scheduler.is_empty() {
+            tracing::warn!("No report schedules configured, no reports are sent");
+            return;
+        }
+        tracing::info!("Start node stats check task");
+        // Registered only now, a task with nothing to schedule is not a stopped checker.
+        let _guard = state.status.register_checker("node_stats", None);
+        let mut previous_daily: HashMap<String, ValidatorReport> = HashMap::new();
+        let mut last_fire = chrono::Utc::now();
         loop {
-            let current_minutes = chrono::Utc::now().minute() as u64;
-            let current_seconds = chrono::Utc::now().second() as u64;
-            let seconds_to_next_hour = 3600 - (current_minutes * 60 + current_seconds);
-
-            tracing::info!("Sleep node stats thread on {}s", seconds_to_next_hour);
-            sleep(Duration::from_secs(seconds_to_next_hour));
+            let now = chrono::Utc::now().max(last_fire);
+            let Some((fire_at, kinds)) = scheduler.next_after(now) else {
+                tracing::warn!("No report schedules configured, stop node stats task");
+                return;
+            };
+            let wait = (fire_at - chrono::Utc::now()).to_std().unwrap_or_default();
+            tracing::info!("Sleep node stats task on {:?} for {:?}", wait, kinds);
+            tokio::select! {
+                _ = tokio::time::sleep(wait) => {}
+                _ = shutdown.requested() => return,
+            }
+            last_fire = fire_at;
 
-            for node in nodes_check_list.read().unwrap().iter() {
-                let client = Client::new(&node.validator);
-                let skip_rate = client.get_skip_rate();
-                let cluster_skip_rate = client.get_stake_weighted_skip_rate().1;
-                let epoch_info = client.get_epoch_info();
-                let blocks = client.get_block_production();
-                let slot_count = client.get_slot_count();
-                let mut msg: String;
-                if skip_rate >= cluster_skip_rate + node.critical_excess_of_skip_rate
-                    && epoch_info.2 > 0.5
-                    && blocks.0 as f32 / slot_count as f32 > 0.5
-                {
-                    msg = format!(
-                        "<b>{} [{}]</b> 🔴",
-                        client.validator.name,
-                        client.get_version()
-                    );
-                    send_message(
-                        format!(
-                            "<b>{}</b>\npubkey -> {}\n<b>CRITICAL_SKIP_RATE => {}!!!</b>!!!",
-                            client.validator.name.as_str(),
-                            &client.validator.identity[..16],
-                            skip_rate
-                        ),
-                        telegram_settings.token.as_str(),
-                        telegram_settings.alert_chat_id,
-                    )
-                    .expect("Send alert message error");
-                } else if let Some(delinquent) = client.is_delinquent() {
-                    if delinquent {
-                        msg = format!(
-                            "<b>{} [{}]</b> 🔴",
-                            client.validator.name,
-                            client.get_version()
-                        );
+            let mut digest_reports = Vec::new();
+            let mut daily_reports = Vec::new();
+            let mut daily_charts = Vec::new();
+            let clusters = ClusterCache::new(&cluster_settings, &state.rpc);
+            let due: Vec<(&NodeCheckSettings, Vec<ReportKind>)> = nodes
+                .iter()
+                .filter_map(|node| {
+                    let wanted: Vec<ReportKind> = kinds
+                        .iter()
+                        .filter(|kind| !node.disabled_reports.contains(kind))
+                        .cloned()
+                        .collect();
+                    if wanted.is_empty() {
+                        tracing::trace!("Reports disabled for {}", node.validator.name);
+                        return None;
+                    }
+                    Some((node, wanted))
+                })
+                .collect();
+            // Reports are collected concurrently and sent in the order of the settings.
+            let collected = join_all(due.iter().map(|(node, wanted)| {
+                let clusters = &clusters;
+                let state = &state;
+                async move {
+                    let client = Client::new(&node.validator, &state.rpc);
+                    let report = check(&client, node, clusters, state).await;
+                    let epoch_start = if charts && wanted.contains(&ReportKind::Daily) {
+                        client.get_epoch_start_time(0).await
                     } else {
-                        msg = format!(
-                            "<b>{} [{}]</b> 🟢",
-                            client.validator.name,
-                            client.get_version()
-                        );
+                        None
+                    };
+                    (report, epoch_start, client.calls())
+                }
+            }))
+            .await;
+            let calls = collected
+                .iter()
+                .map(|(_, _, calls)| *calls)
+                .fold(CallCount::default(), |total, calls| total + calls);
+            for ((_, wanted), (report, epoch_start, _)) in due.iter().zip(collected) {
+                if let Some(export) = &report_export {
+                    if let Err(e) = report.export(Path::new(&export.dir), &export.formats) {
+                        tracing::error!("Failed to export report for {}: {}", report.name, e);
                     }
-                } else {
-                    msg = format!(
-                        "<b>{} [{}]</b> 🟢",
-                        client.validator.name,
-                        client.get_version()
-                    );
                 }
-                msg.push_str("\n\n");
-                msg.push_str("<code>");
-                msg.push_str(format!("{:^16} | {:^16}\n", "identity", "vote").as_str());
-                msg.push_str(format!("{:-<35}\n", "").as_str());
-                msg.push_str(
-                    format!(
-                        "{:<16} | {:<16}\n",
-                        &client.validator.identity[..16].to_string(),
-                        &client.validator.vote[..16].to_string()
-                    )
-                    .as_str(),
-                );
-                let identity_balance = client.get_identity_balance();
-                msg.push_str(
-                    format!(
-                        "{:^16.*} | {:^16.*}\n",
-                        2,
-                        identity_balance,
-                        2,
-                        client.get_vote_balance()
-                    )
-                    .as_str(),
-                );
-                let credits_data = client.get_credits_and_place();
-                msg.push_str(format!("{:-<35}\n", "").as_str());
-                msg.push_str(
-                    format!(
-                        " place: {:^8.*} | credits: {:^7.*}\n",
-                        0, credits_data.0, 0, credits_data.1
-                    )
-                    .as_str(),
-                );
-                msg.push_str(format!("{:-<35}\n", "").as_str());
-                msg.push_str(" progress | skip | skip% | cluster%\n");
-                msg.push_str(format!("{:-<35}\n", "").as_str());
-                let progress = slot_count.to_string() + "/" + blocks.0.to_string().as_str();
-                msg.push_str(
-                    format!(
-                        "{:^10}|{:^6}|{:^7.2}|{:^9.2}\n",
-                        progress,
-                        blocks.0 - blocks.1,
-                        skip_rate,
-                        cluster_skip_rate
-                    )
-                    .as_str(),
-                );
-                msg.push_str(format!("{:-<35}\n", "").as_str());
-                msg.push_str(format!("epoch:{:^4}|{:^25}\n", epoch_info.0, epoch_info.1).as_str());
-                msg.push_str(format!("{:-<35}\n", "").as_str());
 
-                let activated_stake = client.activated_stake().unwrap_or_default();
-                msg.push_str(format!("Active stake |{:^22.2}", activated_stake).as_str());
-                msg.push_str(format!("{:-<35}\n", "").as_str());
-
-                msg.push_str("</code>");
-
-                let result = send_message(
-                    msg,
-                    telegram_settings.token.as_str(),
-                    telegram_settings.chat_id,
-                );
-                match result {
-                    Ok(_) => {
-                        tracing::info!("Ok");
-                    }
-                    Err(e) => {
-                        tracing::info!("Error: {}", e);
+                if wanted.contains(&ReportKind::Full) {
+                    state
+                        .bus
+                        .publish(Event::ReportReady(report.render(ReportFormat::Html)));
+                }
+                if wanted.contains(&ReportKind::Digest) {
+                    digest_reports.push(report.clone());
+                }
+                if wanted.contains(&ReportKind::Daily) {
+                    if charts {
+                        let epoch_start =
+                            epoch_start.unwrap_or_else(|| fire_at - chrono::Duration::days(2));
+                        daily_charts.extend(chart::validator_charts(
+                            &state.store,
+                            &report.name,
+                            epoch_start,
+                        ));
                     }
+                    daily_reports.push(report);
                 }
+            }
 
-                if identity_balance < node.min_balance_amount {
-                    send_message(
-                        format!(
-                            "<b>{}</b>\npubkey -> {}\n<b>SMALL AMOUNT => {}!!!</b>!!!",
-                            client.validator.name.as_str(),
-                            &client.validator.identity[..16],
-                            identity_balance
-                        ),
-                        telegram_settings.token.as_str(),
-                        telegram_settings.alert_chat_id,
-                    )
-                    .expect("Send alert message error");
+            if !digest_reports.is_empty() {
+                state
+                    .bus
+                    .publish(Event::ReportReady(render::digest(&digest_reports)));
+            }
+            if !daily_reports.is_empty() {
+                let stats: HashMap<String, DailyStats> = daily_reports
+                    .iter()
+                    .map(|report| {
+                        (
+                            report.name.clone(),
+                            DailyStats::from_history(&state.store, &report.name, fire_at),
+                        )
+                    })
+                    .collect();
+                state.bus.publish(Event::ReportReady(render::daily(
+                    fire_at,
+                    &daily_reports,
+                    &previous_daily,
+                    &stats,
+                )));
+                for report in daily_reports {
+                    previous_daily.insert(report.name.clone(), report);
+                }
+                for event in chart_events(daily_charts) {
+                    state.bus.publish(event);
                 }
             }
+            if calls.all_failed() {
+                tracing::warn!("Node stats failed: no RPC call succeeded");
+            } else {
+                state.status.checker_succeeded("node_stats");
+            }
         }
-    })
+    }
+}
+
+/// Collects the report of a validator and publishes its metrics. Its alerts are
+/// raised by the [`HealthCheck`](crate::checkers::health_check::HealthCheck).
+pub async fn check(
+    client: &Client,
+    node: &NodeCheckSettings,
+    clusters: &ClusterCache,
+    state: &SharedState,
+) -> ValidatorReport {
+    let report = collect(client, node, clusters, state).await;
+    state.bus.publish(Event::Measured {
+        validator: report.name.clone(),
+        samples: report.metrics(),
+    });
+    state
+        .bus
+        .publish(Event::ReportCollected(Box::new(report.clone())));
+    report
+}
+
+/// The current report of a validator, with the fee revenue of its epoch.
+pub async fn collect(
+    client: &Client,
+    node: &NodeCheckSettings,
+    clusters: &ClusterCache,
+    state: &SharedState,
+) -> ValidatorReport {
+    let cluster = clusters.get(&node.validator, None).await;
+    let mut report = ValidatorReport::collect(client, node, &cluster).await;
+    if let Ok(epoch) = report.epoch.epoch.parse() {
+        report.fee_revenue = state.revenue.epoch_revenue(&report.name, epoch).await;
+    }
+    report
+}
+
+pub fn chart_events(charts: Vec<(String, Vec<u8>)>) -> Vec<Event> {
+    charts
+        .into_iter()
+        .map(|(caption, png)| Event::ChartReady { caption, png })
+        .collect()
 }
@@ -0,0 +1,127 @@
+use clap::{Parser, Subcommand};
+use std::path::PathBuf;
+
+use crate::client::{Client, RpcLimits};
+use crate::cluster::ClusterCache;
+use crate::report::{Health, ReportFormat, ValidatorReport};
+use crate::send_message;
+use crate::settings::Settings;
+
+#[derive(Debug, Parser)]
+#[command(version, about = "Bot for solana validators")]
+pub struct Cli {
+    /// Settings file (JSON, TOML or YAML), `settings.json` next to the executable by default.
+    /// Repeat to layer several files, later ones override earlier ones
+    #[arg(long, value_name = "PATH")]
+    pub config: Vec<PathBuf>,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Run the bot (default)
+    Run,
+    /// Run every check once and print the results
+    Check,
+    /// Print the hourly report of a validator
+    Report {
+        /// Validator name from the settings
+        validator: String,
+        /// html, text, markdown, json or csv
+        #[arg(long, default_value = "text")]
+        format: ReportFormat,
+    },
+    /// Load the settings and report problems
+    ValidateConfig,
+    /// Send a test message to the report and alert chats
+    TestNotify,
+}
+
+/// Runs every check once for all validators. Returns false if any of them needs attention.
+pub async fn check(settings: &Settings) -> bool {
+    let mut healthy = true;
+    let limits = RpcLimits::new(&settings.rpc);
+    let clusters = ClusterCache::new(&settings.clusters, &limits);
+    for node in &settings.nodes {
+        let client = Client::new(&node.validator, &limits);
+        let cluster = clusters.get(&node.validator, None).await;
+        let report = ValidatorReport::collect(&client, node, &cluster).await;
+        println!("{}", report.render(ReportFormat::Text));
+        println!(
+            "slot lag: {}",
+            client
+                .get_slot_lag()
+                .await
+                .map(|lag| lag.to_string())
+                .unwrap_or_else(|| "?".to_string())
+        );
+
+        let mut problems = Vec::new();
+        if report.health != Health::Healthy {
+            problems.push(report.health.to_string());
+        }
+        if report.balances.identity < 0. || report.balances.vote < 0. {
+            problems.push("balance unavailable".to_string());
+        } else if report.balances.identity < node.min_balance_amount() {
+            problems.push(format!(
+                "identity balance {:.3} below {:.3}",
+                report.balances.identity,
+                node.min_balance_amount()
+            ));
+        }
+        if problems.is_empty() {
+            println!("result: OK\n");
+        } else {
+            healthy = false;
+            println!("result: {}\n", problems.join(", "));
+        }
+    }
+    healthy
+}
+
+pub async fn report(
+    settings: &Settings,
+    validator: &str,
+    format: ReportFormat,
+) -> Result<(), String> {
+    let node = settings
+        .nodes
+        .iter()
+        .find(|node| node.validator.name == validator)
+        .ok_or_else(|| format!("Unknown validator: {}", validator))?;
+    let limits = RpcLimits::new(&settings.rpc);
+    let client = Client::new(&node.validator, &limits);
+    let clusters = ClusterCache::new(&settings.clusters, &limits);
+    let cluster = clusters.get(&node.validator, None).await;
+    println!(
+        "{}",
+        ValidatorReport::collect(&client, node, &cluster)
+            .await
+            .render(format)
+    );
+    Ok(())
+}
+
+pub fn test_notify(settings: &Settings) -> bool {
+    let telegram = &settings.telegram;
+    let mut delivered = true;
+    for (chat, chat_id) in [
+        ("report", telegram.chat_id),
+        ("alert", telegram.alert_chat_id),
+    ] {
+        let result = send_message(
+            format!("<b>solana-bot</b> test notification to the {} chat", chat),
+            telegram,
+            chat_id,
+        );
+        match result {
+            Ok(_) => println!("{} chat {}: delivered", chat, chat_id),
+            Err(e) => {
+                delivered = false;
+                println!("{} chat {}: {}", chat, chat_id, e);
+            }
+        }
+    }
+    delivered
+}
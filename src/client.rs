@@ -1,307 +1,638 @@
+use chrono::{DateTime, Utc};
 use std::collections::HashMap;
+use std::future::Future;
 use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
 use std::time::Duration;
+use tokio::sync::Semaphore;
 
-use solana_client::rpc_client::RpcClient;
+use solana_account_decoder::{UiAccountEncoding, UiDataSliceConfig};
+use solana_client::client_error::{ClientErrorKind, Result as ClientResult};
+use solana_client::nonblocking::rpc_client::RpcClient;
 use solana_client::rpc_config::{
-    RpcBlockProductionConfig, RpcGetVoteAccountsConfig, RpcLeaderScheduleConfig,
+    RpcAccountInfoConfig, RpcBlockConfig, RpcBlockProductionConfig, RpcBlockProductionConfigRange,
+    RpcGetVoteAccountsConfig, RpcLeaderScheduleConfig, RpcProgramAccountsConfig,
 };
-use solana_client::rpc_response::RpcContactInfo;
+use solana_client::rpc_custom_error::{
+    JSON_RPC_SERVER_ERROR_LONG_TERM_STORAGE_SLOT_SKIPPED, JSON_RPC_SERVER_ERROR_SLOT_SKIPPED,
+};
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use solana_client::rpc_request::RpcError;
+use solana_client::rpc_response::{RpcContactInfo, RpcInflationReward, RpcVoteAccountStatus};
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::epoch_info::EpochInfo;
 use solana_sdk::native_token::lamports_to_sol;
 use solana_sdk::pubkey::Pubkey;
+use solana_transaction_status::{RewardType, TransactionDetails};
+
+use crate::settings::secret::redact;
+use crate::settings::{RpcSettings, Validator};
+
+const INFLATION_REWARD_BATCH: usize = 100;
+pub const DEFAULT_SLOT_TIME_MS: u64 = 400;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockFee {
+    Produced(u64),
+    Skipped,
+}
+
+/// Limits shared by the RPC clients of all validators: a timeout for each call
+/// and a cap on the requests in flight.
+#[derive(Clone)]
+pub struct RpcLimits {
+    timeout: Duration,
+    permits: Arc<Semaphore>,
+}
+
+impl RpcLimits {
+    pub fn new(settings: &RpcSettings) -> Self {
+        RpcLimits {
+            timeout: settings.timeout,
+            permits: Arc::new(Semaphore::new(settings.max_concurrent_requests)),
+        }
+    }
+}
+
+impl Default for RpcLimits {
+    fn default() -> Self {
+        RpcLimits::new(&RpcSettings::default())
+    }
+}
+
+/// RPC calls of a client that succeeded and failed.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CallCount {
+    pub succeeded: u64,
+    pub failed: u64,
+}
+
+impl CallCount {
+    /// Calls made since the client counted `earlier`.
+    pub fn since(self, earlier: CallCount) -> CallCount {
+        CallCount {
+            succeeded: self.succeeded - earlier.succeeded,
+            failed: self.failed - earlier.failed,
+        }
+    }
 
-use crate::settings::Validator;
+    /// Whether calls were made and none got an answer, as when the RPC is down. A
+    /// few failures among answers, e.g. for skipped slots, are part of a normal check.
+    pub fn all_failed(self) -> bool {
+        self.failed > 0 && self.succeeded == 0
+    }
+}
+
+impl std::ops::Add for CallCount {
+    type Output = CallCount;
+
+    fn add(self, other: CallCount) -> CallCount {
+        CallCount {
+            succeeded: self.succeeded + other.succeeded,
+            failed: self.failed + other.failed,
+        }
+    }
+}
 
 pub struct Client {
     pub validator: Validator,
-    pub client: Option<RpcClient>,
+    client: RpcClient,
+    limits: RpcLimits,
+    succeeded: AtomicU64,
+    failed: AtomicU64,
 }
 
 impl Client {
-    pub fn new(validator: &Validator) -> Self {
+    pub fn new(validator: &Validator, limits: &RpcLimits) -> Self {
         Self {
             validator: validator.to_owned(),
-            client: Some(RpcClient::new(validator.to_owned().rpc)),
+            client: RpcClient::new_with_timeout(validator.rpc.clone(), limits.timeout),
+            limits: limits.clone(),
+            succeeded: AtomicU64::new(0),
+            failed: AtomicU64::new(0),
         }
     }
 
-    pub fn get_version(&self) -> String {
-        if let Some(client) = &self.client {
-            let pubkey = Pubkey::from_str(&self.validator.identity);
-            if let Some(info) = get_contact_info(client, &pubkey.unwrap()) {
-                return info.version.unwrap_or_else(|| "?".to_string());
-            }
+    /// Calls made by this client so far.
+    pub fn calls(&self) -> CallCount {
+        CallCount {
+            succeeded: self.succeeded.load(Ordering::Relaxed),
+            failed: self.failed.load(Ordering::Relaxed),
         }
-        "?".to_string()
-    }
-
-    fn get_balance(&self, key: &str) -> f64 {
-        let pubkey = Pubkey::from_str(key);
-        match pubkey {
-            Ok(key) => {
-                if let Some(client) = &self.client {
-                    let balance = client.get_balance(&key);
-                    match balance {
-                        Ok(value) => {
-                            return lamports_to_sol(value);
-                        }
-                        Err(err) => {
-                            tracing::error!("{:?}", err.kind);
-                        }
-                    }
-                }
+    }
+
+    /// Runs one RPC request once a slot under the concurrency limit is free,
+    /// abandoning it after the call timeout.
+    async fn call<T>(
+        &self,
+        request: impl Future<Output = ClientResult<T>>,
+    ) -> Result<T, ClientErrorKind> {
+        let _permit = self.limits.permits.acquire().await.ok();
+        let result = match tokio::time::timeout(self.limits.timeout, request).await {
+            Ok(result) => result.map_err(|err| err.kind),
+            Err(_) => Err(ClientErrorKind::Custom(format!(
+                "request to {} timed out after {:?}",
+                self.client.url(),
+                self.limits.timeout
+            ))),
+        };
+        let counter = if result.is_ok() {
+            &self.succeeded
+        } else {
+            &self.failed
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        result
+    }
+
+    pub async fn get_version(&self) -> String {
+        let pubkey = Pubkey::from_str(&self.validator.identity).unwrap_or_default();
+        match self.get_contact_info(&pubkey).await {
+            Some(info) => info.version.unwrap_or_else(|| "?".to_string()),
+            None => "?".to_string(),
+        }
+    }
+
+    async fn get_balance(&self, key: &str) -> f64 {
+        let Ok(key) = Pubkey::from_str(key) else {
+            return -1.;
+        };
+        match self.call(self.client.get_balance(&key)).await {
+            Ok(value) => lamports_to_sol(value),
+            Err(err) => {
+                log_error(&err);
                 -1.
             }
-            Err(_) => -1.,
         }
     }
 
-    pub fn get_identity_balance(&self) -> f64 {
-        self.get_balance(self.validator.identity.as_str())
+    pub async fn get_identity_balance(&self) -> f64 {
+        self.get_balance(self.validator.identity.as_str()).await
     }
 
-    pub fn get_vote_balance(&self) -> f64 {
-        self.get_balance(self.validator.vote.as_str())
+    pub async fn get_vote_balance(&self) -> f64 {
+        self.get_balance(self.validator.vote.as_str()).await
     }
 
-    pub fn is_delinquent(&self) -> Option<bool> {
-        if let Some(client) = &self.client {
-            let result = client.get_vote_accounts_with_config(RpcGetVoteAccountsConfig {
-                vote_pubkey: Some(self.validator.vote.clone()),
-                ..Default::default()
-            });
-            return match result {
-                Ok(vote) => Some(!vote.delinquent.is_empty()),
-                Err(err) => {
-                    tracing::error!("{:?}", err.kind);
-                    None
-                }
-            };
-        }
-        Some(false)
+    async fn get_own_vote_accounts(&self) -> Result<RpcVoteAccountStatus, ClientErrorKind> {
+        self.call(
+            self.client
+                .get_vote_accounts_with_config(RpcGetVoteAccountsConfig {
+                    vote_pubkey: Some(self.validator.vote.clone()),
+                    ..Default::default()
+                }),
+        )
+        .await
     }
 
-    pub fn activated_stake(&self) -> Option<f64> {
-        if let Some(client) = &self.client {
-            let result = client.get_vote_accounts_with_config(RpcGetVoteAccountsConfig {
-                vote_pubkey: Some(self.validator.vote.clone()),
-                ..Default::default()
-            });
-            return match result {
-                Ok(vote) => {
-                    if vote.current.is_empty() {
-                        Some(lamports_to_sol(
-                            vote.delinquent.first().unwrap().activated_stake,
-                        ))
-                    } else {
-                        Some(lamports_to_sol(
-                            vote.current.first().unwrap().activated_stake,
-                        ))
-                    }
-                }
-                Err(err) => {
-                    tracing::error!("{:?}", err.kind);
-                    None
-                }
-            };
+    pub async fn is_delinquent(&self) -> Option<bool> {
+        match self.get_own_vote_accounts().await {
+            Ok(vote) => Some(!vote.delinquent.is_empty()),
+            Err(err) => {
+                log_error(&err);
+                None
+            }
         }
-        Some(0.)
     }
 
-    pub fn get_credits_and_place(&self) -> (usize, u64) {
-        if let Some(client) = &self.client {
-            let result = client.get_vote_accounts();
-            let vote_accounts = result.unwrap();
-            let mut current: Vec<(String, u64)> = vote_accounts
+    /// Slots between the tip of the node and its last vote.
+    pub async fn get_slot_lag(&self) -> Option<u64> {
+        let vote = self
+            .get_own_vote_accounts()
+            .await
+            .map_err(|err| log_error(&err))
+            .ok()?;
+        let last_vote = vote
+            .current
+            .iter()
+            .chain(vote.delinquent.iter())
+            .next()?
+            .last_vote;
+        let slot = self
+            .call(
+                self.client
+                    .get_slot_with_commitment(CommitmentConfig::processed()),
+            )
+            .await
+            .map_err(|err| log_error(&err))
+            .ok()?;
+        Some(slot.saturating_sub(last_vote))
+    }
+
+    pub async fn activated_stake(&self) -> Option<f64> {
+        match self.get_own_vote_accounts().await {
+            Ok(vote) => vote
                 .current
                 .iter()
-                .map(|vote_account| {
-                    let current_epoch_credits = vote_account.epoch_credits.last().unwrap();
-                    let current_credits = current_epoch_credits.1 - current_epoch_credits.2;
-                    (vote_account.node_pubkey.clone(), current_credits)
-                })
-                .collect();
-            current.sort_by(|a, b| b.1.cmp(&a.1));
-            let position_option_value = current.iter().position(|c| c.0 == self.validator.identity);
-            return match position_option_value {
-                None => (0, 0),
-                Some(value) => {
-                    let my_credits = current.get(value).unwrap();
-                    (value + 1, my_credits.1)
-                }
-            };
+                .chain(vote.delinquent.iter())
+                .next()
+                .map(|vote_account| lamports_to_sol(vote_account.activated_stake)),
+            Err(err) => {
+                log_error(&err);
+                None
+            }
         }
-        (0, 0)
-    }
-
-    pub fn get_stake_weighted_skip_rate(&self) -> (f64, f64) {
-        if let Some(client) = &self.client {
-            let result = client.get_vote_accounts();
-            let vote_accounts = result.unwrap();
-
-            let skip_rate: HashMap<_, _> = client
-                .get_block_production()
-                .ok()
-                .map(|result| {
-                    result
-                        .value
-                        .by_identity
-                        .into_iter()
-                        .map(|(identity, (leader_slots, blocks_produced))| {
-                            (
-                                identity,
-                                100. * (leader_slots.saturating_sub(blocks_produced)) as f64
-                                    / leader_slots as f64,
-                            )
-                        })
-                        .collect()
-                })
-                .unwrap_or_default();
-
-            let current_validators: Vec<(u64, Option<f64>)> = vote_accounts
-                .current
-                .iter()
-                .map(|vote_account| {
-                    (
-                        vote_account.activated_stake,
-                        skip_rate.get(&vote_account.node_pubkey).cloned(),
-                    )
-                })
-                .collect();
+    }
 
-            let delinquent_validators: Vec<(u64, Option<f64>)> = vote_accounts
-                .delinquent
-                .iter()
-                .map(|vote_account| {
-                    (
-                        vote_account.activated_stake,
-                        skip_rate.get(&vote_account.node_pubkey).cloned(),
-                    )
-                })
-                .collect();
+    /// Credits earned by every current vote account in `epoch`, or in the current
+    /// epoch when `None`, best first.
+    pub async fn get_epoch_credits(&self, epoch: Option<u64>) -> Vec<(String, u64)> {
+        match self.call(self.client.get_vote_accounts()).await {
+            Ok(vote_accounts) => {
+                let mut current: Vec<(String, u64)> = vote_accounts
+                    .current
+                    .iter()
+                    .filter_map(|vote_account| {
+                        let epoch_credits = match epoch {
+                            None => vote_account.epoch_credits.last(),
+                            Some(epoch) => vote_account
+                                .epoch_credits
+                                .iter()
+                                .find(|credits| credits.0 == epoch),
+                        }?;
+                        let credits = epoch_credits.1 - epoch_credits.2;
+                        Some((vote_account.node_pubkey.clone(), credits))
+                    })
+                    .collect();
+                current.sort_by_key(|c| std::cmp::Reverse(c.1));
+                current
+            }
+            Err(err) => {
+                log_error(&err);
+                Vec::new()
+            }
+        }
+    }
 
-            let validators: Vec<_> = current_validators
-                .into_iter()
-                .chain(delinquent_validators)
-                .collect();
+    pub async fn get_stake_weighted_skip_rate_in_range(
+        &self,
+        range: Option<(u64, u64)>,
+    ) -> (f64, f64) {
+        let vote_accounts = match self.call(self.client.get_vote_accounts()).await {
+            Ok(vote_accounts) => vote_accounts,
+            Err(err) => {
+                log_error(&err);
+                return (f64::NAN, f64::NAN);
+            }
+        };
 
-            let total_active_stake: u64 = vote_accounts
-                .current
-                .iter()
-                .chain(vote_accounts.delinquent.iter())
-                .map(|vote_account| vote_account.activated_stake)
-                .sum();
-
-            let (average_skip_rate, average_stake_weighted_skip_rate) = {
-                let mut skip_rate_len = 0;
-                let mut skip_rate_sum = 0.;
-                let mut skip_rate_weighted_sum = 0.;
-                for validator in validators.iter() {
-                    if let Some(skip_rate) = validator.1 {
-                        skip_rate_sum += skip_rate;
-                        skip_rate_len += 1;
-                        skip_rate_weighted_sum += skip_rate * validator.0 as f64;
-                    }
+        let skip_rate: HashMap<_, _> = self
+            .call(
+                self.client
+                    .get_block_production_with_config(RpcBlockProductionConfig {
+                        range: block_production_range(range),
+                        ..Default::default()
+                    }),
+            )
+            .await
+            .ok()
+            .map(|result| {
+                result
+                    .value
+                    .by_identity
+                    .into_iter()
+                    .map(|(identity, (leader_slots, blocks_produced))| {
+                        (
+                            identity,
+                            100. * (leader_slots.saturating_sub(blocks_produced)) as f64
+                                / leader_slots as f64,
+                        )
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let current_validators: Vec<(u64, Option<f64>)> = vote_accounts
+            .current
+            .iter()
+            .map(|vote_account| {
+                (
+                    vote_account.activated_stake,
+                    skip_rate.get(&vote_account.node_pubkey).cloned(),
+                )
+            })
+            .collect();
+
+        let delinquent_validators: Vec<(u64, Option<f64>)> = vote_accounts
+            .delinquent
+            .iter()
+            .map(|vote_account| {
+                (
+                    vote_account.activated_stake,
+                    skip_rate.get(&vote_account.node_pubkey).cloned(),
+                )
+            })
+            .collect();
+
+        let validators: Vec<_> = current_validators
+            .into_iter()
+            .chain(delinquent_validators)
+            .collect();
+
+        let total_active_stake: u64 = vote_accounts
+            .current
+            .iter()
+            .chain(vote_accounts.delinquent.iter())
+            .map(|vote_account| vote_account.activated_stake)
+            .sum();
+
+        let mut skip_rate_len = 0;
+        let mut skip_rate_sum = 0.;
+        let mut skip_rate_weighted_sum = 0.;
+        for validator in validators.iter() {
+            if let Some(skip_rate) = validator.1 {
+                skip_rate_sum += skip_rate;
+                skip_rate_len += 1;
+                skip_rate_weighted_sum += skip_rate * validator.0 as f64;
+            }
+        }
+
+        if skip_rate_len > 0 && total_active_stake > 0 {
+            (
+                skip_rate_sum / skip_rate_len as f64,
+                skip_rate_weighted_sum / total_active_stake as f64,
+            )
+        } else {
+            (100., 100.) // Impossible?
+        }
+    }
+
+    pub async fn get_block_production(&self) -> (usize, usize) {
+        self.get_block_production_in_range(None).await
+    }
+
+    /// Leader slots and produced blocks within the inclusive slot range, the
+    /// current epoch when `None`.
+    pub async fn get_block_production_in_range(&self, range: Option<(u64, u64)>) -> (usize, usize) {
+        let block = self
+            .call(
+                self.client
+                    .get_block_production_with_config(RpcBlockProductionConfig {
+                        identity: Some(self.validator.identity.to_string()),
+                        range: block_production_range(range),
+                        ..Default::default()
+                    }),
+            )
+            .await;
+        match block {
+            Ok(bl) => {
+                let val = bl.value.by_identity.get(self.validator.identity.as_str());
+                if let Some(v) = val {
+                    (v.0, v.1)
+                } else {
+                    (0, 0)
                 }
+            }
+            Err(err) => {
+                log_error(&err);
+                (0, 0)
+            }
+        }
+    }
 
-                if skip_rate_len > 0 && total_active_stake > 0 {
-                    (
-                        skip_rate_sum / skip_rate_len as f64,
-                        skip_rate_weighted_sum / total_active_stake as f64,
-                    )
+    pub async fn get_slot_count(&self) -> usize {
+        let leader = self
+            .call(self.client.get_leader_schedule_with_config(
+                None,
+                RpcLeaderScheduleConfig {
+                    identity: Some(self.validator.identity.to_string()),
+                    ..Default::default()
+                },
+            ))
+            .await;
+        match leader {
+            Ok(None) => 0,
+            Ok(Some(slots)) => {
+                if let Some(slots_vec) = slots.get(self.validator.identity.as_str()) {
+                    slots_vec.len()
                 } else {
-                    (100., 100.) // Impossible?
+                    0
                 }
-            };
-            return (average_skip_rate, average_stake_weighted_skip_rate);
+            }
+            Err(err) => {
+                log_error(&err);
+                0
+            }
+        }
+    }
+
+    pub async fn get_raw_epoch_info(&self) -> Option<EpochInfo> {
+        match self.call(self.client.get_epoch_info()).await {
+            Ok(value) => Some(value),
+            Err(err) => {
+                log_error(&err);
+                None
+            }
         }
-        (100., 100.) // Impossible?
     }
 
-    pub fn get_block_production(&self) -> (usize, usize) {
-        if let Some(client) = &self.client {
-            let block = client.get_block_production_with_config(RpcBlockProductionConfig {
-                identity: Some(self.validator.identity.to_string()),
+    /// First and last slot of `epoch`.
+    pub async fn get_epoch_slot_range(&self, epoch: u64) -> Option<(u64, u64)> {
+        match self.call(self.client.get_epoch_schedule()).await {
+            Ok(schedule) => Some((
+                schedule.get_first_slot_in_epoch(epoch),
+                schedule.get_last_slot_in_epoch(epoch),
+            )),
+            Err(err) => {
+                log_error(&err);
+                None
+            }
+        }
+    }
+
+    /// Stake accounts delegated to the validator vote account.
+    pub async fn get_delegated_stake_accounts(&self) -> Vec<Pubkey> {
+        let Ok(vote) = Pubkey::from_str(&self.validator.vote) else {
+            return vec![];
+        };
+        let config = RpcProgramAccountsConfig {
+            filters: Some(vec![
+                // Filter by `StakeStateV2::Stake(_, _, _)`
+                RpcFilterType::Memcmp(Memcmp::new_base58_encoded(0, &[2, 0, 0, 0])),
+                // Filter by `Delegation::voter_pubkey`, which begins at byte offset 124
+                RpcFilterType::Memcmp(Memcmp::new_base58_encoded(124, vote.as_ref())),
+            ]),
+            account_config: RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                data_slice: Some(UiDataSliceConfig {
+                    offset: 0,
+                    length: 0,
+                }),
                 ..Default::default()
-            });
-            return match block {
-                Ok(bl) => {
-                    let val = bl.value.by_identity.get(self.validator.identity.as_str());
-                    if let Some(v) = val {
-                        (v.0, v.1)
-                    } else {
-                        (0, 0)
-                    }
-                }
+            },
+            ..Default::default()
+        };
+        let stake_program = solana_sdk::stake::program::id();
+        match self
+            .call(
+                self.client
+                    .get_program_accounts_with_config(&stake_program, config),
+            )
+            .await
+        {
+            Ok(accounts) => accounts.into_iter().map(|(pubkey, _)| pubkey).collect(),
+            Err(err) => {
+                log_error(&err);
+                vec![]
+            }
+        }
+    }
+
+    pub async fn get_inflation_rewards(
+        &self,
+        addresses: &[Pubkey],
+        epoch: u64,
+    ) -> Option<Vec<Option<RpcInflationReward>>> {
+        let mut rewards = Vec::with_capacity(addresses.len());
+        for chunk in addresses.chunks(INFLATION_REWARD_BATCH) {
+            match self
+                .call(self.client.get_inflation_reward(chunk, Some(epoch)))
+                .await
+            {
+                Ok(value) => rewards.extend(value),
                 Err(err) => {
-                    tracing::error!("{:?}", err.get_transaction_error());
-                    (0, 0)
+                    log_error(&err);
+                    return None;
                 }
-            };
+            }
         }
-        (0, 0)
+        Some(rewards)
     }
 
-    pub fn get_skip_rate(&self) -> f64 {
-        let val = self.get_block_production();
-        (val.0 - val.1) as f64 * 100. / val.0 as f64
+    pub async fn get_vote_inflation_reward(&self, epoch: u64) -> Option<RpcInflationReward> {
+        let vote = Pubkey::from_str(&self.validator.vote).ok()?;
+        match self
+            .call(self.client.get_inflation_reward(&[vote], Some(epoch)))
+            .await
+        {
+            Ok(rewards) => rewards.into_iter().next().flatten(),
+            Err(err) => {
+                log_error(&err);
+                None
+            }
+        }
     }
 
-    pub fn get_slot_count(&self) -> usize {
-        if let Some(client) = &self.client {
-            let leader = client.get_leader_schedule_with_config(
-                None,
+    pub async fn get_average_slot_time_ms(&self) -> Option<u64> {
+        self.call(self.client.get_recent_performance_samples(Some(60)))
+            .await
+            .ok()
+            .and_then(|samples| {
+                let (slots, secs) = samples.iter().fold((0, 0), |(slots, secs), sample| {
+                    (slots + sample.num_slots, secs + sample.sample_period_secs)
+                });
+                (secs as u64).saturating_mul(1000).checked_div(slots)
+            })
+    }
+
+    /// Absolute leader slots of the validator in `epoch`.
+    pub async fn get_leader_slots_in_epoch(&self, epoch: u64) -> Vec<u64> {
+        let Some((first_slot, _)) = self.get_epoch_slot_range(epoch).await else {
+            return vec![];
+        };
+        let leader = self
+            .call(self.client.get_leader_schedule_with_config(
+                Some(first_slot),
                 RpcLeaderScheduleConfig {
                     identity: Some(self.validator.identity.to_string()),
                     ..Default::default()
                 },
-            );
-            let result = match leader.unwrap() {
-                None => 0,
-                Some(slots) => {
-                    if let Some(slots_vec) = slots.get(self.validator.identity.as_str()) {
-                        slots_vec.len()
-                    } else {
-                        0
-                    }
-                }
-            };
-            return result;
+            ))
+            .await;
+        match leader {
+            Ok(Some(schedule)) => schedule
+                .get(self.validator.identity.as_str())
+                .map(|slots| slots.iter().map(|slot| first_slot + *slot as u64).collect())
+                .unwrap_or_default(),
+            Ok(None) => vec![],
+            Err(err) => {
+                log_error(&err);
+                vec![]
+            }
         }
-        0
-    }
-
-    pub fn get_epoch_info(&self) -> (String, String, f32) {
-        if let Some(client) = &self.client {
-            let epoch_info = client.get_epoch_info();
-            return match epoch_info {
-                Ok(value) => {
-                    let epoch_num = value.epoch.to_string();
-                    let remaining_slots = value.slots_in_epoch - value.slot_index;
-                    let average_time_in_ms = client
-                        .get_recent_performance_samples(Some(60))
-                        .ok()
-                        .and_then(|samples| {
-                            let (slots, secs) =
-                                samples.iter().fold((0, 0), |(slots, secs), sample| {
-                                    (slots + sample.num_slots, secs + sample.sample_period_secs)
-                                });
-                            (secs as u64).saturating_mul(1000).checked_div(slots)
-                        });
-                    (
-                        epoch_num,
-                        humantime::format_duration(
-                            Duration::from_secs(remaining_slots * average_time_in_ms.unwrap())
-                                / 1000,
-                        )
-                        .to_string(),
-                        remaining_slots as f32 / value.slots_in_epoch as f32,
+    }
+
+    /// Fee reward the identity earned in its leader `slot`, `None` while the block is unavailable.
+    pub async fn get_block_fee(&self, slot: u64) -> Option<BlockFee> {
+        let block = self
+            .call(self.client.get_block_with_config(
+                slot,
+                RpcBlockConfig {
+                    transaction_details: Some(TransactionDetails::None),
+                    rewards: Some(true),
+                    max_supported_transaction_version: Some(0),
+                    ..Default::default()
+                },
+            ))
+            .await;
+        match block {
+            Ok(block) => {
+                let fee = block
+                    .rewards
+                    .unwrap_or_default()
+                    .iter()
+                    .filter(|reward| {
+                        reward.reward_type == Some(RewardType::Fee)
+                            && reward.pubkey == self.validator.identity
+                    })
+                    .map(|reward| reward.lamports.max(0) as u64)
+                    .sum();
+                Some(BlockFee::Produced(fee))
+            }
+            Err(ClientErrorKind::RpcError(RpcError::RpcResponseError { code, .. }))
+                if code == JSON_RPC_SERVER_ERROR_SLOT_SKIPPED
+                    || code == JSON_RPC_SERVER_ERROR_LONG_TERM_STORAGE_SLOT_SKIPPED =>
+            {
+                Some(BlockFee::Skipped)
+            }
+            Err(err) => {
+                log_error(&err);
+                None
+            }
+        }
+    }
+
+    /// Estimated start time of the epoch `epochs_back` epochs before the current one.
+    pub async fn get_epoch_start_time(&self, epochs_back: u64) -> Option<DateTime<Utc>> {
+        let epoch_info = self.get_raw_epoch_info().await?;
+        let slot_time_ms = self
+            .get_average_slot_time_ms()
+            .await
+            .unwrap_or(DEFAULT_SLOT_TIME_MS);
+        let slots = epoch_info.slot_index + epochs_back * epoch_info.slots_in_epoch;
+        Some(Utc::now() - chrono::Duration::milliseconds((slots * slot_time_ms) as i64))
+    }
+
+    pub async fn get_epoch_info(&self) -> (String, String, f32) {
+        match self.call(self.client.get_epoch_info()).await {
+            Ok(value) => {
+                let epoch_num = value.epoch.to_string();
+                let remaining_slots = value.slots_in_epoch - value.slot_index;
+                let average_time_in_ms = self
+                    .get_average_slot_time_ms()
+                    .await
+                    .unwrap_or(DEFAULT_SLOT_TIME_MS);
+                (
+                    epoch_num,
+                    humantime::format_duration(
+                        Duration::from_secs(remaining_slots * average_time_in_ms) / 1000,
                     )
-                }
-                Err(_) => (String::from(""), String::from(""), 0.),
-            };
+                    .to_string(),
+                    remaining_slots as f32 / value.slots_in_epoch as f32,
+                )
+            }
+            Err(_) => (String::from(""), String::from(""), 0.),
         }
-        (String::from(""), String::from(""), 0.)
+    }
+
+    async fn get_contact_info(&self, identity: &Pubkey) -> Option<RpcContactInfo> {
+        self.call(self.client.get_cluster_nodes())
+            .await
+            .ok()
+            .unwrap_or_default()
+            .into_iter()
+            .find(|node| node.pubkey == identity.to_string())
     }
     /*
     pub fn get_stakes(&self) -> f64 {
@@ -383,11 +714,22 @@ impl Client {
     */
 }
 
-fn get_contact_info(rpc_client: &RpcClient, identity: &Pubkey) -> Option<RpcContactInfo> {
-    rpc_client
-        .get_cluster_nodes()
-        .ok()
-        .unwrap_or_default()
-        .into_iter()
-        .find(|node| node.pubkey == identity.to_string())
+fn block_production_range(range: Option<(u64, u64)>) -> Option<RpcBlockProductionConfigRange> {
+    range.map(|(first_slot, last_slot)| RpcBlockProductionConfigRange {
+        first_slot,
+        last_slot: Some(last_slot),
+    })
+}
+
+/// Place and credits of `identity` in credits sorted by [`Client::get_epoch_credits`].
+pub fn credits_place(credits: &[(String, u64)], identity: &str) -> (usize, u64) {
+    match credits.iter().position(|c| c.0 == identity) {
+        None => (0, 0),
+        Some(index) => (index + 1, credits[index].1),
+    }
+}
+
+/// Logs an RPC failure; transport errors carry the RPC URL, which may embed an API key.
+fn log_error(kind: &ClientErrorKind) {
+    tracing::error!("{}", redact(&format!("{:?}", kind)));
 }
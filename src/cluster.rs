@@ -0,0 +1,125 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::OnceCell;
+
+use crate::client::{credits_place, Client, RpcLimits};
+use crate::settings::secret::redact;
+use crate::settings::{ClusterSettings, Validator};
+
+/// Numbers that describe the whole cluster rather than a single validator.
+#[derive(Debug, Clone, Default)]
+pub struct ClusterStats {
+    pub skip_rate: f64,
+    credits: Vec<(String, u64)>,
+}
+
+impl ClusterStats {
+    /// Collects the current epoch, or the finished `epoch` when given.
+    pub async fn collect(client: &Client, epoch: Option<u64>) -> Self {
+        let range = match epoch {
+            Some(epoch) => client.get_epoch_slot_range(epoch).await,
+            None => None,
+        };
+        let (skip_rate, credits) = tokio::join!(
+            client.get_stake_weighted_skip_rate_in_range(range),
+            client.get_epoch_credits(epoch)
+        );
+        ClusterStats {
+            skip_rate: skip_rate.1,
+            credits,
+        }
+    }
+
+    /// Place and credits of the validator with `identity` in the cluster.
+    pub fn credits_and_place(&self, identity: &str) -> (usize, u64) {
+        credits_place(&self.credits, identity)
+    }
+}
+
+/// Stats of one cluster for one epoch, filled by the first lookup.
+type StatsCell = Arc<OnceCell<Arc<ClusterStats>>>;
+
+struct CachedStats {
+    created: Instant,
+    cell: StatsCell,
+}
+
+/// Fetches cluster-wide data once per cluster instead of once per validator.
+/// Validators without a cluster use their own RPC as the reference. Concurrent
+/// lookups of the same cluster wait for a single fetch.
+pub struct ClusterCache {
+    clusters: BTreeMap<String, ClusterSettings>,
+    limits: RpcLimits,
+    /// Age after which the stats of the current epoch are fetched again, kept forever if unset.
+    ttl: Option<Duration>,
+    stats: Mutex<HashMap<(String, Option<u64>), CachedStats>>,
+}
+
+impl ClusterCache {
+    pub fn new(clusters: &BTreeMap<String, ClusterSettings>, limits: &RpcLimits) -> Self {
+        ClusterCache {
+            clusters: clusters.clone(),
+            limits: limits.clone(),
+            ttl: None,
+            stats: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// A cache that fetches the stats of the current epoch again once they are `ttl` old,
+    /// for a checker that lives across many runs. Stats of a finished epoch don't change.
+    pub fn with_ttl(
+        clusters: &BTreeMap<String, ClusterSettings>,
+        limits: &RpcLimits,
+        ttl: Duration,
+    ) -> Self {
+        ClusterCache {
+            ttl: Some(ttl),
+            ..ClusterCache::new(clusters, limits)
+        }
+    }
+
+    fn reference_rpc(&self, validator: &Validator) -> String {
+        validator
+            .cluster
+            .as_ref()
+            .and_then(|cluster| self.clusters.get(cluster))
+            .map_or_else(|| validator.rpc.clone(), |cluster| cluster.rpc.clone())
+    }
+
+    /// Stats of the cluster `validator` belongs to, for the current epoch or a finished `epoch`.
+    pub async fn get(&self, validator: &Validator, epoch: Option<u64>) -> Arc<ClusterStats> {
+        let rpc = self.reference_rpc(validator);
+        let cell = {
+            let mut stats = self.stats.lock().unwrap();
+            let cached = stats
+                .entry((rpc.clone(), epoch))
+                .or_insert_with(|| CachedStats {
+                    created: Instant::now(),
+                    cell: StatsCell::default(),
+                });
+            let expired =
+                epoch.is_none() && self.ttl.is_some_and(|ttl| cached.created.elapsed() >= ttl);
+            if expired {
+                *cached = CachedStats {
+                    created: Instant::now(),
+                    cell: StatsCell::default(),
+                };
+            }
+            cached.cell.clone()
+        };
+        cell.get_or_init(|| async {
+            tracing::info!("Collect cluster stats from {}", redact(&rpc));
+            let client = Client::new(
+                &Validator {
+                    rpc: rpc.clone(),
+                    ..validator.clone()
+                },
+                &self.limits,
+            );
+            Arc::new(ClusterStats::collect(&client, epoch).await)
+        })
+        .await
+        .clone()
+    }
+}
@@ -0,0 +1,208 @@
+use crate::report::ValidatorReport;
+use crate::settings::Validator;
+use crate::status::AlertKind;
+use crate::storage::Metric;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast::{self, error::RecvError};
+
+/// Events a subscriber may fall behind by before it misses some.
+const CAPACITY: usize = 1024;
+
+/// What a check observed about a validator. Checkers only publish events, the
+/// outputs (notifier, history store, alert status, log) subscribe to the
+/// [`EventBus`] and decide what to do with them.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    /// Metric samples of a validator, for the history.
+    Measured {
+        validator: String,
+        samples: Vec<(Metric, f64)>,
+    },
+    /// Outcome of a delinquency check, `None` when the RPC did not answer.
+    DelinquencyChecked {
+        validator: String,
+        delinquent: Option<bool>,
+        slot_lag: Option<u64>,
+    },
+    /// Both balances of a validator were read.
+    BalanceChecked {
+        validator: String,
+        identity: f64,
+        vote: f64,
+    },
+    /// The full report of a validator was collected.
+    ReportCollected(Box<ValidatorReport>),
+    Delinquent(Validator),
+    BalanceChanged {
+        validator: Validator,
+        account: Account,
+        previous: f64,
+        current: f64,
+    },
+    SkipRateCritical {
+        validator: Validator,
+        skip_rate: f64,
+        cluster_skip_rate: f64,
+    },
+    LowBalance {
+        validator: Validator,
+        balance: f64,
+        minimum: f64,
+    },
+    RewardsBelowTrend {
+        validator: Validator,
+        epoch: u64,
+        apy: f64,
+        trend_apy: f64,
+        income: f64,
+        trend_income: f64,
+    },
+    /// The condition behind an active alert is gone.
+    Recovered {
+        validator: String,
+        kind: AlertKind,
+    },
+    /// A report for the report chat, in Telegram HTML.
+    ReportReady(String),
+    /// A chart for the report chat.
+    ChartReady {
+        caption: String,
+        png: Vec<u8>,
+    },
+    /// The bot started watching `validators` validators.
+    BotStarted {
+        validators: usize,
+    },
+    /// The bot received a signal and is shutting down.
+    BotStopping,
+    /// Periodic sign of life of the bot.
+    StillAlive {
+        validators: usize,
+        active_alerts: usize,
+        uptime: Duration,
+    },
+    /// The watched peer instance did not answer for `silent_for`.
+    PeerSilent {
+        peer: String,
+        silent_for: Duration,
+    },
+    /// This instance took over notifying, or handed it over to another one.
+    LeadershipChanged {
+        instance: String,
+        active: bool,
+    },
+}
+
+/// The account of a validator whose balance is watched.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Account {
+    Identity,
+    Vote,
+}
+
+/// An output of the bot. Every subscriber gets every event, in order, on a
+/// thread of its own, so a slow output never delays the checks or the others.
+pub trait Subscriber: Send + Sync + 'static {
+    /// Name of the subscriber thread and in logs.
+    fn name(&self) -> &'static str;
+
+    fn handle(&self, event: &Event);
+}
+
+/// Broadcasts the events of the checkers to the subscribed outputs.
+pub struct EventBus {
+    sender: broadcast::Sender<(u64, Arc<Event>)>,
+    /// Sequence number of the last published event.
+    published: Mutex<u64>,
+    progress: Arc<Progress>,
+}
+
+/// Sequence number of the last event handled by each subscriber.
+#[derive(Default)]
+struct Progress {
+    handled: Mutex<Vec<u64>>,
+    changed: Condvar,
+}
+
+impl Progress {
+    fn set(&self, index: usize, sequence: u64) {
+        self.handled.lock().unwrap()[index] = sequence;
+        self.changed.notify_all();
+    }
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        EventBus {
+            sender: broadcast::channel(CAPACITY).0,
+            published: Mutex::new(0),
+            progress: Arc::default(),
+        }
+    }
+}
+
+impl EventBus {
+    /// Hands `event` to every subscriber. Events published before anyone subscribes are lost.
+    pub fn publish(&self, event: Event) {
+        let mut published = self.published.lock().unwrap();
+        *published += 1;
+        // Fails only when nobody subscribed.
+        let _ = self.sender.send((*published, Arc::new(event)));
+    }
+
+    /// Starts delivering the events published from now on to `subscriber`.
+    pub fn subscribe(&self, subscriber: Arc<dyn Subscriber>) {
+        let mut receiver = self.sender.subscribe();
+        let index = {
+            let published = self.published.lock().unwrap();
+            let mut handled = self.progress.handled.lock().unwrap();
+            handled.push(*published);
+            handled.len() - 1
+        };
+        let progress = self.progress.clone();
+        let name = subscriber.name();
+        let spawned = thread::Builder::new()
+            .name(name.to_string())
+            .spawn(move || loop {
+                match receiver.blocking_recv() {
+                    Ok((sequence, event)) => {
+                        let handled =
+                            panic::catch_unwind(AssertUnwindSafe(|| subscriber.handle(&event)));
+                        if handled.is_err() {
+                            tracing::error!("{} failed to handle {:?}", name, event);
+                        }
+                        progress.set(index, sequence);
+                    }
+                    Err(RecvError::Lagged(missed)) => {
+                        tracing::warn!("{} fell behind and missed {} events", name, missed);
+                    }
+                    Err(RecvError::Closed) => {
+                        progress.set(index, u64::MAX);
+                        return;
+                    }
+                }
+            });
+        if let Err(e) = spawned {
+            tracing::error!("Failed to start {}: {}", name, e);
+            self.progress.set(index, u64::MAX);
+        }
+    }
+
+    /// Waits until every subscriber has handled the events published so far.
+    /// Returns false on timeout.
+    pub fn settle(&self, timeout: Duration) -> bool {
+        let target = *self.published.lock().unwrap();
+        let deadline = Instant::now() + timeout;
+        let mut handled = self.progress.handled.lock().unwrap();
+        while handled.iter().any(|sequence| *sequence < target) {
+            let Some(left) = deadline.checked_duration_since(Instant::now()) else {
+                return false;
+            };
+            handled = self.progress.changed.wait_timeout(handled, left).unwrap().0;
+        }
+        true
+    }
+}
@@ -0,0 +1,237 @@
+use crate::events::{Event, Subscriber};
+use crate::scheduler::Shutdown;
+use crate::settings::HaSettings;
+use crate::state::SharedState;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Renewals per lease, so a single slow renewal doesn't cost the lease.
+const RENEWALS_PER_LEASE: u32 = 3;
+
+/// Whether this instance is the one that notifies. Without HA it always is.
+pub struct Leadership {
+    active: AtomicBool,
+}
+
+impl Leadership {
+    pub fn new(active: bool) -> Self {
+        Leadership {
+            active: AtomicBool::new(active),
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active.load(Ordering::SeqCst)
+    }
+
+    /// Returns whether the role changed.
+    pub fn set(&self, active: bool) -> bool {
+        self.active.swap(active, Ordering::SeqCst) != active
+    }
+}
+
+/// Passes events on to `S` only while this instance is active, so a standby keeps
+/// checking and recording but stays silent.
+pub struct ActiveOnly<S> {
+    leadership: Arc<Leadership>,
+    subscriber: S,
+}
+
+impl<S: Subscriber> ActiveOnly<S> {
+    pub fn new(leadership: &Arc<Leadership>, subscriber: S) -> Self {
+        ActiveOnly {
+            leadership: leadership.clone(),
+            subscriber,
+        }
+    }
+}
+
+impl<S: Subscriber> Subscriber for ActiveOnly<S> {
+    fn name(&self) -> &'static str {
+        self.subscriber.name()
+    }
+
+    fn handle(&self, event: &Event) {
+        if self.leadership.is_active() {
+            self.subscriber.handle(event);
+        }
+    }
+}
+
+/// The lease in the lock file.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Lease {
+    holder: String,
+    #[serde(with = "chrono::serde::ts_milliseconds")]
+    expires_at: DateTime<Utc>,
+}
+
+/// A lease shared by the instances through a file on a common filesystem. The
+/// holder renews it; once it expires or is released, any instance may take it.
+/// Every read and write of the lease happens under an exclusive lock of the file,
+/// so two instances never both take an expired lease.
+pub struct LockFile {
+    path: PathBuf,
+    instance: String,
+    lease: Duration,
+}
+
+impl LockFile {
+    pub fn new(settings: &HaSettings) -> Self {
+        LockFile {
+            path: PathBuf::from(&settings.lock_path),
+            instance: settings
+                .instance
+                .clone()
+                .unwrap_or_else(|| gethostname::gethostname().to_string_lossy().into_owned()),
+            lease: settings.lease,
+        }
+    }
+
+    pub fn instance(&self) -> &str {
+        &self.instance
+    }
+
+    /// Takes or renews the lease. Returns whether this instance holds it.
+    pub fn acquire(&self) -> bool {
+        let Ok(lease) = chrono::Duration::from_std(self.lease) else {
+            return false;
+        };
+        let result = self.locked(|file| {
+            let now = Utc::now();
+            if let Some(lease) = read(file, &self.path) {
+                if lease.holder != self.instance && lease.expires_at > now {
+                    return Ok(false);
+                }
+            }
+            write(
+                file,
+                Some(&Lease {
+                    holder: self.instance.clone(),
+                    expires_at: now + lease,
+                }),
+            )?;
+            Ok(true)
+        });
+        result.unwrap_or_else(|e| {
+            tracing::error!("Failed to take lock file {:?}: {}", self.path, e);
+            false
+        })
+    }
+
+    /// Whether this instance holds an unexpired lease, checked before it starts to notify.
+    pub fn holds(&self) -> bool {
+        let now = Utc::now();
+        self.locked(|file| {
+            Ok(read(file, &self.path)
+                .is_some_and(|lease| lease.holder == self.instance && lease.expires_at > now))
+        })
+        .unwrap_or(false)
+    }
+
+    /// Gives the lease up if this instance holds it, so a standby takes over
+    /// without waiting for it to expire.
+    pub fn release(&self) {
+        let result = self.locked(|file| {
+            if read(file, &self.path).is_some_and(|lease| lease.holder == self.instance) {
+                write(file, None)?;
+            }
+            Ok(())
+        });
+        if let Err(e) = result {
+            tracing::error!("Failed to release lock file {:?}: {}", self.path, e);
+        }
+    }
+
+    /// Runs `f` on the lock file while holding its exclusive lock. The file is never
+    /// replaced or removed, so every instance locks the same one.
+    fn locked<T>(&self, f: impl FnOnce(&mut File) -> io::Result<T>) -> io::Result<T> {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&self.path)?;
+        file.lock()?;
+        let result = f(&mut file);
+        file.unlock()?;
+        result
+    }
+}
+
+/// The lease in `file`, `None` when it is released.
+fn read(file: &mut File, path: &Path) -> Option<Lease> {
+    let mut json = String::new();
+    if let Err(e) = file
+        .seek(SeekFrom::Start(0))
+        .and_then(|_| file.read_to_string(&mut json))
+    {
+        tracing::warn!("Failed to read lock file {:?}: {}", path, e);
+        return None;
+    }
+    if json.trim().is_empty() {
+        return None;
+    }
+    match serde_json::from_str(&json) {
+        Ok(lease) => Some(lease),
+        Err(e) => {
+            tracing::warn!("Ignoring unreadable lock file {:?}: {}", path, e);
+            None
+        }
+    }
+}
+
+/// Replaces the lease in `file`, or empties it to release the lease.
+fn write(file: &mut File, lease: Option<&Lease>) -> io::Result<()> {
+    let json = match lease {
+        Some(lease) => serde_json::to_vec(lease).map_err(io::Error::from)?,
+        None => Vec::new(),
+    };
+    file.set_len(0)?;
+    file.seek(SeekFrom::Start(0))?;
+    file.write_all(&json)?;
+    file.sync_data()
+}
+
+/// Takes or renews the lease and returns whether this instance is to be active. A
+/// standby re-reads the lease before it starts to notify, so it never acts on a
+/// lease another instance took in between.
+pub fn elect(lock: &LockFile, active: bool) -> bool {
+    lock.acquire() && (active || lock.holds())
+}
+
+/// Renews or takes the lease until shutdown and switches this instance between
+/// active and standby.
+pub async fn run(lock: Arc<LockFile>, state: SharedState, mut shutdown: Shutdown) {
+    tracing::info!("Start leader election as {}", lock.instance());
+    let renewal = lock.lease / RENEWALS_PER_LEASE;
+    let _guard = state.status.register_checker("ha", Some(renewal));
+    let mut ticks = tokio::time::interval(renewal);
+    loop {
+        tokio::select! {
+            _ = ticks.tick() => {}
+            _ = shutdown.requested() => return,
+        }
+        let active = {
+            let lock = lock.clone();
+            let active = state.leadership.is_active();
+            tokio::task::spawn_blocking(move || elect(&lock, active))
+                .await
+                .unwrap_or(false)
+        };
+        if state.leadership.set(active) {
+            state.bus.publish(Event::LeadershipChanged {
+                instance: lock.instance().to_string(),
+                active,
+            });
+        }
+        state.status.checker_succeeded("ha");
+    }
+}
@@ -0,0 +1,148 @@
+use crate::events::Event;
+use crate::scheduler::Shutdown;
+use crate::settings::secret::{redact, Secret};
+use crate::settings::{HeartbeatSettings, PeerSettings};
+use crate::state::SharedState;
+use crate::status::AlertKind;
+use std::future::Future;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Time a single ping or peer poll may take.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Sends the heartbeats until shutdown: pings `pingUrl`, posts the "still alive"
+/// digest and watches the peer instance.
+pub fn run(
+    settings: &HeartbeatSettings,
+    validators: usize,
+    state: SharedState,
+    mut shutdown: Shutdown,
+) -> impl Future<Output = ()> {
+    let settings = settings.clone();
+    async move {
+        tracing::info!("Start heartbeat task");
+        let _guard = state
+            .status
+            .register_checker("heartbeat", Some(settings.interval));
+        let started = Instant::now();
+        let mut next_digest = settings.digest.map(|digest| started + digest);
+        let mut peer = settings.peer.as_ref().map(PeerWatch::new);
+        let mut ticks = tokio::time::interval(settings.interval);
+        loop {
+            tokio::select! {
+                _ = ticks.tick() => {}
+                _ = shutdown.requested() => return,
+            }
+            if let Some(ping_url) = &settings.ping_url {
+                ping(ping_url, &state).await;
+            }
+            if let Some(peer) = &mut peer {
+                if let Some(event) = peer.poll().await {
+                    state.bus.publish(event);
+                }
+            }
+            if let (Some(at), Some(digest)) = (next_digest, settings.digest) {
+                if Instant::now() >= at {
+                    state.bus.publish(Event::StillAlive {
+                        validators,
+                        active_alerts: state.status.active_alerts().len(),
+                        uptime: started.elapsed(),
+                    });
+                    next_digest = Some(at + digest);
+                }
+            }
+            state.status.checker_succeeded("heartbeat");
+        }
+    }
+}
+
+/// Pings the dead man's switch, unless a checker is stuck: a bot that no longer
+/// checks anything should go quiet as well.
+async fn ping(url: &Secret, state: &SharedState) {
+    let now = chrono::Utc::now();
+    let stuck: Vec<String> = state
+        .status
+        .checkers()
+        .into_iter()
+        .filter(|(_, checker)| checker.is_stuck(now))
+        .map(|(name, _)| name)
+        .collect();
+    if !stuck.is_empty() {
+        tracing::warn!("Skip heartbeat ping, stuck checkers: {}", stuck.join(", "));
+        return;
+    }
+    let url = url.expose().to_string();
+    let result = tokio::task::spawn_blocking(move || {
+        ureq::get(&url)
+            .timeout(REQUEST_TIMEOUT)
+            .call()
+            .map(|_| ())
+            .map_err(|e| redact(&e.to_string()))
+    })
+    .await;
+    match result {
+        Ok(Ok(())) => tracing::trace!("Heartbeat ping sent"),
+        Ok(Err(e)) => tracing::error!("Failed to send heartbeat ping: {}", e),
+        Err(e) => tracing::error!("Heartbeat ping panicked: {}", e),
+    }
+}
+
+/// Polls the HTTP API of another instance and reports when it goes quiet.
+struct PeerWatch {
+    settings: PeerSettings,
+    last_seen: Instant,
+    silent: bool,
+}
+
+impl PeerWatch {
+    fn new(settings: &PeerSettings) -> Self {
+        PeerWatch {
+            settings: settings.clone(),
+            last_seen: Instant::now(),
+            silent: false,
+        }
+    }
+
+    /// The event to publish when the peer went quiet or came back.
+    async fn poll(&mut self) -> Option<Event> {
+        let now = Instant::now();
+        if is_reachable(&self.settings.url).await {
+            self.last_seen = now;
+            if self.silent {
+                self.silent = false;
+                return Some(Event::Recovered {
+                    validator: self.settings.url.clone(),
+                    kind: AlertKind::PeerSilent,
+                });
+            }
+            return None;
+        }
+        let silent_for = now - self.last_seen;
+        if !self.silent && silent_for >= self.settings.timeout {
+            self.silent = true;
+            return Some(Event::PeerSilent {
+                peer: self.settings.url.clone(),
+                silent_for,
+            });
+        }
+        None
+    }
+}
+
+/// Whether anything answers at `url`. A peer answering with an error status is
+/// alive, its own alerts cover what is wrong with it.
+async fn is_reachable(url: &str) -> bool {
+    let url = url.to_string();
+    tokio::task::spawn_blocking(
+        move || match ureq::get(&url).timeout(REQUEST_TIMEOUT).call() {
+            Ok(_) | Err(ureq::Error::Status(..)) => true,
+            Err(e) => {
+                tracing::debug!("Peer {} unreachable: {}", url, e);
+                false
+            }
+        },
+    )
+    .await
+    .unwrap_or(false)
+}
@@ -0,0 +1,186 @@
+use chrono::Utc;
+use tiny_http::Request;
+
+use crate::client::Client;
+use crate::http::{respond, HttpContext};
+use crate::message::escape;
+use crate::report::chart::ChartKind;
+use crate::report::render;
+use crate::settings::cluster_label;
+use crate::status::{Alert, AlertKind, ValidatorSnapshot};
+
+const STYLE: &str = "body{font-family:sans-serif;margin:2em;color:#222}\
+table{border-collapse:collapse;margin-bottom:2em}\
+th,td{border:1px solid #ccc;padding:4px 10px;text-align:left}\
+th{background:#f3f3f3}\
+pre{background:#f8f8f8;padding:1em;display:inline-block}\
+img{display:block;margin:1em 0;border:1px solid #ddd}\
+a{color:#0645ad;text-decoration:none}";
+
+fn encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|byte| {
+            if byte.is_ascii_alphanumeric() || b"-_.~".contains(&byte) {
+                (byte as char).to_string()
+            } else {
+                format!("%{:02X}", byte)
+            }
+        })
+        .collect()
+}
+
+fn page(title: &str, body: &str) -> Vec<u8> {
+    format!(
+        "<!DOCTYPE html><html><head><meta charset=\"utf-8\">\
+         <meta http-equiv=\"refresh\" content=\"60\">\
+         <title>{}</title><style>{}</style></head><body>{}</body></html>",
+        escape(title),
+        STYLE,
+        body
+    )
+    .into_bytes()
+}
+
+fn status_emoji(snapshot: Option<&ValidatorSnapshot>, alerts: &[Alert]) -> &'static str {
+    let failing = alerts.iter().any(|alert| {
+        matches!(
+            alert.kind,
+            AlertKind::Delinquent | AlertKind::CriticalSkipRate | AlertKind::LowBalance
+        )
+    });
+    match snapshot {
+        None => "⚪",
+        Some(snapshot) if failing || snapshot.delinquent == Some(true) => "🔴",
+        Some(_) => "🟢",
+    }
+}
+
+fn format_value<T: ToString>(value: Option<T>) -> String {
+    value
+        .map(|value| value.to_string())
+        .unwrap_or_else(|| "-".to_string())
+}
+
+fn alerts_table(alerts: &[Alert]) -> String {
+    if alerts.is_empty() {
+        return "<p>No active alerts</p>".to_string();
+    }
+    let mut table = String::from(
+        "<table><tr><th>validator</th><th>alert</th><th>message</th><th>since</th></tr>",
+    );
+    for alert in alerts {
+        table.push_str(&format!(
+            "<tr><td>{}</td><td>{:?}</td><td>{}</td><td>{}</td></tr>",
+            escape(&alert.validator),
+            alert.kind,
+            escape(&alert.message),
+            alert.raised_at.format("%Y-%m-%d %H:%M:%S UTC")
+        ));
+    }
+    table.push_str("</table>");
+    table
+}
+
+pub fn index(request: Request, context: &HttpContext) {
+    let status = &context.state.status;
+    let active_alerts = status.active_alerts();
+    let mut body = String::from("<h1>Validators</h1><table><tr><th></th><th>name</th><th>identity balance</th><th>vote balance</th><th>skip %</th><th>cluster %</th><th>place</th><th>slot lag</th><th>updated</th></tr>");
+    for node in &context.nodes {
+        let name = &node.validator.name;
+        let snapshot = status.validator(name);
+        let alerts: Vec<Alert> = active_alerts
+            .iter()
+            .filter(|alert| &alert.validator == name)
+            .cloned()
+            .collect();
+        let report = snapshot
+            .as_ref()
+            .and_then(|snapshot| snapshot.report.as_ref());
+        body.push_str(&format!(
+            "<tr><td>{}</td><td><a href=\"/validator/{}\">{}</a></td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+            status_emoji(snapshot.as_ref(), &alerts),
+            encode(name),
+            escape(&node.validator.label()),
+            format_value(snapshot.as_ref().and_then(|s| s.identity_balance).map(|v| format!("{:.3}", v))),
+            format_value(snapshot.as_ref().and_then(|s| s.vote_balance).map(|v| format!("{:.3}", v))),
+            format_value(report.map(|r| format!("{:.2}", r.skip_rate.skip_rate))),
+            format_value(report.map(|r| format!("{:.2}", r.skip_rate.cluster_skip_rate))),
+            format_value(report.map(|r| r.credits.rank)),
+            format_value(snapshot.as_ref().and_then(|s| s.slot_lag)),
+            format_value(snapshot.as_ref().and_then(|s| s.updated_at).map(|t| t.format("%H:%M:%S").to_string())),
+        ));
+    }
+    body.push_str("</table><h2>Active alerts</h2>");
+    body.push_str(&alerts_table(&active_alerts));
+    respond(
+        request,
+        200,
+        "text/html; charset=utf-8",
+        page("Validators", &body),
+    );
+}
+
+pub fn validator(request: Request, context: &HttpContext, name: &str) {
+    if !context.nodes.iter().any(|node| node.validator.name == name) {
+        respond(request, 404, "text/plain", b"unknown validator".to_vec());
+        return;
+    }
+    let status = &context.state.status;
+    let snapshot = status.validator(name);
+    let alerts: Vec<Alert> = status
+        .active_alerts()
+        .into_iter()
+        .filter(|alert| alert.validator == name)
+        .collect();
+    let mut body = format!(
+        "<p><a href=\"/\">&larr; all validators</a></p><h1>{} {}</h1>",
+        status_emoji(snapshot.as_ref(), &alerts),
+        escape(&cluster_label(
+            name,
+            snapshot.as_ref().and_then(|s| s.cluster.as_deref())
+        ))
+    );
+    match snapshot
+        .as_ref()
+        .and_then(|snapshot| snapshot.report.as_ref())
+    {
+        Some(report) => body.push_str(&format!("<pre>{}</pre>", escape(&render::text(report)))),
+        None => body.push_str("<p>No report collected yet</p>"),
+    }
+    body.push_str("<h2>Active alerts</h2>");
+    body.push_str(&alerts_table(&alerts));
+    body.push_str("<h2>History</h2>");
+    for kind in ChartKind::ALL {
+        body.push_str(&format!(
+            "<img src=\"/validator/{}/chart/{}.png\" alt=\"{}\">",
+            encode(name),
+            kind.slug(),
+            kind.slug()
+        ));
+    }
+    respond(request, 200, "text/html; charset=utf-8", page(name, &body));
+}
+
+pub fn chart(request: Request, context: &HttpContext, name: &str, file: &str) {
+    let node = context
+        .nodes
+        .iter()
+        .find(|node| node.validator.name == name);
+    let kind = file.strip_suffix(".png").and_then(ChartKind::from_slug);
+    let (Some(node), Some(kind)) = (node, kind) else {
+        respond(request, 404, "text/plain", b"not found".to_vec());
+        return;
+    };
+    let epoch_start = match kind {
+        ChartKind::SkipRate => context
+            .runtime
+            .block_on(Client::new(&node.validator, &context.state.rpc).get_epoch_start_time(0)),
+        _ => None,
+    }
+    .unwrap_or_else(|| Utc::now() - chrono::Duration::days(2));
+    match kind.render(&context.state.store, name, epoch_start) {
+        Some((_, png)) => respond(request, 200, "image/png", png),
+        None => respond(request, 404, "text/plain", b"no data".to_vec()),
+    }
+}
@@ -0,0 +1,202 @@
+use serde::Serialize;
+use serde_json::json;
+use std::sync::mpsc::{self, SyncSender, TrySendError};
+use std::sync::Arc;
+use std::thread;
+use std::thread::JoinHandle;
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use crate::settings::{HttpSettings, NodeCheckSettings};
+use crate::state::SharedState;
+
+mod dashboard;
+
+/// Chart requests waiting for the chart thread; more are turned away with a 503.
+const CHART_QUEUE: usize = 8;
+
+/// A chart request handed from the accept loop to the chart thread.
+struct ChartRequest {
+    request: Request,
+    name: String,
+    file: String,
+}
+
+pub struct HttpContext {
+    pub nodes: Vec<NodeCheckSettings>,
+    pub state: SharedState,
+    pub dashboard: bool,
+    /// Runtime of the checkers, used to make RPC calls from the server thread.
+    pub runtime: tokio::runtime::Handle,
+}
+
+/// The running HTTP server threads: the accept loop answering the API and the pages,
+/// and the chart thread rendering charts, whose history reads and RPC calls are slow.
+pub struct HttpServer {
+    server: Arc<Server>,
+    thread: JoinHandle<()>,
+    charts: JoinHandle<()>,
+}
+
+impl HttpServer {
+    /// Stops accepting requests and waits for the requests in progress.
+    pub fn stop(self) {
+        self.server.unblock();
+        if self.thread.join().is_err() {
+            tracing::error!("HTTP server thread panicked");
+        }
+        // The accept loop dropped the chart queue, so the chart thread ends with it.
+        if self.charts.join().is_err() {
+            tracing::error!("HTTP chart thread panicked");
+        }
+    }
+}
+
+pub fn run(
+    settings: &HttpSettings,
+    nodes: &[NodeCheckSettings],
+    state: SharedState,
+) -> Option<HttpServer> {
+    let server = match Server::http(&settings.listen) {
+        Ok(server) => Arc::new(server),
+        Err(e) => {
+            tracing::error!("Failed to start HTTP server on {}: {}", settings.listen, e);
+            return None;
+        }
+    };
+    tracing::info!("HTTP server listening on {}", settings.listen);
+    let context = Arc::new(HttpContext {
+        nodes: nodes.to_vec(),
+        state,
+        dashboard: settings.dashboard,
+        runtime: tokio::runtime::Handle::current(),
+    });
+    let (queue, chart_requests) = mpsc::sync_channel::<ChartRequest>(CHART_QUEUE);
+    let charts = {
+        let context = context.clone();
+        thread::spawn(move || {
+            for chart in chart_requests {
+                dashboard::chart(chart.request, &context, &chart.name, &chart.file);
+            }
+        })
+    };
+    let thread = {
+        let server = server.clone();
+        thread::spawn(move || {
+            for request in server.incoming_requests() {
+                handle(request, &context, &queue);
+            }
+        })
+    };
+    Some(HttpServer {
+        server,
+        thread,
+        charts,
+    })
+}
+
+fn handle(request: Request, context: &HttpContext, charts: &SyncSender<ChartRequest>) {
+    let status = &context.state.status;
+    tracing::trace!("{} {}", request.method(), request.url());
+    if request.method() != &Method::Get {
+        respond_json(request, 405, &json!({ "error": "method not allowed" }));
+        return;
+    }
+    let path = request
+        .url()
+        .split('?')
+        .next()
+        .unwrap_or_default()
+        .to_string();
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    match segments.as_slice() {
+        [""] if context.dashboard => dashboard::index(request, context),
+        ["validator", name] if context.dashboard => {
+            dashboard::validator(request, context, &decode(name))
+        }
+        ["validator", name, "chart", file] if context.dashboard => {
+            let chart = ChartRequest {
+                request,
+                name: decode(name),
+                file: file.to_string(),
+            };
+            match charts.try_send(chart) {
+                Ok(()) => {}
+                Err(TrySendError::Full(chart)) | Err(TrySendError::Disconnected(chart)) => {
+                    respond(chart.request, 503, "text/plain", b"busy".to_vec())
+                }
+            }
+        }
+        ["health"] => {
+            let now = chrono::Utc::now();
+            let checkers = status.checkers();
+            let healthy = checkers.values().all(|checker| checker.is_alive(now));
+            let body = json!({
+                "status": if healthy { "ok" } else { "degraded" },
+                "active": context.state.leadership.is_active(),
+                "checkers": checkers
+                    .iter()
+                    .map(|(name, checker)| {
+                        (name.clone(), json!({
+                            "alive": checker.is_alive(now),
+                            "status": checker,
+                        }))
+                    })
+                    .collect::<serde_json::Map<_, _>>(),
+            });
+            respond_json(request, if healthy { 200 } else { 503 }, &body);
+        }
+        ["validators"] => respond_json(request, 200, &status.validators()),
+        ["validators", name] => match status.validator(&decode(name)) {
+            Some(snapshot) => respond_json(request, 200, &snapshot),
+            None => respond_json(request, 404, &json!({ "error": "unknown validator" })),
+        },
+        ["alerts"] => respond_json(
+            request,
+            200,
+            &json!({
+                "active": status.active_alerts(),
+                "recent": status.recent_alerts(),
+            }),
+        ),
+        _ => respond_json(request, 404, &json!({ "error": "not found" })),
+    }
+}
+
+/// Decodes percent-encoded path segments such as validator names with spaces.
+pub fn decode(segment: &str) -> String {
+    let bytes = segment.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).unwrap_or_default();
+            if let Ok(byte) = u8::from_str_radix(hex, 16) {
+                decoded.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+pub fn respond(request: Request, code: u16, content_type: &str, body: Vec<u8>) {
+    let response = Response::from_data(body)
+        .with_status_code(code)
+        .with_header(Header::from_bytes("Content-Type", content_type).unwrap());
+    if let Err(e) = request.respond(response) {
+        tracing::error!("Failed to send HTTP response: {}", e);
+    }
+}
+
+fn respond_json<T: Serialize>(request: Request, code: u16, body: &T) {
+    match serde_json::to_vec_pretty(body) {
+        Ok(json) => respond(request, code, "application/json", json),
+        Err(e) => {
+            tracing::error!("Failed to serialize HTTP response: {}", e);
+            respond(request, 500, "text/plain", b"internal error".to_vec());
+        }
+    }
+}
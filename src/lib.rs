@@ -0,0 +1,321 @@
+use crate::checkers::balance_check::BalanceCheck;
+use crate::checkers::deliquency_check::DelinquencyCheck;
+use crate::checkers::epoch_check::EpochCheck;
+use crate::checkers::health_check::HealthCheck;
+use crate::checkers::node_stats;
+use crate::events::Event;
+use crate::scheduler::Scheduler;
+use serde_json::{json, Map, Value};
+use ureq::{Error, Response};
+
+use crate::settings::{Settings, SettingsProblem, Telegram};
+use crate::state::SharedState;
+use crate::SolanaBotError::{
+    InvalidSettings, ParseFileError, ParseSettingsError, ReadSettingsError,
+};
+use std::fmt;
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+pub mod checkers;
+pub mod cli;
+pub mod client;
+pub mod cluster;
+pub mod events;
+pub mod ha;
+pub mod heartbeat;
+pub mod http;
+pub mod logger;
+pub mod message;
+pub mod notifier;
+pub mod report;
+pub mod revenue;
+pub mod rewards;
+pub mod scheduler;
+pub mod settings;
+pub mod state;
+pub mod status;
+pub mod storage;
+
+#[derive(Debug)]
+pub enum SolanaBotError {
+    ReadSettingsError(PathBuf, std::io::Error),
+    ParseFileError(PathBuf, String),
+    ParseSettingsError(serde_json::Error),
+    InvalidSettings(Vec<SettingsProblem>),
+}
+
+impl From<serde_json::Error> for SolanaBotError {
+    fn from(value: serde_json::Error) -> Self {
+        ParseSettingsError(value)
+    }
+}
+
+impl fmt::Display for SolanaBotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReadSettingsError(path, e) => write!(f, "failed to read {}: {}", path.display(), e),
+            ParseFileError(path, e) => write!(f, "failed to parse {}: {}", path.display(), e),
+            ParseSettingsError(e) => write!(f, "failed to parse settings: {}", e),
+            InvalidSettings(problems) => {
+                write!(f, "invalid settings:")?;
+                for problem in problems {
+                    write!(f, "\n  {}", problem)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Merges the settings layers in order, applies environment overrides and resolves secrets.
+pub fn read_settings(layers: &[PathBuf]) -> Result<Settings, SolanaBotError> {
+    let mut document = Value::Object(Map::new());
+    for layer in layers {
+        settings::loader::merge(&mut document, settings::loader::read(layer)?);
+    }
+    settings::loader::apply_env(&mut document, std::env::vars());
+    settings::secret::resolve(&mut document).map_err(InvalidSettings)?;
+    let mut settings: Settings = match serde_json::from_value(document) {
+        Ok(value) => value,
+        Err(e) => return Err(ParseSettingsError(e)),
+    };
+    settings.validate().map_err(InvalidSettings)?;
+    settings.apply_defaults();
+    Ok(settings)
+}
+
+/// Starts the HTTP server and every checker task, then runs until SIGTERM or SIGINT.
+pub async fn run(settings: Settings) {
+    run_until(settings, shutdown_signal()).await
+}
+
+/// Runs the bot until `stop` completes. On shutdown, checks in progress get
+/// `shutdown.timeout` to finish, then queued events and messages get as long
+/// again to be delivered.
+pub async fn run_until(settings: Settings, stop: impl Future<Output = ()>) {
+    let state = SharedState::new(&settings);
+    let http_server = settings
+        .http
+        .as_ref()
+        .and_then(|http_settings| http::run(http_settings, &settings.nodes, state.clone()));
+    let mut scheduler = Scheduler::new(&settings.nodes, &state);
+    scheduler.register(Arc::new(DelinquencyCheck));
+    scheduler.register(Arc::new(BalanceCheck::default()));
+    scheduler.register(Arc::new(EpochCheck::new(&settings, &state.rpc)));
+    scheduler.register(Arc::new(HealthCheck::new(&settings, &state.rpc)));
+    scheduler.spawn("node_stats", |shutdown| {
+        node_stats::run(&settings, state.clone(), shutdown)
+    });
+    let lock = settings
+        .ha
+        .as_ref()
+        .map(|ha| Arc::new(ha::LockFile::new(ha)));
+    if let Some(lock) = &lock {
+        let active = tokio::task::spawn_blocking({
+            let lock = lock.clone();
+            move || ha::elect(&lock, false)
+        })
+        .await
+        .unwrap_or(false);
+        tracing::info!(
+            "Instance {} starts as {}",
+            lock.instance(),
+            if active { "active" } else { "standby" }
+        );
+        state.leadership.set(active);
+        scheduler.spawn("ha", |shutdown| {
+            ha::run(lock.clone(), state.clone(), shutdown)
+        });
+    }
+    if let Some(heartbeat) = &settings.heartbeat {
+        scheduler.spawn("heartbeat", |shutdown| {
+            heartbeat::run(heartbeat, settings.nodes.len(), state.clone(), shutdown)
+        });
+    }
+    state.bus.publish(Event::BotStarted {
+        validators: settings.nodes.len(),
+    });
+
+    let timeout = settings.shutdown.timeout;
+    scheduler.run_until(stop, timeout).await;
+    state.bus.publish(Event::BotStopping);
+    if let Some(http_server) = http_server {
+        tokio::task::spawn_blocking(move || http_server.stop())
+            .await
+            .expect("");
+    }
+    let flushed = tokio::task::spawn_blocking(move || {
+        let flushed = state.bus.settle(timeout) && state.notifier.flush(timeout);
+        state.store.flush();
+        // Only now, so the standby doesn't take over while this instance still notifies.
+        if let Some(lock) = lock {
+            lock.release();
+        }
+        flushed
+    })
+    .await
+    .expect("");
+    if !flushed {
+        tracing::warn!(
+            "Messages still queued after {:?}, undelivered alerts stay in the spool",
+            timeout
+        );
+    }
+    tracing::info!("Bot stopped");
+}
+
+/// Completes on SIGINT (Ctrl-C) or, on Unix, SIGTERM.
+async fn shutdown_signal() {
+    let interrupt = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            tracing::error!("Failed to listen for SIGINT: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                terminate.recv().await;
+            }
+            Err(e) => {
+                tracing::error!("Failed to listen for SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+    tokio::select! {
+        _ = interrupt => tracing::info!("Received SIGINT"),
+        _ = terminate => tracing::info!("Received SIGTERM"),
+    }
+}
+
+/// A failed Telegram request, with the bot token redacted from the request URL.
+#[derive(Debug)]
+pub struct SendError {
+    /// HTTP status of the Bot API response, `None` for transport errors.
+    pub status: Option<u16>,
+    /// Wait requested by the Bot API before the next request, sent with status 429.
+    pub retry_after: Option<Duration>,
+    message: String,
+}
+
+impl SendError {
+    /// Whether the same request may succeed later: rate limits, server and network errors.
+    pub fn is_transient(&self) -> bool {
+        match self.status {
+            Some(status) => status == 429 || status >= 500,
+            None => true,
+        }
+    }
+}
+
+impl From<Error> for SendError {
+    fn from(value: Error) -> Self {
+        match value {
+            Error::Status(status, response) => {
+                let url = response.get_url().to_string();
+                let body = response.into_string().unwrap_or_default();
+                let json = serde_json::from_str::<Value>(&body).unwrap_or_default();
+                let retry_after = json["parameters"]["retry_after"]
+                    .as_u64()
+                    .map(Duration::from_secs);
+                let description = json["description"]
+                    .as_str()
+                    .map(str::to_string)
+                    .unwrap_or(body);
+                SendError {
+                    status: Some(status),
+                    retry_after,
+                    message: settings::secret::redact(&format!(
+                        "{}: status code {}: {}",
+                        url, status, description
+                    )),
+                }
+            }
+            Error::Transport(_) => SendError {
+                status: None,
+                retry_after: None,
+                message: settings::secret::redact(&value.to_string()),
+            },
+        }
+    }
+}
+
+impl fmt::Display for SendError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+fn bot_url(telegram: &Telegram, method: &str) -> String {
+    format!(
+        "{}/bot{}/{}",
+        telegram.api_url.trim_end_matches('/'),
+        telegram.token.expose(),
+        method
+    )
+}
+
+pub fn send_message(msg: String, telegram: &Telegram, chat_id: i64) -> Result<Response, SendError> {
+    tracing::info!("{}", msg);
+    let parse_mode = telegram.parse_mode(chat_id);
+    let mut request_body = Map::new();
+    request_body.insert("text".to_string(), Value::String(parse_mode.format(&msg)));
+    request_body.insert("chat_id".to_string(), json!(chat_id));
+    request_body.insert(
+        "parse_mode".to_string(),
+        Value::String(parse_mode.as_str().to_string()),
+    );
+
+    ureq::post(&bot_url(telegram, "sendMessage"))
+        .send_json(json!(request_body))
+        .map_err(SendError::from)
+}
+
+pub fn send_photo(
+    png: &[u8],
+    caption: &str,
+    telegram: &Telegram,
+    chat_id: i64,
+) -> Result<Response, SendError> {
+    tracing::info!("Send photo: {}", caption);
+    let boundary = format!(
+        "solana-bot-{}",
+        chrono::Utc::now().timestamp_nanos_opt().unwrap_or_default()
+    );
+    let mut body = Vec::with_capacity(png.len() + 512);
+    for (name, value) in [
+        ("chat_id", chat_id.to_string()),
+        ("caption", caption.to_string()),
+    ] {
+        body.extend_from_slice(
+            format!(
+                "--{boundary}\r\nContent-Disposition: form-data; name=\"{name}\"\r\n\r\n{value}\r\n"
+            )
+            .as_bytes(),
+        );
+    }
+    body.extend_from_slice(
+        format!(
+            "--{boundary}\r\nContent-Disposition: form-data; name=\"photo\"; filename=\"chart.png\"\r\nContent-Type: image/png\r\n\r\n"
+        )
+        .as_bytes(),
+    );
+    body.extend_from_slice(png);
+    body.extend_from_slice(format!("\r\n--{boundary}--\r\n").as_bytes());
+
+    ureq::post(&bot_url(telegram, "sendPhoto"))
+        .set(
+            "Content-Type",
+            &format!("multipart/form-data; boundary={boundary}"),
+        )
+        .send_bytes(&body)
+        .map_err(SendError::from)
+}
@@ -1,3 +1,4 @@
+use crate::events::{Event, Subscriber};
 use tracing::info;
 use tracing::subscriber::set_global_default;
 use tracing_log::LogTracer;
@@ -16,3 +17,91 @@ pub fn setup_logger() {
 
     info!("Logger initialized");
 }
+
+/// Plain logging to stderr for one-shot commands, so stdout carries only their output.
+pub fn setup_cli_logger() {
+    LogTracer::init().expect("Failed to set logger");
+    let env_filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("warn"));
+
+    let subscriber = Registry::default()
+        .with(env_filter)
+        .with(tracing_subscriber::fmt::layer().with_writer(std::io::stderr));
+    set_global_default(subscriber).expect("Failed to set subscriber");
+}
+
+/// Logs the alerts raised by the checkers.
+pub struct EventLog;
+
+impl Subscriber for EventLog {
+    fn name(&self) -> &'static str {
+        "event_log"
+    }
+
+    fn handle(&self, event: &Event) {
+        match event {
+            Event::Delinquent(validator) => {
+                tracing::error!("Validator {} is delinquent", validator.name)
+            }
+            Event::BalanceChanged {
+                validator,
+                account,
+                previous,
+                current,
+            } => info!(
+                "{} {:?} balance: {:.3};{:.3};{:.3}",
+                validator.name,
+                account,
+                previous,
+                current,
+                current - previous
+            ),
+            Event::SkipRateCritical {
+                validator,
+                skip_rate,
+                cluster_skip_rate,
+            } => tracing::warn!(
+                "Validator {} skip rate {:.2}% exceeds cluster {:.2}%",
+                validator.name,
+                skip_rate,
+                cluster_skip_rate
+            ),
+            Event::LowBalance {
+                validator,
+                balance,
+                minimum,
+            } => tracing::warn!(
+                "Validator {} identity balance {:.3} is below {:.3}",
+                validator.name,
+                balance,
+                minimum
+            ),
+            Event::RewardsBelowTrend {
+                validator,
+                epoch,
+                apy,
+                trend_apy,
+                ..
+            } => tracing::warn!(
+                "Validator {} epoch {} APY {:.2}% is below trend {:.2}%",
+                validator.name,
+                epoch,
+                apy,
+                trend_apy
+            ),
+            Event::Recovered { validator, kind } => {
+                info!("Validator {} recovered from {:?}", validator, kind)
+            }
+            Event::BotStarted { validators } => info!("Bot started for {} validators", validators),
+            Event::BotStopping => info!("Bot stopping"),
+            Event::LeadershipChanged { instance, active } => info!(
+                "Instance {} is now {}",
+                instance,
+                if *active { "active" } else { "standby" }
+            ),
+            Event::PeerSilent { peer, silent_for } => {
+                tracing::error!("Peer {} silent for {:?}", peer, silent_for)
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,258 @@
+use serde::{Deserialize, Serialize};
+
+/// Longest text Telegram accepts in one message.
+pub const MAX_MESSAGE_LENGTH: usize = 4096;
+
+/// Markup of the messages sent to a chat. Messages are written in Telegram HTML
+/// and converted when a chat asks for MarkdownV2.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ParseMode {
+    #[default]
+    Html,
+    MarkdownV2,
+}
+
+impl ParseMode {
+    /// Value of the Bot API `parse_mode` parameter.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ParseMode::Html => "HTML",
+            ParseMode::MarkdownV2 => "MarkdownV2",
+        }
+    }
+
+    /// Converts a message written in Telegram HTML to this mode.
+    pub fn format(&self, html: &str) -> String {
+        match self {
+            ParseMode::Html => html.to_string(),
+            ParseMode::MarkdownV2 => to_markdown_v2(html),
+        }
+    }
+}
+
+/// Escapes a value for Telegram HTML, e.g. a validator name in a message.
+pub fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Splits an HTML message into chunks of at most `limit` characters, on line boundaries
+/// where possible. Tags open at a split, such as `<code>`, are closed at the end of the
+/// chunk and opened again at the start of the next one.
+pub fn split(text: &str, limit: usize) -> Vec<String> {
+    if text.chars().count() <= limit {
+        return vec![text.to_string()];
+    }
+    let mut splitter = Splitter::new(limit);
+    for line in text.split_inclusive('\n') {
+        if splitter.push(line) {
+            continue;
+        }
+        for token in tokens(line) {
+            if !splitter.push(token) {
+                // A single tag or character longer than the limit, nothing better to do.
+                splitter.append(token);
+            }
+        }
+    }
+    splitter.finish()
+}
+
+struct Splitter<'a> {
+    limit: usize,
+    chunks: Vec<String>,
+    chunk: String,
+    len: usize,
+    /// Opening tags not closed yet at the end of `chunk`.
+    open: Vec<&'a str>,
+    /// The chunk holds nothing but reopened tags.
+    fresh: bool,
+}
+
+impl<'a> Splitter<'a> {
+    fn new(limit: usize) -> Self {
+        Splitter {
+            limit,
+            chunks: Vec::new(),
+            chunk: String::new(),
+            len: 0,
+            open: Vec::new(),
+            fresh: true,
+        }
+    }
+
+    /// Appends `unit` if it fits, starting a new chunk when needed.
+    fn push(&mut self, unit: &'a str) -> bool {
+        if self.fits(unit) {
+            self.append(unit);
+            return true;
+        }
+        if self.fresh {
+            return false;
+        }
+        self.flush();
+        if self.fits(unit) {
+            self.append(unit);
+            return true;
+        }
+        false
+    }
+
+    fn fits(&self, unit: &'a str) -> bool {
+        let mut open = self.open.clone();
+        track_tags(&mut open, unit);
+        self.len + unit.chars().count() + closing_tags(&open).chars().count() <= self.limit
+    }
+
+    fn append(&mut self, unit: &'a str) {
+        self.chunk.push_str(unit);
+        self.len += unit.chars().count();
+        track_tags(&mut self.open, unit);
+        self.fresh = false;
+    }
+
+    fn flush(&mut self) {
+        self.chunk.push_str(&closing_tags(&self.open));
+        self.chunks.push(std::mem::take(&mut self.chunk));
+        self.chunk = self.open.concat();
+        self.len = self.chunk.chars().count();
+        self.fresh = true;
+    }
+
+    fn finish(mut self) -> Vec<String> {
+        if !self.fresh {
+            self.chunks.push(self.chunk);
+        }
+        self.chunks
+    }
+}
+
+fn tag_name(tag: &str) -> String {
+    tag.trim_start_matches('<')
+        .trim_start_matches('/')
+        .trim_end_matches('>')
+        .split_whitespace()
+        .next()
+        .unwrap_or_default()
+        .to_ascii_lowercase()
+}
+
+fn is_tag(token: &str) -> bool {
+    token.len() > 2 && token.starts_with('<') && token.ends_with('>')
+}
+
+/// Updates the stack of open tags with the tags found in `text`.
+fn track_tags<'a>(open: &mut Vec<&'a str>, text: &'a str) {
+    for token in tokens(text).into_iter().filter(|token| is_tag(token)) {
+        if token.starts_with("</") {
+            let name = tag_name(token);
+            if let Some(index) = open.iter().rposition(|tag| tag_name(tag) == name) {
+                open.truncate(index);
+            }
+        } else if !token.ends_with("/>") {
+            open.push(token);
+        }
+    }
+}
+
+fn closing_tags(open: &[&str]) -> String {
+    open.iter()
+        .rev()
+        .map(|tag| format!("</{}>", tag_name(tag)))
+        .collect()
+}
+
+/// Splits HTML into tags, entities and single characters.
+fn tokens(text: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut rest = text;
+    while let Some(first) = rest.chars().next() {
+        let end = match first {
+            '<' => rest.find('>').map(|end| end + 1),
+            '&' => rest
+                .find(';')
+                .filter(|end| {
+                    *end <= 10
+                        && rest[1..*end]
+                            .chars()
+                            .all(|c| c.is_ascii_alphanumeric() || c == '#')
+                })
+                .map(|end| end + 1),
+            _ => None,
+        }
+        .unwrap_or(first.len_utf8());
+        tokens.push(&rest[..end]);
+        rest = &rest[end..];
+    }
+    tokens
+}
+
+fn unescape(entity: &str) -> Option<char> {
+    let name = entity.strip_prefix('&')?.strip_suffix(';')?;
+    match name {
+        "lt" => Some('<'),
+        "gt" => Some('>'),
+        "amp" => Some('&'),
+        "quot" => Some('"'),
+        _ => {
+            let code = name.strip_prefix('#')?;
+            let code = match code.strip_prefix(['x', 'X']) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                None => code.parse().ok()?,
+            };
+            char::from_u32(code)
+        }
+    }
+}
+
+/// Converts the Telegram HTML written by the bot to MarkdownV2: bold, italic, underline,
+/// strikethrough and code are kept, other tags are dropped and text is escaped.
+pub fn to_markdown_v2(html: &str) -> String {
+    let mut markdown = String::with_capacity(html.len());
+    let mut code = false;
+    for token in tokens(html) {
+        if is_tag(token) {
+            let closing = token.starts_with("</");
+            match tag_name(token).as_str() {
+                "b" | "strong" => markdown.push('*'),
+                "i" | "em" => markdown.push('_'),
+                "u" | "ins" => markdown.push_str("__"),
+                "s" | "strike" | "del" => markdown.push('~'),
+                // A `<pre><code>` pair makes a single block.
+                "code" | "pre" if closing && code => {
+                    markdown.push_str("```");
+                    code = false;
+                }
+                "code" | "pre" if !closing && !code => {
+                    markdown.push_str("```\n");
+                    code = true;
+                }
+                _ => {}
+            }
+            continue;
+        }
+        let value = if token.starts_with('&') && token.len() > 1 {
+            unescape(token)
+                .map(String::from)
+                .unwrap_or(token.to_string())
+        } else {
+            token.to_string()
+        };
+        for c in value.chars() {
+            let special = if code {
+                matches!(c, '`' | '\\')
+            } else {
+                "_*[]()~`>#+-=|{}.!\\".contains(c)
+            };
+            if special {
+                markdown.push('\\');
+            }
+            markdown.push(c);
+        }
+    }
+    markdown
+}
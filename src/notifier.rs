@@ -0,0 +1,479 @@
+use crate::events::{Account, Event, Subscriber};
+use crate::message::{self, escape, MAX_MESSAGE_LENGTH};
+use crate::settings::{QueueSettings, Telegram};
+use crate::status::AlertKind;
+use crate::{send_message, send_photo};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender, TryRecvError};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+const RATE_WINDOW: Duration = Duration::from_secs(1);
+
+/// Hands messages to the outbound queue. Delivery happens on the sender thread, so checkers
+/// never block on Telegram.
+#[derive(Clone)]
+pub struct Notifier {
+    commands: Sender<Command>,
+    chat_id: i64,
+    alert_chat_id: i64,
+    lifecycle_notices: bool,
+}
+
+enum Command {
+    Send(Outgoing),
+    Flush(Sender<()>),
+}
+
+struct Outgoing {
+    chat_id: i64,
+    body: Body,
+    /// Alerts are spooled to disk and retried until delivered.
+    durable: bool,
+    attempts: u32,
+}
+
+enum Body {
+    Text(String),
+    Photo { caption: String, png: Vec<u8> },
+}
+
+/// An undelivered alert in the spool file.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Spooled {
+    chat_id: i64,
+    text: String,
+}
+
+impl Notifier {
+    /// Starts the sender thread. It stops once every `Notifier` clone is dropped;
+    /// alerts still queued at that point stay in the spool.
+    pub fn start(telegram: &Telegram) -> (Notifier, JoinHandle<()>) {
+        let (commands, receiver) = mpsc::channel();
+        let outbox = Outbox::new(telegram);
+        let thread = thread::spawn(move || {
+            tracing::info!("Start telegram sender thread");
+            outbox.run(receiver)
+        });
+        let notifier = Notifier {
+            commands,
+            chat_id: telegram.chat_id,
+            alert_chat_id: telegram.alert_chat_id,
+            lifecycle_notices: telegram.lifecycle_notices,
+        };
+        (notifier, thread)
+    }
+
+    /// Queues a message to the report chat, split if it is too long for one message.
+    pub fn report(&self, text: String) {
+        for chunk in message::split(&text, MAX_MESSAGE_LENGTH) {
+            self.send(self.chat_id, Body::Text(chunk), false);
+        }
+    }
+
+    /// Queues a message to the alert chat, split if it is too long for one message.
+    pub fn alert(&self, text: String) {
+        for chunk in message::split(&text, MAX_MESSAGE_LENGTH) {
+            self.send(self.alert_chat_id, Body::Text(chunk), true);
+        }
+    }
+
+    /// Queues a chart to the report chat.
+    pub fn photo(&self, caption: &str, png: Vec<u8>) {
+        let body = Body::Photo {
+            caption: caption.to_string(),
+            png,
+        };
+        self.send(self.chat_id, body, false);
+    }
+
+    /// Waits until the queue is empty. Returns false on timeout.
+    pub fn flush(&self, timeout: Duration) -> bool {
+        let (done, wait) = mpsc::channel();
+        self.commands.send(Command::Flush(done)).is_ok() && wait.recv_timeout(timeout).is_ok()
+    }
+
+    fn send(&self, chat_id: i64, body: Body, durable: bool) {
+        let outgoing = Outgoing {
+            chat_id,
+            body,
+            durable,
+            attempts: 0,
+        };
+        if self.commands.send(Command::Send(outgoing)).is_err() {
+            tracing::error!(
+                "Telegram sender thread is stopped, message to chat {} lost",
+                chat_id
+            );
+        }
+    }
+}
+
+/// Turns events into Telegram messages: alerts to the alert chat, reports and charts
+/// to the report chat.
+impl Subscriber for Notifier {
+    fn name(&self) -> &'static str {
+        "notifier"
+    }
+
+    fn handle(&self, event: &Event) {
+        match event {
+            Event::Delinquent(validator) => self.alert(format!(
+                "<b>{}</b>\npubkey -> {}\n<b>DELINQUENT!!!</b>!!!",
+                escape(&validator.label()),
+                &validator.identity[..16]
+            )),
+            Event::BalanceChanged {
+                validator,
+                account,
+                previous,
+                current,
+            } => {
+                let account = match account {
+                    Account::Identity => "Identity",
+                    Account::Vote => "Vote",
+                };
+                self.alert(format!(
+                    "<b>{}</b>\npubkey -> {}\n<b>{} balance changed!!! {:.3};{:.3};{:.3}</b>!!!",
+                    escape(&validator.label()),
+                    &validator.identity[..16],
+                    account,
+                    previous,
+                    current,
+                    current - previous
+                ))
+            }
+            Event::SkipRateCritical {
+                validator,
+                skip_rate,
+                ..
+            } => self.alert(format!(
+                "<b>{}</b>\npubkey -> {}\n<b>CRITICAL_SKIP_RATE => {}!!!</b>!!!",
+                escape(&validator.label()),
+                &validator.identity[..16],
+                skip_rate
+            )),
+            Event::LowBalance {
+                validator, balance, ..
+            } => self.alert(format!(
+                "<b>{}</b>\npubkey -> {}\n<b>SMALL AMOUNT => {}!!!</b>!!!",
+                escape(&validator.label()),
+                &validator.identity[..16],
+                balance
+            )),
+            Event::RewardsBelowTrend {
+                validator,
+                apy,
+                trend_apy,
+                income,
+                trend_income,
+                ..
+            } => self.alert(format!(
+                "<b>{}</b>\npubkey -> {}\n<b>REWARDS BELOW TREND => APY {:.2}% vs {:.2}%, income {:.4} vs {:.4}!!!</b>!!!",
+                escape(&validator.label()),
+                &validator.identity[..16],
+                apy,
+                trend_apy,
+                income,
+                trend_income
+            )),
+            Event::ReportReady(html) => self.report(html.clone()),
+            Event::ChartReady { caption, png } => self.photo(caption, png.clone()),
+            Event::BotStarted { validators } if self.lifecycle_notices => self.report(format!(
+                "<b>solana-bot</b> started, watching {} validators",
+                validators
+            )),
+            Event::BotStopping if self.lifecycle_notices => {
+                self.report("<b>solana-bot</b> stopping".to_string())
+            }
+            Event::StillAlive {
+                validators,
+                active_alerts,
+                uptime,
+            } => self.report(format!(
+                "<b>solana-bot</b> still alive: watching {} validators, {} active alerts, up {} h {} min",
+                validators,
+                active_alerts,
+                uptime.as_secs() / 3600,
+                uptime.as_secs() / 60 % 60
+            )),
+            Event::PeerSilent { peer, silent_for } => self.alert(format!(
+                "<b>Peer bot {}</b>\n<b>NO HEARTBEAT for {} min!!!</b>!!!",
+                escape(peer),
+                silent_for.as_secs() / 60
+            )),
+            Event::Recovered {
+                validator,
+                kind: AlertKind::PeerSilent,
+            } => self.alert(format!("<b>Peer bot {}</b> is back", escape(validator))),
+            Event::LeadershipChanged {
+                instance,
+                active: true,
+            } => self.alert(format!(
+                "<b>solana-bot</b> {} is now the active instance",
+                escape(instance)
+            )),
+            _ => {}
+        }
+    }
+}
+
+/// Enforces the Bot API limits: a number of messages per second over all chats
+/// and a minimum interval between messages to one chat, longer for groups.
+struct RateLimiter {
+    per_second: usize,
+    chat_interval: Duration,
+    group_chat_interval: Duration,
+    sent: VecDeque<Instant>,
+    chats: HashMap<i64, Instant>,
+}
+
+impl RateLimiter {
+    fn new(settings: &QueueSettings) -> Self {
+        RateLimiter {
+            per_second: settings.messages_per_second as usize,
+            chat_interval: settings.chat_interval,
+            group_chat_interval: settings.group_chat_interval,
+            sent: VecDeque::new(),
+            chats: HashMap::new(),
+        }
+    }
+
+    /// Earliest time a message may go to `chat_id`.
+    fn ready_at(&mut self, chat_id: i64, now: Instant) -> Instant {
+        while self
+            .sent
+            .front()
+            .is_some_and(|sent| now.duration_since(*sent) >= RATE_WINDOW)
+        {
+            self.sent.pop_front();
+        }
+        let global = match self.sent.front() {
+            Some(first) if self.sent.len() >= self.per_second => *first + RATE_WINDOW,
+            _ => now,
+        };
+        // Groups and channels have negative ids.
+        let interval = if chat_id < 0 {
+            self.group_chat_interval
+        } else {
+            self.chat_interval
+        };
+        let chat = self
+            .chats
+            .get(&chat_id)
+            .map(|last| *last + interval)
+            .unwrap_or(now);
+        global.max(chat)
+    }
+
+    fn record(&mut self, chat_id: i64, now: Instant) {
+        self.sent.push_back(now);
+        self.chats.insert(chat_id, now);
+    }
+}
+
+struct Outbox {
+    telegram: Telegram,
+    queue: VecDeque<Outgoing>,
+    limiter: RateLimiter,
+    /// Set after a rate limit or a failure, no request is made before it.
+    paused_until: Option<Instant>,
+    backoff: Duration,
+    spool_path: PathBuf,
+    waiters: Vec<Sender<()>>,
+}
+
+impl Outbox {
+    fn new(telegram: &Telegram) -> Self {
+        let settings = &telegram.queue;
+        let mut outbox = Outbox {
+            telegram: telegram.clone(),
+            queue: VecDeque::new(),
+            limiter: RateLimiter::new(settings),
+            paused_until: None,
+            backoff: settings.retry_backoff,
+            spool_path: PathBuf::from(&settings.spool_path),
+            waiters: Vec::new(),
+        };
+        outbox.load_spool();
+        outbox
+    }
+
+    fn run(mut self, commands: Receiver<Command>) {
+        loop {
+            loop {
+                match commands.try_recv() {
+                    Ok(command) => self.accept(command),
+                    Err(TryRecvError::Empty) => break,
+                    Err(TryRecvError::Disconnected) => return,
+                }
+            }
+            let now = Instant::now();
+            let command = match self.next(now) {
+                Some((index, at)) if at <= now => {
+                    self.deliver(index);
+                    continue;
+                }
+                Some((_, at)) => match commands.recv_timeout(at - now) {
+                    Ok(command) => command,
+                    Err(RecvTimeoutError::Timeout) => continue,
+                    Err(RecvTimeoutError::Disconnected) => return,
+                },
+                None => match commands.recv() {
+                    Ok(command) => command,
+                    Err(_) => return,
+                },
+            };
+            self.accept(command);
+        }
+    }
+
+    fn accept(&mut self, command: Command) {
+        match command {
+            Command::Send(outgoing) => {
+                let durable = outgoing.durable;
+                self.queue.push_back(outgoing);
+                if durable {
+                    self.save_spool();
+                }
+            }
+            Command::Flush(done) => {
+                if self.queue.is_empty() {
+                    let _ = done.send(());
+                } else {
+                    self.waiters.push(done);
+                }
+            }
+        }
+    }
+
+    /// The queued message that may be sent first and when. Messages to one chat keep their order.
+    fn next(&mut self, now: Instant) -> Option<(usize, Instant)> {
+        let paused_until = self.paused_until.unwrap_or(now);
+        let mut next: Option<(usize, Instant)> = None;
+        for (index, outgoing) in self.queue.iter().enumerate() {
+            let at = self
+                .limiter
+                .ready_at(outgoing.chat_id, now)
+                .max(paused_until);
+            if next.is_none_or(|(_, first)| at < first) {
+                next = Some((index, at));
+            }
+        }
+        next
+    }
+
+    fn deliver(&mut self, index: usize) {
+        let settings = &self.telegram.queue;
+        let outgoing = &mut self.queue[index];
+        let chat_id = outgoing.chat_id;
+        let result = match &outgoing.body {
+            Body::Text(text) => send_message(text.clone(), &self.telegram, chat_id),
+            Body::Photo { caption, png } => send_photo(png, caption, &self.telegram, chat_id),
+        };
+        let now = Instant::now();
+        self.limiter.record(chat_id, now);
+        self.paused_until = None;
+
+        match result {
+            Ok(_) => {
+                tracing::trace!("Delivered message to chat {}", chat_id);
+                self.backoff = settings.retry_backoff;
+            }
+            Err(e) if e.status == Some(429) => {
+                let wait = e.retry_after.unwrap_or(self.backoff);
+                tracing::warn!("Rate limited by Telegram, retrying in {:?}", wait);
+                self.paused_until = Some(now + wait);
+                return;
+            }
+            Err(e) if e.is_transient() => {
+                outgoing.attempts += 1;
+                tracing::warn!(
+                    "Failed to send to chat {} (attempt {}), retrying in {:?}: {}",
+                    chat_id,
+                    outgoing.attempts,
+                    self.backoff,
+                    e
+                );
+                self.paused_until = Some(now + self.backoff);
+                self.backoff = (self.backoff * 2).min(settings.max_backoff);
+                if outgoing.durable || outgoing.attempts < settings.max_attempts {
+                    return;
+                }
+                tracing::error!(
+                    "Dropping message to chat {} after {} attempts",
+                    chat_id,
+                    outgoing.attempts
+                );
+            }
+            Err(e) => {
+                tracing::error!("Telegram rejected message to chat {}: {}", chat_id, e);
+            }
+        }
+        if self
+            .queue
+            .remove(index)
+            .is_some_and(|outgoing| outgoing.durable)
+        {
+            self.save_spool();
+        }
+        if self.queue.is_empty() {
+            for done in self.waiters.drain(..) {
+                let _ = done.send(());
+            }
+        }
+    }
+
+    fn load_spool(&mut self) {
+        let Ok(json) = std::fs::read_to_string(&self.spool_path) else {
+            return;
+        };
+        match serde_json::from_str::<Vec<Spooled>>(&json) {
+            Ok(spooled) => {
+                if !spooled.is_empty() {
+                    tracing::info!("Resending {} undelivered alerts", spooled.len());
+                }
+                self.queue
+                    .extend(spooled.into_iter().map(|spooled| Outgoing {
+                        chat_id: spooled.chat_id,
+                        body: Body::Text(spooled.text),
+                        durable: true,
+                        attempts: 0,
+                    }));
+            }
+            Err(e) => tracing::error!("Failed to parse spool {:?}: {}", self.spool_path, e),
+        }
+    }
+
+    /// Rewrites the spool with the alerts still queued, removes it when there are none.
+    fn save_spool(&self) {
+        let spooled: Vec<Spooled> = self
+            .queue
+            .iter()
+            .filter(|outgoing| outgoing.durable)
+            .filter_map(|outgoing| match &outgoing.body {
+                Body::Text(text) => Some(Spooled {
+                    chat_id: outgoing.chat_id,
+                    text: text.clone(),
+                }),
+                Body::Photo { .. } => None,
+            })
+            .collect();
+        let result = if spooled.is_empty() {
+            match std::fs::remove_file(&self.spool_path) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+                _ => Ok(()),
+            }
+        } else {
+            serde_json::to_string_pretty(&spooled)
+                .map_err(std::io::Error::from)
+                .and_then(|json| std::fs::write(&self.spool_path, json))
+        };
+        if let Err(e) = result {
+            tracing::error!("Failed to write spool {:?}: {}", self.spool_path, e);
+        }
+    }
+}
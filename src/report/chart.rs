@@ -0,0 +1,206 @@
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use image::{ImageOutputFormat, RgbImage};
+use plotters::prelude::*;
+use plotters::style::register_font;
+use std::io::Cursor;
+use std::sync::Once;
+
+use crate::storage::{HistoryStore, Metric};
+
+const WIDTH: u32 = 800;
+const HEIGHT: u32 = 400;
+const MAX_POINTS: usize = 500;
+const FONT: &[u8] = include_bytes!("../../assets/fonts/DejaVuSansMono.ttf");
+
+static REGISTER_FONT: Once = Once::new();
+
+pub struct Series {
+    pub label: &'static str,
+    pub color: RGBColor,
+    pub points: Vec<(DateTime<Utc>, f64)>,
+}
+
+/// Averages consecutive points into at most `max` buckets.
+fn downsample(points: &[(DateTime<Utc>, f64)], max: usize) -> Vec<(i64, f64)> {
+    let bucket = points.len().div_ceil(max).max(1);
+    points
+        .chunks(bucket)
+        .map(|chunk| {
+            let sum: f64 = chunk.iter().map(|(_, value)| value).sum();
+            (chunk[0].0.timestamp(), sum / chunk.len() as f64)
+        })
+        .collect()
+}
+
+fn format_time(timestamp: &i64) -> String {
+    Utc.timestamp_opt(*timestamp, 0)
+        .single()
+        .map(|time| time.format("%d.%m %H:%M").to_string())
+        .unwrap_or_default()
+}
+
+/// Renders a line chart of the series into PNG bytes. Returns `None` when there is nothing to draw.
+pub fn render(title: &str, series: &[Series]) -> Option<Vec<u8>> {
+    REGISTER_FONT.call_once(|| {
+        if register_font("sans-serif", FontStyle::Normal, FONT).is_err() {
+            tracing::error!("Failed to register chart font");
+        }
+    });
+    let series: Vec<(&Series, Vec<(i64, f64)>)> = series
+        .iter()
+        .map(|series| (series, downsample(&series.points, MAX_POINTS)))
+        .filter(|(_, points)| !points.is_empty())
+        .collect();
+    let all_points = series.iter().flat_map(|(_, points)| points.iter());
+    let (min_x, max_x, min_y, max_y) = all_points.fold(
+        (i64::MAX, i64::MIN, f64::MAX, f64::MIN),
+        |(min_x, max_x, min_y, max_y), (x, y)| {
+            (min_x.min(*x), max_x.max(*x), min_y.min(*y), max_y.max(*y))
+        },
+    );
+    if series.is_empty() {
+        return None;
+    }
+    let margin = ((max_y - min_y) * 0.05).max(0.01);
+
+    let mut buffer = vec![0; (WIDTH * HEIGHT * 3) as usize];
+    {
+        let root = BitMapBackend::with_buffer(&mut buffer, (WIDTH, HEIGHT)).into_drawing_area();
+        let draw = || -> Result<(), Box<dyn std::error::Error + '_>> {
+            root.fill(&WHITE)?;
+            let mut chart = ChartBuilder::on(&root)
+                .caption(title, ("sans-serif", 20))
+                .margin(10)
+                .x_label_area_size(30)
+                .y_label_area_size(60)
+                .build_cartesian_2d(
+                    min_x..max_x.max(min_x + 1),
+                    (min_y - margin)..(max_y + margin),
+                )?;
+            chart
+                .configure_mesh()
+                .x_labels(6)
+                .x_label_formatter(&format_time)
+                .label_style(("sans-serif", 12))
+                .draw()?;
+            for (series, points) in &series {
+                let color = series.color;
+                chart
+                    .draw_series(LineSeries::new(
+                        points.iter().cloned(),
+                        color.stroke_width(2),
+                    ))?
+                    .label(series.label)
+                    .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], color));
+            }
+            chart
+                .configure_series_labels()
+                .background_style(WHITE.mix(0.8))
+                .border_style(BLACK)
+                .label_font(("sans-serif", 12))
+                .draw()?;
+            root.present()?;
+            Ok(())
+        };
+        if let Err(e) = draw() {
+            tracing::error!("Failed to draw chart '{}': {}", title, e);
+            return None;
+        }
+    }
+
+    let image = RgbImage::from_raw(WIDTH, HEIGHT, buffer)?;
+    let mut png = Cursor::new(Vec::new());
+    if let Err(e) = image.write_to(&mut png, ImageOutputFormat::Png) {
+        tracing::error!("Failed to encode chart '{}': {}", title, e);
+        return None;
+    }
+    Some(png.into_inner())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChartKind {
+    SkipRate,
+    Rank,
+    Balance,
+}
+
+impl ChartKind {
+    pub const ALL: [ChartKind; 3] = [ChartKind::SkipRate, ChartKind::Rank, ChartKind::Balance];
+
+    pub fn slug(&self) -> &'static str {
+        match self {
+            ChartKind::SkipRate => "skip",
+            ChartKind::Rank => "rank",
+            ChartKind::Balance => "balance",
+        }
+    }
+
+    pub fn from_slug(slug: &str) -> Option<Self> {
+        ChartKind::ALL.into_iter().find(|kind| kind.slug() == slug)
+    }
+
+    /// Renders the chart for `name`; the skip rate chart starts at `epoch_start`,
+    /// the others cover the last week.
+    pub fn render(
+        &self,
+        store: &HistoryStore,
+        name: &str,
+        epoch_start: DateTime<Utc>,
+    ) -> Option<(String, Vec<u8>)> {
+        let now = Utc::now();
+        let week_ago = now - Duration::days(7);
+        let (title, series) = match self {
+            ChartKind::SkipRate => (
+                format!("{}: skip rate over the epoch", name),
+                vec![
+                    Series {
+                        label: "skip %",
+                        color: RED,
+                        points: store.query(name, Metric::SkipRate, epoch_start, now),
+                    },
+                    Series {
+                        label: "cluster %",
+                        color: BLUE,
+                        points: store.query(name, Metric::ClusterSkipRate, epoch_start, now),
+                    },
+                ],
+            ),
+            ChartKind::Rank => (
+                format!("{}: credits rank, 7 days", name),
+                vec![Series {
+                    label: "place",
+                    color: MAGENTA,
+                    points: store.query(name, Metric::Rank, week_ago, now),
+                }],
+            ),
+            ChartKind::Balance => (
+                format!("{}: balance, 7 days", name),
+                vec![
+                    Series {
+                        label: "identity",
+                        color: GREEN,
+                        points: store.query(name, Metric::IdentityBalance, week_ago, now),
+                    },
+                    Series {
+                        label: "vote",
+                        color: BLUE,
+                        points: store.query(name, Metric::VoteBalance, week_ago, now),
+                    },
+                ],
+            ),
+        };
+        render(&title, &series).map(|png| (title, png))
+    }
+}
+
+/// Standard chart set for a validator: skip rate over the epoch, rank and balances over a week.
+pub fn validator_charts(
+    store: &HistoryStore,
+    name: &str,
+    epoch_start: DateTime<Utc>,
+) -> Vec<(String, Vec<u8>)> {
+    ChartKind::ALL
+        .iter()
+        .filter_map(|kind| kind.render(store, name, epoch_start))
+        .collect()
+}
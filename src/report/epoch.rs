@@ -0,0 +1,181 @@
+use serde::{Deserialize, Serialize};
+
+use crate::client::Client;
+use crate::cluster::ClusterStats;
+use crate::message::escape;
+use crate::revenue::EpochRevenue;
+use solana_sdk::native_token::lamports_to_sol;
+
+/// Values observed at the start of an epoch, the baseline for its summary.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EpochBaseline {
+    pub epoch: u64,
+    pub partial: bool,
+    pub identity_balance: f64,
+    pub vote_balance: f64,
+    pub activated_stake: f64,
+}
+
+impl EpochBaseline {
+    pub async fn observe(client: &Client, epoch: u64, partial: bool) -> Self {
+        let (identity_balance, vote_balance, activated_stake) = tokio::join!(
+            client.get_identity_balance(),
+            client.get_vote_balance(),
+            client.activated_stake()
+        );
+        EpochBaseline {
+            epoch,
+            partial,
+            identity_balance,
+            vote_balance,
+            activated_stake: activated_stake.unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EpochSummary {
+    pub name: String,
+    pub epoch: u64,
+    /// The bot started in the middle of the epoch, so balance and stake changes are incomplete.
+    pub partial: bool,
+    pub leader_slots: usize,
+    pub blocks_produced: usize,
+    pub skip_rate: f64,
+    pub cluster_skip_rate: f64,
+    pub rank: usize,
+    pub credits: u64,
+    pub stake_before: f64,
+    pub stake_after: f64,
+    pub identity_balance_before: f64,
+    pub identity_balance_after: f64,
+    pub vote_balance_before: f64,
+    pub vote_balance_after: f64,
+    pub inflation_reward: Option<f64>,
+    pub commission: Option<u8>,
+    pub fee_revenue: Option<EpochRevenue>,
+}
+
+impl EpochSummary {
+    /// Summarizes the finished epoch of `baseline`, `next` is the baseline of the epoch that follows.
+    /// `cluster` holds the cluster-wide numbers of the finished epoch.
+    pub async fn collect(
+        client: &Client,
+        cluster: &ClusterStats,
+        baseline: &EpochBaseline,
+        next: &EpochBaseline,
+    ) -> Self {
+        let range = client.get_epoch_slot_range(baseline.epoch).await;
+        let (blocks, reward) = tokio::join!(
+            client.get_block_production_in_range(range),
+            client.get_vote_inflation_reward(baseline.epoch)
+        );
+        // Without leader slots nothing was skipped, `to_html` shows the rate as n/a.
+        let skip_rate = if blocks.0 == 0 {
+            0.
+        } else {
+            (blocks.0 - blocks.1) as f64 * 100. / blocks.0 as f64
+        };
+        let cluster_skip_rate = cluster.skip_rate;
+        let credits = cluster.credits_and_place(&client.validator.identity);
+
+        EpochSummary {
+            name: client.validator.label(),
+            epoch: baseline.epoch,
+            partial: baseline.partial,
+            leader_slots: blocks.0,
+            blocks_produced: blocks.1,
+            skip_rate,
+            cluster_skip_rate,
+            rank: credits.0,
+            credits: credits.1,
+            stake_before: baseline.activated_stake,
+            stake_after: next.activated_stake,
+            identity_balance_before: baseline.identity_balance,
+            identity_balance_after: next.identity_balance,
+            vote_balance_before: baseline.vote_balance,
+            vote_balance_after: next.vote_balance,
+            inflation_reward: reward.as_ref().map(|reward| lamports_to_sol(reward.amount)),
+            commission: reward.and_then(|reward| reward.commission),
+            fee_revenue: None,
+        }
+    }
+
+    pub fn to_html(&self) -> String {
+        let mut msg = format!(
+            "<b>{}</b> epoch {} finished",
+            escape(&self.name),
+            self.epoch
+        );
+        if self.partial {
+            msg.push_str(" (tracked partially)");
+        }
+        msg.push_str("\n\n<code>");
+        let skip_rate = if self.leader_slots == 0 {
+            "n/a".to_string()
+        } else {
+            format!("{:.2}%", self.skip_rate)
+        };
+        msg.push_str(
+            format!(
+                "blocks   {}/{} | skip {} | cluster {:.2}%\n",
+                self.blocks_produced, self.leader_slots, skip_rate, self.cluster_skip_rate
+            )
+            .as_str(),
+        );
+        msg.push_str(format!("credits  {} | place {}\n", self.credits, self.rank).as_str());
+        msg.push_str(
+            format!(
+                "stake    {:.2} -> {:.2} ({:+.2})\n",
+                self.stake_before,
+                self.stake_after,
+                self.stake_after - self.stake_before
+            )
+            .as_str(),
+        );
+        msg.push_str(
+            format!(
+                "identity {:.3} -> {:.3} ({:+.3})\n",
+                self.identity_balance_before,
+                self.identity_balance_after,
+                self.identity_balance_after - self.identity_balance_before
+            )
+            .as_str(),
+        );
+        msg.push_str(
+            format!(
+                "vote     {:.3} -> {:.3} ({:+.3})\n",
+                self.vote_balance_before,
+                self.vote_balance_after,
+                self.vote_balance_after - self.vote_balance_before
+            )
+            .as_str(),
+        );
+        match self.inflation_reward {
+            Some(reward) => msg.push_str(
+                format!(
+                    "reward   {:.4} SOL | commission {}%\n",
+                    reward,
+                    self.commission
+                        .map(|commission| commission.to_string())
+                        .unwrap_or_else(|| "?".to_string())
+                )
+                .as_str(),
+            ),
+            None => msg.push_str("reward   not available yet\n"),
+        }
+        if let Some(revenue) = &self.fee_revenue {
+            msg.push_str(
+                format!(
+                    "fees     {:.4} SOL from {} blocks\n",
+                    revenue.fees, revenue.blocks
+                )
+                .as_str(),
+            );
+        }
+        msg.push_str("</code>");
+        msg
+    }
+}
@@ -0,0 +1,303 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::client::Client;
+use crate::cluster::ClusterStats;
+use crate::revenue::EpochRevenue;
+use crate::settings::{cluster_label, NodeCheckSettings};
+use crate::storage::{HistoryStore, Metric};
+
+pub mod chart;
+pub mod epoch;
+pub mod render;
+pub mod schedule;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Health {
+    Healthy,
+    Delinquent,
+    CriticalSkipRate,
+}
+
+impl Health {
+    pub fn emoji(&self) -> &'static str {
+        match self {
+            Health::Healthy => "🟢",
+            Health::Delinquent | Health::CriticalSkipRate => "🔴",
+        }
+    }
+}
+
+impl fmt::Display for Health {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let value = match self {
+            Health::Healthy => "healthy",
+            Health::Delinquent => "delinquent",
+            Health::CriticalSkipRate => "critical skip rate",
+        };
+        f.write_str(value)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Balances {
+    pub identity: f64,
+    pub vote: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Credits {
+    pub rank: usize,
+    pub credits: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SkipRate {
+    pub leader_slots_total: usize,
+    pub leader_slots_passed: usize,
+    pub blocks_produced: usize,
+    pub skipped: usize,
+    pub skip_rate: f64,
+    pub cluster_skip_rate: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EpochProgress {
+    pub epoch: String,
+    pub time_remaining: String,
+    pub remaining_ratio: f32,
+}
+
+/// Snapshot of everything the hourly report shows for a single validator.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ValidatorReport {
+    pub timestamp: DateTime<Utc>,
+    pub name: String,
+    pub identity: String,
+    pub vote: String,
+    pub version: String,
+    pub health: Health,
+    pub balances: Balances,
+    pub credits: Credits,
+    pub skip_rate: SkipRate,
+    pub epoch: EpochProgress,
+    pub activated_stake: Option<f64>,
+    #[serde(default)]
+    pub fee_revenue: Option<EpochRevenue>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cluster: Option<String>,
+}
+
+impl ValidatorReport {
+    pub async fn collect(
+        client: &Client,
+        node: &NodeCheckSettings,
+        cluster: &ClusterStats,
+    ) -> Self {
+        let (
+            blocks,
+            epoch_info,
+            slot_count,
+            delinquent,
+            version,
+            identity_balance,
+            vote_balance,
+            activated_stake,
+        ) = tokio::join!(
+            client.get_block_production(),
+            client.get_epoch_info(),
+            client.get_slot_count(),
+            client.is_delinquent(),
+            client.get_version(),
+            client.get_identity_balance(),
+            client.get_vote_balance(),
+            client.activated_stake()
+        );
+        let skip_rate = (blocks.0 - blocks.1) as f64 * 100. / blocks.0 as f64;
+        let cluster_skip_rate = cluster.skip_rate;
+
+        let health = if skip_rate >= cluster_skip_rate + node.critical_excess_of_skip_rate()
+            && epoch_info.2 > 0.5
+            && blocks.0 as f32 / slot_count as f32 > 0.5
+        {
+            Health::CriticalSkipRate
+        } else if delinquent == Some(true) {
+            Health::Delinquent
+        } else {
+            Health::Healthy
+        };
+        let credits = cluster.credits_and_place(&client.validator.identity);
+
+        ValidatorReport {
+            timestamp: Utc::now(),
+            name: client.validator.name.clone(),
+            identity: client.validator.identity.clone(),
+            vote: client.validator.vote.clone(),
+            version,
+            health,
+            balances: Balances {
+                identity: identity_balance,
+                vote: vote_balance,
+            },
+            credits: Credits {
+                rank: credits.0,
+                credits: credits.1,
+            },
+            skip_rate: SkipRate {
+                leader_slots_total: slot_count,
+                leader_slots_passed: blocks.0,
+                blocks_produced: blocks.1,
+                skipped: blocks.0 - blocks.1,
+                skip_rate,
+                cluster_skip_rate,
+            },
+            epoch: EpochProgress {
+                epoch: epoch_info.0,
+                time_remaining: epoch_info.1,
+                remaining_ratio: epoch_info.2,
+            },
+            activated_stake,
+            fee_revenue: None,
+            cluster: client.validator.cluster.clone(),
+        }
+    }
+
+    /// Name used in messages, with the cluster when the validator belongs to one.
+    pub fn label(&self) -> String {
+        cluster_label(&self.name, self.cluster.as_deref())
+    }
+
+    pub fn metrics(&self) -> Vec<(Metric, f64)> {
+        let mut metrics = vec![
+            (Metric::SkipRate, self.skip_rate.skip_rate),
+            (Metric::ClusterSkipRate, self.skip_rate.cluster_skip_rate),
+            (Metric::Credits, self.credits.credits as f64),
+            (Metric::Rank, self.credits.rank as f64),
+        ];
+        if let Some(stake) = self.activated_stake {
+            metrics.push((Metric::ActivatedStake, stake));
+        }
+        metrics.retain(|(_, value)| value.is_finite());
+        metrics
+    }
+
+    pub fn render(&self, format: ReportFormat) -> String {
+        match format {
+            ReportFormat::Html => render::html(self),
+            ReportFormat::Text => render::text(self),
+            ReportFormat::Markdown => render::markdown(self),
+            ReportFormat::Json => render::json(self),
+            ReportFormat::Csv => render::csv(std::slice::from_ref(self)),
+        }
+    }
+
+    /// Writes the snapshot into `dir` in every requested format. CSV rows are
+    /// appended so the file accumulates a history, other formats hold the latest report.
+    pub fn export(&self, dir: &Path, formats: &[ReportFormat]) -> std::io::Result<()> {
+        std::fs::create_dir_all(dir)?;
+        let file_stem: String = self
+            .name
+            .chars()
+            .map(|c| {
+                if c.is_alphanumeric() || c == '-' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        for format in formats {
+            let path = dir.join(format!("{}.{}", file_stem, format.extension()));
+            match format {
+                ReportFormat::Csv => {
+                    let is_new = !path.exists();
+                    let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+                    if is_new {
+                        writeln!(file, "{}", render::csv_header())?;
+                    }
+                    writeln!(file, "{}", render::csv_row(self))?;
+                }
+                _ => std::fs::write(&path, self.render(*format))?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Aggregates over the last day of recorded history.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DailyStats {
+    pub uptime: Option<f64>,
+    pub max_slot_lag: Option<f64>,
+}
+
+impl DailyStats {
+    pub fn from_history(store: &HistoryStore, validator: &str, until: DateTime<Utc>) -> Self {
+        let since = until - chrono::Duration::days(1);
+        let delinquency = store.query(validator, Metric::Delinquent, since, until);
+        let uptime = if delinquency.is_empty() {
+            None
+        } else {
+            let healthy = delinquency.iter().filter(|(_, value)| *value == 0.).count();
+            Some(healthy as f64 * 100. / delinquency.len() as f64)
+        };
+        let max_slot_lag = store
+            .query(validator, Metric::SlotLag, since, until)
+            .into_iter()
+            .map(|(_, value)| value)
+            .reduce(f64::max);
+        DailyStats {
+            uptime,
+            max_slot_lag,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ReportFormat {
+    Html,
+    Text,
+    Markdown,
+    Json,
+    Csv,
+}
+
+impl ReportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ReportFormat::Html => "html",
+            ReportFormat::Text => "txt",
+            ReportFormat::Markdown => "md",
+            ReportFormat::Json => "json",
+            ReportFormat::Csv => "csv",
+        }
+    }
+}
+
+impl FromStr for ReportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "html" => Ok(ReportFormat::Html),
+            "text" | "txt" => Ok(ReportFormat::Text),
+            "markdown" | "md" => Ok(ReportFormat::Markdown),
+            "json" => Ok(ReportFormat::Json),
+            "csv" => Ok(ReportFormat::Csv),
+            _ => Err(format!("Unknown report format: {}", s)),
+        }
+    }
+}
@@ -0,0 +1,353 @@
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+
+use crate::message::escape;
+use crate::report::{DailyStats, ValidatorReport};
+
+const SEPARATOR_WIDTH: usize = 35;
+
+fn separator() -> String {
+    format!("{:-<width$}\n", "", width = SEPARATOR_WIDTH)
+}
+
+fn progress(report: &ValidatorReport) -> String {
+    format!(
+        "{}/{}",
+        report.skip_rate.leader_slots_total, report.skip_rate.leader_slots_passed
+    )
+}
+
+fn table(report: &ValidatorReport) -> String {
+    let mut table = String::new();
+    table.push_str(format!("{:^16} | {:^16}\n", "identity", "vote").as_str());
+    table.push_str(separator().as_str());
+    table.push_str(
+        format!(
+            "{:<16} | {:<16}\n",
+            &report.identity[..16],
+            &report.vote[..16]
+        )
+        .as_str(),
+    );
+    table.push_str(
+        format!(
+            "{:^16.*} | {:^16.*}\n",
+            2, report.balances.identity, 2, report.balances.vote
+        )
+        .as_str(),
+    );
+    table.push_str(separator().as_str());
+    table.push_str(
+        format!(
+            " place: {:^8} | credits: {:^7}\n",
+            report.credits.rank, report.credits.credits
+        )
+        .as_str(),
+    );
+    table.push_str(separator().as_str());
+    table.push_str(" progress | skip | skip% | cluster%\n");
+    table.push_str(separator().as_str());
+    table.push_str(
+        format!(
+            "{:^10}|{:^6}|{:^7.2}|{:^9.2}\n",
+            progress(report),
+            report.skip_rate.skipped,
+            report.skip_rate.skip_rate,
+            report.skip_rate.cluster_skip_rate
+        )
+        .as_str(),
+    );
+    table.push_str(separator().as_str());
+    table.push_str(
+        format!(
+            "epoch:{:^4}|{:^25}\n",
+            report.epoch.epoch, report.epoch.time_remaining
+        )
+        .as_str(),
+    );
+    table.push_str(separator().as_str());
+    table.push_str(
+        format!(
+            "Active stake |{:^22.2}\n",
+            report.activated_stake.unwrap_or_default()
+        )
+        .as_str(),
+    );
+    if let Some(revenue) = &report.fee_revenue {
+        table.push_str(format!("Fees {:>5} bl|{:^22.4}\n", revenue.blocks, revenue.fees).as_str());
+    }
+    table.push_str(separator().as_str());
+    table
+}
+
+pub fn html(report: &ValidatorReport) -> String {
+    let mut msg = format!(
+        "<b>{} [{}]</b> {}",
+        escape(&report.label()),
+        escape(&report.version),
+        report.health.emoji()
+    );
+    msg.push_str("\n\n");
+    msg.push_str("<code>");
+    msg.push_str(table(report).as_str());
+    msg.push_str("</code>");
+    msg
+}
+
+pub fn text(report: &ValidatorReport) -> String {
+    let mut msg = format!(
+        "{} [{}] {} ({})",
+        report.label(),
+        report.version,
+        report.health.emoji(),
+        report.health
+    );
+    msg.push_str("\n\n");
+    msg.push_str(table(report).as_str());
+    msg
+}
+
+pub fn markdown(report: &ValidatorReport) -> String {
+    let mut msg = format!(
+        "### {} [{}] {}\n\n",
+        report.label(),
+        report.version,
+        report.health.emoji()
+    );
+    msg.push_str("| field | value |\n");
+    msg.push_str("|---|---|\n");
+    let rows = [
+        ("status", report.health.to_string()),
+        ("identity", format!("`{}`", report.identity)),
+        ("vote", format!("`{}`", report.vote)),
+        (
+            "identity balance",
+            format!("{:.2}", report.balances.identity),
+        ),
+        ("vote balance", format!("{:.2}", report.balances.vote)),
+        ("place", report.credits.rank.to_string()),
+        ("credits", report.credits.credits.to_string()),
+        ("progress", progress(report)),
+        ("skipped", report.skip_rate.skipped.to_string()),
+        ("skip %", format!("{:.2}", report.skip_rate.skip_rate)),
+        (
+            "cluster skip %",
+            format!("{:.2}", report.skip_rate.cluster_skip_rate),
+        ),
+        ("epoch", report.epoch.epoch.clone()),
+        ("epoch ends in", report.epoch.time_remaining.clone()),
+        (
+            "active stake",
+            format!("{:.2}", report.activated_stake.unwrap_or_default()),
+        ),
+        (
+            "fee revenue",
+            report
+                .fee_revenue
+                .as_ref()
+                .map(|revenue| format!("{:.4} ({} blocks)", revenue.fees, revenue.blocks))
+                .unwrap_or_else(|| "-".to_string()),
+        ),
+    ];
+    for (field, value) in rows {
+        msg.push_str(format!("| {} | {} |\n", field, value.replace('|', "\\|")).as_str());
+    }
+    msg
+}
+
+pub fn digest(reports: &[ValidatorReport]) -> String {
+    let mut msg = String::from("<b>Digest</b>\n");
+    for report in reports {
+        msg.push_str(
+            format!(
+                "\n{} <b>{}</b> skip {:.2}%/{:.2}% | #{} | {:.2} SOL",
+                report.health.emoji(),
+                escape(&report.label()),
+                report.skip_rate.skip_rate,
+                report.skip_rate.cluster_skip_rate,
+                report.credits.rank,
+                report.balances.identity
+            )
+            .as_str(),
+        );
+    }
+    msg
+}
+
+fn delta(current: f64, previous: Option<f64>) -> String {
+    match previous {
+        Some(previous) => format!(" ({:+.2})", current - previous),
+        None => String::new(),
+    }
+}
+
+/// Daily summary of all validators, with changes against the previous daily snapshot.
+pub fn daily(
+    date: DateTime<Utc>,
+    reports: &[ValidatorReport],
+    previous: &HashMap<String, ValidatorReport>,
+    stats: &HashMap<String, DailyStats>,
+) -> String {
+    let mut msg = format!("<b>Daily summary {}</b>\n", date.format("%Y-%m-%d"));
+    for report in reports {
+        let prev = previous.get(&report.name);
+        let place_change = match prev {
+            Some(prev) if prev.credits.rank > 0 && report.credits.rank > 0 => {
+                match prev.credits.rank as i64 - report.credits.rank as i64 {
+                    0 => String::new(),
+                    change if change > 0 => format!(" (▲{})", change),
+                    change => format!(" (▼{})", -change),
+                }
+            }
+            _ => String::new(),
+        };
+        msg.push_str(
+            format!(
+                "\n<b>{} [{}]</b> {}\n",
+                escape(&report.label()),
+                escape(&report.version),
+                report.health.emoji()
+            )
+            .as_str(),
+        );
+        msg.push_str("<code>");
+        msg.push_str(
+            format!(
+                "identity {:.2}{}\nvote     {:.2}{}\n",
+                report.balances.identity,
+                delta(
+                    report.balances.identity,
+                    prev.map(|prev| prev.balances.identity)
+                ),
+                report.balances.vote,
+                delta(report.balances.vote, prev.map(|prev| prev.balances.vote))
+            )
+            .as_str(),
+        );
+        msg.push_str(
+            format!(
+                "credits  {} | place {}{}\n",
+                report.credits.credits, report.credits.rank, place_change
+            )
+            .as_str(),
+        );
+        msg.push_str(
+            format!(
+                "skip     {:.2}% | cluster {:.2}%\n",
+                report.skip_rate.skip_rate, report.skip_rate.cluster_skip_rate
+            )
+            .as_str(),
+        );
+        let stake = report.activated_stake.unwrap_or_default();
+        msg.push_str(
+            format!(
+                "stake    {:.2}{}",
+                stake,
+                delta(stake, prev.and_then(|prev| prev.activated_stake))
+            )
+            .as_str(),
+        );
+        if let Some(stats) = stats.get(&report.name) {
+            let uptime = stats
+                .uptime
+                .map(|uptime| format!("{:.2}%", uptime))
+                .unwrap_or_else(|| "?".to_string());
+            let max_slot_lag = stats
+                .max_slot_lag
+                .map(|lag| format!("{:.0}", lag))
+                .unwrap_or_else(|| "?".to_string());
+            msg.push_str(format!("\nuptime   {} | max lag {}", uptime, max_slot_lag).as_str());
+        }
+        msg.push_str("</code>\n");
+    }
+    msg
+}
+
+pub fn json(report: &ValidatorReport) -> String {
+    serde_json::to_string_pretty(report).unwrap_or_default()
+}
+
+const CSV_HEADER: [&str; 21] = [
+    "timestamp",
+    "name",
+    "identity",
+    "vote",
+    "version",
+    "health",
+    "identity_balance",
+    "vote_balance",
+    "rank",
+    "credits",
+    "leader_slots_total",
+    "leader_slots_passed",
+    "blocks_produced",
+    "skipped",
+    "skip_rate",
+    "cluster_skip_rate",
+    "epoch",
+    "epoch_remaining_ratio",
+    "activated_stake",
+    "fee_blocks",
+    "fee_revenue",
+];
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+pub fn csv_header() -> String {
+    CSV_HEADER.join(",")
+}
+
+pub fn csv_row(report: &ValidatorReport) -> String {
+    let row = [
+        report.timestamp.to_rfc3339(),
+        report.name.clone(),
+        report.identity.clone(),
+        report.vote.clone(),
+        report.version.clone(),
+        report.health.to_string(),
+        report.balances.identity.to_string(),
+        report.balances.vote.to_string(),
+        report.credits.rank.to_string(),
+        report.credits.credits.to_string(),
+        report.skip_rate.leader_slots_total.to_string(),
+        report.skip_rate.leader_slots_passed.to_string(),
+        report.skip_rate.blocks_produced.to_string(),
+        report.skip_rate.skipped.to_string(),
+        report.skip_rate.skip_rate.to_string(),
+        report.skip_rate.cluster_skip_rate.to_string(),
+        report.epoch.epoch.clone(),
+        report.epoch.remaining_ratio.to_string(),
+        report
+            .activated_stake
+            .map(|stake| stake.to_string())
+            .unwrap_or_default(),
+        report
+            .fee_revenue
+            .as_ref()
+            .map(|revenue| revenue.blocks.to_string())
+            .unwrap_or_default(),
+        report
+            .fee_revenue
+            .as_ref()
+            .map(|revenue| revenue.fees.to_string())
+            .unwrap_or_default(),
+    ];
+    let row: Vec<String> = row.iter().map(|value| csv_field(value)).collect();
+    row.join(",")
+}
+
+pub fn csv(reports: &[ValidatorReport]) -> String {
+    let mut csv = csv_header();
+    csv.push('\n');
+    for report in reports {
+        csv.push_str(csv_row(report).as_str());
+        csv.push('\n');
+    }
+    csv
+}
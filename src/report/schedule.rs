@@ -0,0 +1,95 @@
+use chrono::{DateTime, Duration, LocalResult, NaiveDateTime, Offset, TimeZone, Utc};
+use chrono_tz::Tz;
+use cron::Schedule;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+use crate::settings::{ReportSchedule, Reports};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ReportKind {
+    Full,
+    Digest,
+    Daily,
+}
+
+impl ReportSchedule {
+    pub fn parse(&self) -> Result<(Schedule, Tz), String> {
+        let schedule = Schedule::from_str(&self.cron)
+            .map_err(|e| format!("invalid cron expression '{}': {}", self.cron, e))?;
+        let timezone = Tz::from_str(&self.timezone)
+            .map_err(|e| format!("invalid time zone '{}': {}", self.timezone, e))?;
+        Ok((schedule, timezone))
+    }
+}
+
+pub struct ReportScheduler {
+    entries: Vec<(ReportKind, Schedule, Tz)>,
+}
+
+impl ReportScheduler {
+    pub fn new(reports: &Reports) -> Self {
+        let mut entries = Vec::new();
+        for (kind, schedule) in [
+            (ReportKind::Full, &reports.full),
+            (ReportKind::Digest, &reports.digest),
+            (ReportKind::Daily, &reports.daily),
+        ] {
+            if let Some(schedule) = schedule {
+                match schedule.parse() {
+                    Ok((cron, timezone)) => entries.push((kind, cron, timezone)),
+                    Err(e) => tracing::error!("Skip {:?} report schedule: {}", kind, e),
+                }
+            }
+        }
+        ReportScheduler { entries }
+    }
+
+    /// Whether no report is scheduled at all.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the nearest fire time after `now` together with every report kind due at it.
+    pub fn next_after(&self, now: DateTime<Utc>) -> Option<(DateTime<Utc>, Vec<ReportKind>)> {
+        let upcoming: Vec<(ReportKind, DateTime<Utc>)> = self
+            .entries
+            .iter()
+            .filter_map(|(kind, schedule, timezone)| {
+                next_fire(schedule, timezone, now).map(|time| (*kind, time))
+            })
+            .collect();
+        let fire_at = upcoming.iter().map(|(_, time)| *time).min()?;
+        let kinds = upcoming
+            .into_iter()
+            .filter(|(_, time)| *time == fire_at)
+            .map(|(kind, _)| kind)
+            .collect();
+        Some((fire_at, kinds))
+    }
+}
+
+/// The first time after `now` at which `schedule` fires on the wall clock of `timezone`.
+/// A time repeated when the clocks go back fires once, at its first occurrence, and a
+/// time skipped when they go forward fires right after the change.
+fn next_fire(schedule: &Schedule, timezone: &Tz, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    // The wall clock is walked as if it were UTC, so the cron crate never meets a DST change.
+    let wall_clock = now.with_timezone(timezone).naive_local().and_utc();
+    // Start early enough to see wall clock times that repeat after the clocks go back.
+    schedule
+        .after(&(wall_clock - Duration::hours(3)))
+        .map(|time| to_utc(timezone, time.naive_utc()))
+        .find(|time| *time > now)
+}
+
+fn to_utc(timezone: &Tz, local: NaiveDateTime) -> DateTime<Utc> {
+    match timezone.from_local_datetime(&local) {
+        LocalResult::Single(time) | LocalResult::Ambiguous(time, _) => time.with_timezone(&Utc),
+        // In the gap the clocks skipped, so counted with the offset from before the change.
+        LocalResult::None => {
+            let before = timezone.offset_from_utc_datetime(&(local - Duration::days(1)));
+            (local - Duration::seconds(before.fix().local_minus_utc().into())).and_utc()
+        }
+    }
+}
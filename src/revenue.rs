@@ -0,0 +1,183 @@
+use serde::{Deserialize, Serialize};
+use solana_sdk::native_token::lamports_to_sol;
+use std::collections::{BTreeMap, HashMap};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use crate::client::{BlockFee, Client};
+
+const CSV_HEADER: &str = "slot,epoch,produced,fee_lamports";
+const MAX_BLOCKS_PER_UPDATE: usize = 200;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SlotRevenue {
+    pub slot: u64,
+    pub epoch: u64,
+    pub produced: bool,
+    pub fee_lamports: u64,
+}
+
+impl SlotRevenue {
+    fn to_csv(self) -> String {
+        format!(
+            "{},{},{},{}",
+            self.slot, self.epoch, self.produced, self.fee_lamports
+        )
+    }
+
+    fn from_csv(line: &str) -> Option<Self> {
+        let mut fields = line.split(',');
+        Some(SlotRevenue {
+            slot: fields.next()?.parse().ok()?,
+            epoch: fields.next()?.parse().ok()?,
+            produced: fields.next()?.parse().ok()?,
+            fee_lamports: fields.next()?.parse().ok()?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EpochRevenue {
+    pub blocks: usize,
+    pub fees: f64,
+}
+
+#[derive(Default)]
+struct ValidatorLedger {
+    slots: BTreeMap<u64, SlotRevenue>,
+    leader_slots: Option<(u64, Vec<u64>)>,
+}
+
+/// Fee revenue of produced blocks, kept per validator in an append-only CSV
+/// ledger with one row per leader slot for accounting exports. Ledgers of
+/// different validators are updated independently.
+pub struct RevenueBook {
+    dir: PathBuf,
+    ledgers: Mutex<HashMap<String, Arc<tokio::sync::Mutex<ValidatorLedger>>>>,
+}
+
+impl RevenueBook {
+    pub fn open(dir: &Path) -> Self {
+        RevenueBook {
+            dir: dir.to_path_buf(),
+            ledgers: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn ledger_path(&self, name: &str) -> PathBuf {
+        let file_stem: String = name
+            .chars()
+            .map(|c| {
+                if c.is_alphanumeric() || c == '-' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        self.dir.join(format!("{}-revenue.csv", file_stem))
+    }
+
+    fn ledger(&self, name: &str) -> Arc<tokio::sync::Mutex<ValidatorLedger>> {
+        self.ledgers
+            .lock()
+            .unwrap()
+            .entry(name.to_string())
+            .or_insert_with(|| {
+                let mut ledger = ValidatorLedger::default();
+                if let Ok(csv) = std::fs::read_to_string(self.ledger_path(name)) {
+                    for slot in csv.lines().skip(1).filter_map(SlotRevenue::from_csv) {
+                        ledger.slots.insert(slot.slot, slot);
+                    }
+                }
+                Arc::new(tokio::sync::Mutex::new(ledger))
+            })
+            .clone()
+    }
+
+    fn append(&self, name: &str, slots: &[SlotRevenue]) -> std::io::Result<()> {
+        std::fs::create_dir_all(&self.dir)?;
+        let path = self.ledger_path(name);
+        let is_new = !path.exists();
+        let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+        if is_new {
+            writeln!(file, "{}", CSV_HEADER)?;
+        }
+        for slot in slots {
+            writeln!(file, "{}", slot.to_csv())?;
+        }
+        Ok(())
+    }
+
+    /// Accounts leader slots of `epoch` up to `last_slot`. Returns the number
+    /// of slots in that range that are still not recorded.
+    pub async fn update(&self, client: &Client, epoch: u64, last_slot: u64) -> usize {
+        let name = client.validator.name.clone();
+        let ledger = self.ledger(&name);
+        let mut ledger = ledger.lock().await;
+        if ledger
+            .leader_slots
+            .as_ref()
+            .is_none_or(|(cached_epoch, _)| *cached_epoch != epoch)
+        {
+            ledger.leader_slots = Some((epoch, client.get_leader_slots_in_epoch(epoch).await));
+        }
+        let pending: Vec<u64> = ledger
+            .leader_slots
+            .as_ref()
+            .map(|(_, slots)| {
+                slots
+                    .iter()
+                    .filter(|slot| **slot <= last_slot && !ledger.slots.contains_key(slot))
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let mut recorded = Vec::new();
+        for slot in pending.iter().take(MAX_BLOCKS_PER_UPDATE) {
+            let Some(fee) = client.get_block_fee(*slot).await else {
+                break;
+            };
+            let revenue = SlotRevenue {
+                slot: *slot,
+                epoch,
+                produced: fee != BlockFee::Skipped,
+                fee_lamports: match fee {
+                    BlockFee::Produced(lamports) => lamports,
+                    BlockFee::Skipped => 0,
+                },
+            };
+            ledger.slots.insert(*slot, revenue);
+            recorded.push(revenue);
+        }
+        let remaining = pending.len() - recorded.len();
+
+        if !recorded.is_empty() {
+            tracing::trace!("Recorded {} leader slots for {}", recorded.len(), name);
+            if let Err(e) = self.append(&name, &recorded) {
+                tracing::error!("Failed to write revenue ledger for {}: {}", name, e);
+            }
+        }
+        remaining
+    }
+
+    pub async fn epoch_revenue(&self, name: &str, epoch: u64) -> Option<EpochRevenue> {
+        let ledger = self.ledgers.lock().unwrap().get(name)?.clone();
+        let ledger = ledger.lock().await;
+        let (blocks, lamports) = ledger
+            .slots
+            .values()
+            .filter(|slot| slot.epoch == epoch && slot.produced)
+            .fold((0, 0), |(blocks, lamports), slot| {
+                (blocks + 1, lamports + slot.fee_lamports)
+            });
+        Some(EpochRevenue {
+            blocks,
+            fees: lamports_to_sol(lamports),
+        })
+    }
+}
@@ -0,0 +1,193 @@
+use serde::{Deserialize, Serialize};
+use solana_sdk::native_token::lamports_to_sol;
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::client::{Client, DEFAULT_SLOT_TIME_MS};
+use crate::message::escape;
+
+const SECONDS_PER_YEAR: f64 = 365.25 * 24. * 3600.;
+
+/// Inflation rewards of the vote account and its delegators for one epoch.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EpochRewards {
+    pub epoch: u64,
+    pub commission: Option<u8>,
+    pub commission_income: f64,
+    pub stake_accounts: usize,
+    pub delegated_stake: f64,
+    pub staking_rewards: f64,
+    pub epoch_duration_secs: u64,
+}
+
+impl EpochRewards {
+    /// Returns `None` until the rewards of `epoch` have been paid out.
+    pub async fn collect(client: &Client, epoch: u64, slots_in_epoch: u64) -> Option<Self> {
+        let vote_reward = client.get_vote_inflation_reward(epoch).await?;
+        let stake_accounts = client.get_delegated_stake_accounts().await;
+        let stake_rewards = client.get_inflation_rewards(&stake_accounts, epoch).await?;
+
+        let (mut delegated_stake, mut staking_rewards, mut rewarded_accounts) = (0, 0, 0);
+        for reward in stake_rewards.into_iter().flatten() {
+            delegated_stake += reward.post_balance.saturating_sub(reward.amount);
+            staking_rewards += reward.amount;
+            rewarded_accounts += 1;
+        }
+        let slot_time_ms = client
+            .get_average_slot_time_ms()
+            .await
+            .unwrap_or(DEFAULT_SLOT_TIME_MS);
+
+        Some(EpochRewards {
+            epoch,
+            commission: vote_reward.commission,
+            commission_income: lamports_to_sol(vote_reward.amount),
+            stake_accounts: rewarded_accounts,
+            delegated_stake: lamports_to_sol(delegated_stake),
+            staking_rewards: lamports_to_sol(staking_rewards),
+            epoch_duration_secs: slots_in_epoch * slot_time_ms / 1000,
+        })
+    }
+
+    /// Delegator APY in percent, compounding the epoch yield over a year.
+    pub fn apy(&self) -> f64 {
+        if self.delegated_stake <= 0. || self.epoch_duration_secs == 0 {
+            return 0.;
+        }
+        let epoch_yield = self.staking_rewards / self.delegated_stake;
+        let epochs_per_year = SECONDS_PER_YEAR / self.epoch_duration_secs as f64;
+        ((1. + epoch_yield).powf(epochs_per_year) - 1.) * 100.
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RewardsTrend {
+    pub epochs: usize,
+    pub apy: f64,
+    pub commission_income: f64,
+}
+
+impl RewardsTrend {
+    pub fn from_history(previous: &[EpochRewards]) -> Option<Self> {
+        if previous.is_empty() {
+            return None;
+        }
+        let count = previous.len() as f64;
+        Some(RewardsTrend {
+            epochs: previous.len(),
+            apy: previous.iter().map(EpochRewards::apy).sum::<f64>() / count,
+            commission_income: previous
+                .iter()
+                .map(|rewards| rewards.commission_income)
+                .sum::<f64>()
+                / count,
+        })
+    }
+
+    /// True when the APY or the commission income dropped more than `drop_percent` below the trend.
+    pub fn is_dropped(&self, rewards: &EpochRewards, drop_percent: f64) -> bool {
+        let factor = 1. - drop_percent / 100.;
+        rewards.apy() < self.apy * factor
+            || rewards.commission_income < self.commission_income * factor
+    }
+}
+
+fn change_percent(current: f64, average: f64) -> String {
+    if average == 0. {
+        return "n/a".to_string();
+    }
+    format!("{:+.1}%", (current - average) * 100. / average)
+}
+
+pub fn to_html(name: &str, rewards: &EpochRewards, trend: Option<&RewardsTrend>) -> String {
+    let mut msg = format!(
+        "<b>{}</b> rewards for epoch {}\n\n<code>",
+        escape(name),
+        rewards.epoch
+    );
+    msg.push_str(
+        format!(
+            "commission  {}% | income {:.4} SOL\n",
+            rewards
+                .commission
+                .map(|commission| commission.to_string())
+                .unwrap_or_else(|| "?".to_string()),
+            rewards.commission_income
+        )
+        .as_str(),
+    );
+    msg.push_str(
+        format!(
+            "delegators  {} | stake {:.2} SOL\n",
+            rewards.stake_accounts, rewards.delegated_stake
+        )
+        .as_str(),
+    );
+    msg.push_str(
+        format!(
+            "rewards     {:.4} SOL | APY {:.2}%\n",
+            rewards.staking_rewards,
+            rewards.apy()
+        )
+        .as_str(),
+    );
+    if let Some(trend) = trend {
+        msg.push_str(
+            format!(
+                "vs last {}   income {} | APY {}\n",
+                trend.epochs,
+                change_percent(rewards.commission_income, trend.commission_income),
+                change_percent(rewards.apy(), trend.apy)
+            )
+            .as_str(),
+        );
+    }
+    msg.push_str("</code>");
+    msg
+}
+
+/// Per-validator rewards by epoch, persisted as JSON between restarts.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RewardsHistory {
+    #[serde(flatten)]
+    validators: HashMap<String, Vec<EpochRewards>>,
+}
+
+impl RewardsHistory {
+    pub fn load(path: &Path) -> Self {
+        match std::fs::read_to_string(path) {
+            Ok(json) => serde_json::from_str(&json).unwrap_or_else(|e| {
+                tracing::error!("Failed to parse rewards history {:?}: {}", path, e);
+                RewardsHistory::default()
+            }),
+            Err(_) => RewardsHistory::default(),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn contains(&self, name: &str, epoch: u64) -> bool {
+        self.validators
+            .get(name)
+            .is_some_and(|history| history.iter().any(|rewards| rewards.epoch == epoch))
+    }
+
+    pub fn record(&mut self, name: &str, rewards: EpochRewards) {
+        let history = self.validators.entry(name.to_string()).or_default();
+        history.retain(|value| value.epoch != rewards.epoch);
+        history.push(rewards);
+        history.sort_by_key(|value| value.epoch);
+    }
+
+    /// Up to `count` records preceding `epoch`, oldest first.
+    pub fn previous(&self, name: &str, epoch: u64, count: usize) -> &[EpochRewards] {
+        let Some(history) = self.validators.get(name) else {
+            return &[];
+        };
+        let end = history.partition_point(|rewards| rewards.epoch < epoch);
+        &history[end.saturating_sub(count)..end]
+    }
+}
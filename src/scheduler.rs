@@ -0,0 +1,171 @@
+use crate::checkers::{shortest_period, Checker, ValidatorContext};
+use crate::settings::NodeCheckSettings;
+use crate::state::SharedState;
+use rand::Rng;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::task::JoinSet;
+use tokio::time::Instant;
+
+/// Largest random delay added to a check, as a share of its interval.
+const MAX_JITTER: f64 = 0.1;
+
+/// Runs every registered checker for every validator in its own task, so a slow
+/// validator or checker never delays the others.
+pub struct Scheduler {
+    nodes: Vec<NodeCheckSettings>,
+    state: SharedState,
+    tasks: JoinSet<()>,
+    stop: watch::Sender<bool>,
+}
+
+/// Tells a task that the bot is shutting down.
+#[derive(Clone)]
+pub struct Shutdown(watch::Receiver<bool>);
+
+impl Shutdown {
+    /// Completes once a shutdown is requested, or when the scheduler is gone.
+    pub async fn requested(&mut self) {
+        let _ = self.0.wait_for(|stop| *stop).await;
+    }
+}
+
+impl Scheduler {
+    pub fn new(nodes: &[NodeCheckSettings], state: &SharedState) -> Self {
+        Scheduler {
+            nodes: nodes.to_vec(),
+            state: state.clone(),
+            tasks: JoinSet::new(),
+            stop: watch::channel(false).0,
+        }
+    }
+
+    fn shutdown(&self) -> Shutdown {
+        Shutdown(self.stop.subscribe())
+    }
+
+    /// Starts checking every validator with `checker` on its interval.
+    pub fn register(&mut self, checker: Arc<dyn Checker>) {
+        let name = checker.name();
+        let period = shortest_period(&self.nodes, |node| checker.interval(node));
+        let guard = self.state.status.register_checker(name, period);
+        tracing::info!("Start {} for {} validators", name, self.nodes.len());
+        let mut checks = JoinSet::new();
+        for node in &self.nodes {
+            let context = Arc::new(ValidatorContext::new(node, &self.state));
+            checks.spawn(run_checks(checker.clone(), context, self.shutdown()));
+        }
+        self.tasks.spawn(async move {
+            let _guard = guard;
+            while checks.join_next().await.is_some() {}
+        });
+    }
+
+    /// Starts a task that keeps its own timing, such as the scheduled reports. The task
+    /// is expected to return once `Shutdown` is requested.
+    pub fn spawn<F>(&mut self, name: &'static str, task: impl FnOnce(Shutdown) -> F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let task = task(self.shutdown());
+        self.tasks.spawn(async move {
+            if let Err(e) = tokio::spawn(task).await {
+                tracing::error!("{} stopped: {}", name, e);
+            }
+        });
+    }
+
+    /// Waits for every checker and task.
+    pub async fn join(mut self) {
+        self.join_tasks().await
+    }
+
+    async fn join_tasks(&mut self) {
+        while self.tasks.join_next().await.is_some() {}
+    }
+
+    /// Runs until `stop` completes, then stops every checker and task. Checks in
+    /// progress get `grace` to finish before they are cancelled. Returns false if
+    /// some had to be cancelled.
+    pub async fn run_until(mut self, stop: impl Future<Output = ()>, grace: Duration) -> bool {
+        tokio::select! {
+            _ = stop => {}
+            _ = self.join_tasks() => return true,
+        }
+        tracing::info!(
+            "Stop checkers, waiting up to {:?} for checks in progress",
+            grace
+        );
+        self.stop.send_replace(true);
+        if tokio::time::timeout(grace, self.join_tasks())
+            .await
+            .is_err()
+        {
+            tracing::warn!("Checks still running after {:?}, cancelling them", grace);
+            self.tasks.shutdown().await;
+            return false;
+        }
+        true
+    }
+}
+
+/// Checks one validator until shutdown. A run never overlaps the previous one: a
+/// check that overruns its interval is followed by the next one right away. A check
+/// that panics is logged and retried on the next interval. A run counts as a success
+/// unless it panicked or every RPC call it made failed.
+async fn run_checks(
+    checker: Arc<dyn Checker>,
+    context: Arc<ValidatorContext>,
+    mut shutdown: Shutdown,
+) {
+    let name = checker.name();
+    let validator = context.node.validator.name.clone();
+    let period = checker.interval(&context.node);
+    // Spread the first checks, so validators and checkers don't all hit the RPC at once.
+    let mut next = Instant::now() + jitter(period);
+    loop {
+        tokio::select! {
+            _ = tokio::time::sleep_until(next) => {}
+            _ = shutdown.requested() => return,
+        }
+        let started = Instant::now();
+        let calls = context.client.calls();
+        let run = {
+            let checker = checker.clone();
+            let context = context.clone();
+            tokio::spawn(async move { checker.check(&context).await })
+        };
+        match run.await {
+            Ok(events) => {
+                for event in events {
+                    context.state.bus.publish(event);
+                }
+                if context.client.calls().since(calls).all_failed() {
+                    tracing::warn!("{} of {} failed: no RPC call succeeded", name, validator);
+                } else {
+                    context.state.status.checker_succeeded(name);
+                }
+            }
+            Err(e) => tracing::error!("{} of {} failed: {}", name, validator, e),
+        }
+        next = started + period + jitter(period);
+        let now = Instant::now();
+        if next < now {
+            tracing::warn!(
+                "{} of {} took {:?}, longer than its interval {:?}",
+                name,
+                validator,
+                now - started,
+                period
+            );
+            next = now;
+        }
+    }
+}
+
+/// A random delay of up to `MAX_JITTER` of `period`.
+fn jitter(period: Duration) -> Duration {
+    period.mul_f64(rand::thread_rng().gen_range(0.0..MAX_JITTER))
+}
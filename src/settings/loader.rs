@@ -0,0 +1,304 @@
+use serde_json::{Map, Value};
+use std::path::{Path, PathBuf};
+
+use crate::SolanaBotError::{self, ParseFileError, ReadSettingsError};
+
+/// Prefix of environment variables that override single settings values.
+const ENV_PREFIX: &str = "SOLANA_BOT__";
+const EXTENSIONS: [&str; 4] = ["json", "toml", "yaml", "yml"];
+
+/// `settings.{json,toml,yaml,yml}` next to the executable, the first one that exists.
+pub fn default_path() -> PathBuf {
+    let mut dir = std::env::current_exe().unwrap_or_default();
+    dir.pop();
+    EXTENSIONS
+        .iter()
+        .map(|extension| dir.join(format!("settings.{}", extension)))
+        .find(|path| path.exists())
+        .unwrap_or_else(|| dir.join("settings.json"))
+}
+
+/// Every file to merge, in order: each given file followed by its per-host override
+/// `<name>.<hostname>.<ext>` when one exists.
+pub fn layers(paths: &[PathBuf]) -> Vec<PathBuf> {
+    let hostname = gethostname::gethostname().to_string_lossy().into_owned();
+    let mut layers = Vec::new();
+    for path in paths {
+        layers.push(path.clone());
+        if let Some(host_path) = host_override(path, &hostname) {
+            if host_path.exists() {
+                layers.push(host_path);
+            }
+        }
+    }
+    layers
+}
+
+fn host_override(path: &Path, hostname: &str) -> Option<PathBuf> {
+    let stem = path.file_stem()?.to_str()?;
+    let extension = path.extension()?.to_str()?;
+    if hostname.is_empty() {
+        return None;
+    }
+    Some(path.with_file_name(format!("{}.{}.{}", stem, hostname, extension)))
+}
+
+/// Reads a JSON, TOML or YAML file, chosen by extension, into a JSON document.
+pub fn read(path: &Path) -> Result<Value, SolanaBotError> {
+    let content =
+        std::fs::read_to_string(path).map_err(|e| ReadSettingsError(path.to_path_buf(), e))?;
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or("json")
+        .to_ascii_lowercase();
+    let parsed = match extension.as_str() {
+        "toml" => toml::from_str(&content).map_err(|e| e.to_string()),
+        "yaml" | "yml" => serde_yaml::from_str(&content).map_err(|e| e.to_string()),
+        _ => serde_json::from_str(&content).map_err(|e| e.to_string()),
+    };
+    parsed.map_err(|e| ParseFileError(path.to_path_buf(), e))
+}
+
+/// Merges `overlay` into `base`: objects are merged key by key, anything else is replaced.
+pub fn merge(base: &mut Value, overlay: Value) {
+    match (base, overlay) {
+        (Value::Object(base), Value::Object(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+/// Applies `SOLANA_BOT__SECTION__KEY=value` variables. Each segment is converted from
+/// snake case to the camelCase key and numeric segments index arrays. A value that
+/// replaces a string stays a string, e.g. a numeric token or validator name; any
+/// other value is parsed as JSON when possible so numbers and booleans keep their type.
+pub fn apply_env(document: &mut Value, vars: impl Iterator<Item = (String, String)>) {
+    let mut vars: Vec<(String, String)> = vars
+        .filter(|(name, _)| name.starts_with(ENV_PREFIX))
+        .collect();
+    vars.sort();
+    for (name, raw) in vars {
+        let segments: Vec<String> = name[ENV_PREFIX.len()..]
+            .split("__")
+            .map(camel_case)
+            .collect();
+        if segments.iter().any(|segment| segment.is_empty()) {
+            tracing::warn!("Ignore malformed settings override {}", name);
+            continue;
+        }
+        if !set(document, &segments, raw) {
+            tracing::warn!("Ignore settings override {}: no such array element", name);
+        }
+    }
+}
+
+fn set(target: &mut Value, segments: &[String], raw: String) -> bool {
+    let Some((segment, rest)) = segments.split_first() else {
+        *target = coerce(target, raw);
+        return true;
+    };
+    if !target.is_object() && !target.is_array() {
+        *target = Value::Object(Map::new());
+    }
+    match target {
+        Value::Array(items) => match segment.parse::<usize>().ok().and_then(|i| items.get_mut(i)) {
+            Some(item) => set(item, rest, raw),
+            None => false,
+        },
+        Value::Object(fields) => set(
+            fields.entry(segment.clone()).or_insert(Value::Null),
+            rest,
+            raw,
+        ),
+        _ => false,
+    }
+}
+
+/// The override `raw` of `existing`, typed like it.
+fn coerce(existing: &Value, raw: String) -> Value {
+    match existing {
+        Value::String(_) => Value::String(raw),
+        _ => serde_json::from_str(&raw).unwrap_or(Value::String(raw)),
+    }
+}
+
+fn camel_case(segment: &str) -> String {
+    let mut result = String::with_capacity(segment.len());
+    for (index, word) in segment.split('_').filter(|w| !w.is_empty()).enumerate() {
+        let word = word.to_ascii_lowercase();
+        if index == 0 {
+            result.push_str(&word);
+        } else {
+            let mut chars = word.chars();
+            if let Some(first) = chars.next() {
+                result.push(first.to_ascii_uppercase());
+                result.push_str(chars.as_str());
+            }
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn env(vars: &[(&str, &str)]) -> impl Iterator<Item = (String, String)> {
+        vars.iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("solana-bot-loader-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn layers_of_every_format_merge_in_order() {
+        let dir = temp_dir("layers");
+        let json = dir.join("settings.json");
+        let toml = dir.join("local.toml");
+        let yaml = dir.join("host.yaml");
+        std::fs::write(
+            &json,
+            r#"{"telegram": {"chatId": 1, "alertChatId": 2}, "balances": ["a", "b"], "timeouts": {"balanceCheckPeriod": "5s"}}"#,
+        )
+        .unwrap();
+        std::fs::write(&toml, "balances = [\"c\"]\n[telegram]\nchatId = 10\n").unwrap();
+        std::fs::write(
+            &yaml,
+            "telegram:\n  chatId: 100\ntimeouts:\n  epochCheckPeriod: 2m\n",
+        )
+        .unwrap();
+
+        let mut document = Value::Object(Map::new());
+        for layer in [&json, &toml, &yaml] {
+            merge(&mut document, read(layer).unwrap());
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(
+            document,
+            json!({
+                "telegram": {"chatId": 100, "alertChatId": 2},
+                "balances": ["c"],
+                "timeouts": {"balanceCheckPeriod": "5s", "epochCheckPeriod": "2m"},
+            })
+        );
+    }
+
+    #[test]
+    fn unparsable_layer_is_reported_with_its_path() {
+        let dir = temp_dir("broken");
+        let path = dir.join("settings.toml");
+        std::fs::write(&path, "telegram = {").unwrap();
+        let error = read(&path).unwrap_err();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert!(matches!(error, ParseFileError(ref failed, _) if failed == &path));
+    }
+
+    #[test]
+    fn host_override_sits_next_to_its_file() {
+        assert_eq!(
+            host_override(Path::new("/etc/bot/settings.toml"), "validator-1"),
+            Some(PathBuf::from("/etc/bot/settings.validator-1.toml"))
+        );
+        assert_eq!(host_override(Path::new("/etc/bot/settings.toml"), ""), None);
+        assert_eq!(
+            host_override(Path::new("/etc/bot/settings"), "validator-1"),
+            None
+        );
+    }
+
+    #[test]
+    fn env_overrides_address_nested_keys_and_array_elements() {
+        let mut document = json!({
+            "timeouts": {"balanceCheckPeriod": "5s"},
+            "nodes": [
+                {"validator": {"name": "node-1"}, "minBalanceAmount": 1},
+                {"validator": {"name": "node-2"}},
+            ],
+        });
+        apply_env(
+            &mut document,
+            env(&[
+                ("SOLANA_BOT__TIMEOUTS__BALANCE_CHECK_PERIOD", "30s"),
+                ("SOLANA_BOT__NODES__0__MIN_BALANCE_AMOUNT", "2.5"),
+                ("SOLANA_BOT__NODES__1__VALIDATOR__RPC", "http://node-2:8899"),
+                ("SOLANA_BOT__NODES__5__MIN_BALANCE_AMOUNT", "3"),
+                ("SOLANA_BOT__HTTP__LISTEN", "0.0.0.0:8080"),
+                ("SOLANA_BOT__NODES____NAME", "ignored"),
+                ("OTHER__TIMEOUTS", "ignored"),
+            ]),
+        );
+
+        assert_eq!(
+            document,
+            json!({
+                "timeouts": {"balanceCheckPeriod": "30s"},
+                "nodes": [
+                    {"validator": {"name": "node-1"}, "minBalanceAmount": 2.5},
+                    {"validator": {"name": "node-2", "rpc": "http://node-2:8899"}},
+                ],
+                "http": {"listen": "0.0.0.0:8080"},
+            })
+        );
+    }
+
+    #[test]
+    fn env_overrides_take_the_type_of_the_value_they_replace() {
+        let mut document = json!({
+            "telegram": {"token": "file:/run/token", "chatId": 1, "lifecycleNotices": false},
+            "nodes": [{"validator": {"name": "node-1"}}],
+        });
+        apply_env(
+            &mut document,
+            env(&[
+                ("SOLANA_BOT__TELEGRAM__TOKEN", "12345"),
+                ("SOLANA_BOT__TELEGRAM__CHAT_ID", "-100200"),
+                ("SOLANA_BOT__TELEGRAM__LIFECYCLE_NOTICES", "true"),
+                ("SOLANA_BOT__TELEGRAM__ALERT_CHAT_ID", "42"),
+                ("SOLANA_BOT__TELEGRAM__API_URL", "\"7\""),
+                ("SOLANA_BOT__NODES__0__VALIDATOR__NAME", "1"),
+            ]),
+        );
+
+        assert_eq!(
+            document,
+            json!({
+                "telegram": {
+                    "token": "12345",
+                    "chatId": -100200,
+                    "lifecycleNotices": true,
+                    "alertChatId": 42,
+                    "apiUrl": "7",
+                },
+                "nodes": [{"validator": {"name": "1"}}],
+            })
+        );
+    }
+
+    #[test]
+    fn env_keys_are_converted_to_camel_case() {
+        assert_eq!(camel_case("BALANCE_CHECK_PERIOD"), "balanceCheckPeriod");
+        assert_eq!(camel_case("TELEGRAM"), "telegram");
+        assert_eq!(camel_case("0"), "0");
+        assert_eq!(camel_case("__"), "");
+    }
+}
@@ -0,0 +1,585 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::time::Duration;
+
+pub mod loader;
+pub mod secret;
+mod validation;
+
+pub use secret::Secret;
+
+use secret::Redacted;
+pub use validation::SettingsProblem;
+
+use crate::message::ParseMode;
+use crate::report::schedule::ReportKind;
+use crate::report::ReportFormat;
+
+#[derive(Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Settings {
+    pub telegram: Telegram,
+    pub timeouts: Timeouts,
+    #[serde(default)]
+    pub defaults: CheckRules,
+    #[serde(default)]
+    pub clusters: BTreeMap<String, ClusterSettings>,
+    #[serde(default)]
+    pub rpc: RpcSettings,
+    pub nodes: Vec<NodeCheckSettings>,
+    pub balances: Vec<String>,
+    #[serde(default)]
+    pub reports: Reports,
+    #[serde(default)]
+    pub rewards: RewardsSettings,
+    #[serde(default)]
+    pub revenue: RevenueSettings,
+    #[serde(default)]
+    pub storage: StorageSettings,
+    pub http: Option<HttpSettings>,
+    #[serde(default)]
+    pub shutdown: ShutdownSettings,
+    pub heartbeat: Option<HeartbeatSettings>,
+    pub ha: Option<HaSettings>,
+}
+
+/// Any string may hold a resolved secret, so every section goes through `redact`.
+impl fmt::Debug for Settings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Settings")
+            .field("telegram", &Redacted(&self.telegram))
+            .field("timeouts", &self.timeouts)
+            .field("defaults", &self.defaults)
+            .field("clusters", &self.clusters)
+            .field("rpc", &self.rpc)
+            .field("nodes", &self.nodes)
+            .field("balances", &Redacted(&self.balances))
+            .field("reports", &Redacted(&self.reports))
+            .field("rewards", &Redacted(&self.rewards))
+            .field("revenue", &Redacted(&self.revenue))
+            .field("storage", &Redacted(&self.storage))
+            .field("http", &Redacted(&self.http))
+            .field("shutdown", &self.shutdown)
+            .field("heartbeat", &Redacted(&self.heartbeat))
+            .field("ha", &Redacted(&self.ha))
+            .finish()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Telegram {
+    pub token: Secret,
+    pub chat_id: i64,
+    pub alert_chat_id: i64,
+    /// Markup of the report chat messages.
+    #[serde(default)]
+    pub parse_mode: ParseMode,
+    #[serde(default)]
+    pub alert_parse_mode: ParseMode,
+    /// Bot API server, for a local Bot API server or a fake one in tests.
+    #[serde(default = "default_telegram_api_url")]
+    pub api_url: String,
+    #[serde(default)]
+    pub queue: QueueSettings,
+    /// Tell the report chat when the bot starts and stops.
+    #[serde(default)]
+    pub lifecycle_notices: bool,
+}
+
+fn default_telegram_api_url() -> String {
+    "https://api.telegram.org".to_string()
+}
+
+impl Default for Telegram {
+    fn default() -> Self {
+        Telegram {
+            token: Secret::default(),
+            chat_id: 0,
+            alert_chat_id: 0,
+            parse_mode: ParseMode::default(),
+            alert_parse_mode: ParseMode::default(),
+            api_url: default_telegram_api_url(),
+            queue: QueueSettings::default(),
+            lifecycle_notices: false,
+        }
+    }
+}
+
+impl Telegram {
+    /// Markup used for `chat_id`, HTML for chats other than the report and alert chats.
+    pub fn parse_mode(&self, chat_id: i64) -> ParseMode {
+        if chat_id == self.alert_chat_id {
+            self.alert_parse_mode
+        } else if chat_id == self.chat_id {
+            self.parse_mode
+        } else {
+            ParseMode::default()
+        }
+    }
+}
+
+/// Outbound message queue: Bot API rate limits, retries and the alert spool.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct QueueSettings {
+    /// Messages sent per second over all chats.
+    pub messages_per_second: u32,
+    /// Minimum time between two messages to the same private chat.
+    #[serde(with = "humantime_serde")]
+    pub chat_interval: Duration,
+    /// Minimum time between two messages to the same group or channel (a negative
+    /// chat id), which Telegram limits to 20 messages a minute.
+    #[serde(with = "humantime_serde")]
+    pub group_chat_interval: Duration,
+    /// First delay after a failed request, doubled on each further failure.
+    #[serde(with = "humantime_serde")]
+    pub retry_backoff: Duration,
+    #[serde(with = "humantime_serde")]
+    pub max_backoff: Duration,
+    /// Attempts before a report or chart is dropped. Alerts are retried until delivered.
+    pub max_attempts: u32,
+    /// Undelivered alerts, sent on the next start.
+    pub spool_path: String,
+}
+
+impl Default for QueueSettings {
+    fn default() -> Self {
+        QueueSettings {
+            messages_per_second: 30,
+            chat_interval: Duration::from_secs(1),
+            group_chat_interval: Duration::from_secs(3),
+            retry_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(300),
+            max_attempts: 5,
+            spool_path: "spool.json".to_string(),
+        }
+    }
+}
+
+/// Limits applied to every RPC request of every validator.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RpcSettings {
+    /// Time a single RPC call may take before it is abandoned.
+    #[serde(with = "humantime_serde")]
+    pub timeout: Duration,
+    /// RPC requests in flight at once over all validators and checkers.
+    pub max_concurrent_requests: usize,
+}
+
+impl Default for RpcSettings {
+    fn default() -> Self {
+        RpcSettings {
+            timeout: Duration::from_secs(30),
+            max_concurrent_requests: 16,
+        }
+    }
+}
+
+/// How the bot stops on SIGTERM or SIGINT.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ShutdownSettings {
+    /// Time given to the checks in progress, then again to the outbound queue.
+    #[serde(with = "humantime_serde")]
+    pub timeout: Duration,
+}
+
+impl Default for ShutdownSettings {
+    fn default() -> Self {
+        ShutdownSettings {
+            timeout: Duration::from_secs(30),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Timeouts {
+    #[serde(with = "humantime_serde")]
+    pub deliquency_check_period: Duration,
+    #[serde(with = "humantime_serde")]
+    pub balance_check_period: Duration,
+    #[serde(with = "humantime_serde", default = "default_epoch_check_period")]
+    pub epoch_check_period: Duration,
+    /// Skip rate and minimum balance alerts, independent of the report schedules.
+    #[serde(with = "humantime_serde", default = "default_health_check_period")]
+    pub health_check_period: Duration,
+}
+
+fn default_epoch_check_period() -> Duration {
+    Duration::from_secs(60)
+}
+
+fn default_health_check_period() -> Duration {
+    Duration::from_secs(3600)
+}
+
+impl Default for Timeouts {
+    fn default() -> Self {
+        Timeouts {
+            deliquency_check_period: Duration::from_secs(10),
+            balance_check_period: Duration::from_secs(5),
+            epoch_check_period: default_epoch_check_period(),
+            health_check_period: default_health_check_period(),
+        }
+    }
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeCheckSettings {
+    pub validator: Validator,
+    #[serde(flatten)]
+    pub rules: CheckRules,
+    #[serde(default)]
+    pub disabled_reports: Vec<ReportKind>,
+}
+
+/// Identity balance change that triggers an alert, in SOL.
+const DEFAULT_IDENTITY_BALANCE_DELTA: f64 = 0.05;
+/// Vote balance change that triggers an alert, in SOL; any change by default.
+const DEFAULT_VOTE_BALANCE_DELTA: f64 = 0.;
+
+impl NodeCheckSettings {
+    pub fn deliquency_check_period(&self) -> Duration {
+        self.rules
+            .deliquency_check_period
+            .unwrap_or(Timeouts::default().deliquency_check_period)
+    }
+
+    pub fn balance_check_period(&self) -> Duration {
+        self.rules
+            .balance_check_period
+            .unwrap_or(Timeouts::default().balance_check_period)
+    }
+
+    pub fn epoch_check_period(&self) -> Duration {
+        self.rules
+            .epoch_check_period
+            .unwrap_or(Timeouts::default().epoch_check_period)
+    }
+
+    pub fn health_check_period(&self) -> Duration {
+        self.rules
+            .health_check_period
+            .unwrap_or(Timeouts::default().health_check_period)
+    }
+
+    pub fn min_balance_amount(&self) -> f64 {
+        self.rules.min_balance_amount.unwrap_or_default()
+    }
+
+    pub fn critical_excess_of_skip_rate(&self) -> f64 {
+        self.rules.critical_excess_of_skip_rate.unwrap_or_default()
+    }
+
+    pub fn identity_balance_delta(&self) -> f64 {
+        self.rules
+            .identity_balance_delta
+            .unwrap_or(DEFAULT_IDENTITY_BALANCE_DELTA)
+    }
+
+    pub fn vote_balance_delta(&self) -> f64 {
+        self.rules
+            .vote_balance_delta
+            .unwrap_or(DEFAULT_VOTE_BALANCE_DELTA)
+    }
+}
+
+/// Check intervals and alert thresholds. Values in `defaults` apply to every node,
+/// values set on a node override them; intervals fall back to `timeouts`.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct CheckRules {
+    #[serde(with = "humantime_serde", skip_serializing_if = "Option::is_none")]
+    pub deliquency_check_period: Option<Duration>,
+    #[serde(with = "humantime_serde", skip_serializing_if = "Option::is_none")]
+    pub balance_check_period: Option<Duration>,
+    #[serde(with = "humantime_serde", skip_serializing_if = "Option::is_none")]
+    pub epoch_check_period: Option<Duration>,
+    #[serde(with = "humantime_serde", skip_serializing_if = "Option::is_none")]
+    pub health_check_period: Option<Duration>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_balance_amount: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub critical_excess_of_skip_rate: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub identity_balance_delta: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vote_balance_delta: Option<f64>,
+}
+
+impl CheckRules {
+    /// Fills every unset value from `fallback`.
+    fn or(&self, fallback: &CheckRules) -> CheckRules {
+        CheckRules {
+            deliquency_check_period: self
+                .deliquency_check_period
+                .or(fallback.deliquency_check_period),
+            balance_check_period: self.balance_check_period.or(fallback.balance_check_period),
+            epoch_check_period: self.epoch_check_period.or(fallback.epoch_check_period),
+            health_check_period: self.health_check_period.or(fallback.health_check_period),
+            min_balance_amount: self.min_balance_amount.or(fallback.min_balance_amount),
+            critical_excess_of_skip_rate: self
+                .critical_excess_of_skip_rate
+                .or(fallback.critical_excess_of_skip_rate),
+            identity_balance_delta: self
+                .identity_balance_delta
+                .or(fallback.identity_balance_delta),
+            vote_balance_delta: self.vote_balance_delta.or(fallback.vote_balance_delta),
+        }
+    }
+}
+
+impl Settings {
+    /// Resolves the rules of every node: node values, then the defaults of its cluster,
+    /// then `defaults`, then `timeouts`.
+    pub fn apply_defaults(&mut self) {
+        let defaults = self.defaults.or(&CheckRules {
+            deliquency_check_period: Some(self.timeouts.deliquency_check_period),
+            balance_check_period: Some(self.timeouts.balance_check_period),
+            epoch_check_period: Some(self.timeouts.epoch_check_period),
+            health_check_period: Some(self.timeouts.health_check_period),
+            ..CheckRules::default()
+        });
+        for node in self.nodes.iter_mut() {
+            let cluster = node
+                .validator
+                .cluster
+                .as_ref()
+                .and_then(|cluster| self.clusters.get(cluster));
+            if let Some(cluster) = cluster {
+                node.rules = node.rules.or(&cluster.defaults);
+            }
+            node.rules = node.rules.or(&defaults);
+        }
+    }
+}
+
+#[derive(Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Validator {
+    pub name: String,
+    pub identity: String,
+    pub vote: String,
+    /// May carry an API key resolved from the environment or a file.
+    pub rpc: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cluster: Option<String>,
+}
+
+impl fmt::Debug for Validator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Validator")
+            .field("name", &self.name)
+            .field("identity", &self.identity)
+            .field("vote", &self.vote)
+            .field("rpc", &Redacted(&self.rpc))
+            .field("cluster", &self.cluster)
+            .finish()
+    }
+}
+
+impl Validator {
+    /// Name used in messages, with the cluster when the validator belongs to one.
+    pub fn label(&self) -> String {
+        cluster_label(&self.name, self.cluster.as_deref())
+    }
+}
+
+pub fn cluster_label(name: &str, cluster: Option<&str>) -> String {
+    match cluster {
+        Some(cluster) => format!("{} ({})", name, cluster),
+        None => name.to_string(),
+    }
+}
+
+/// A cluster validators can belong to, e.g. `mainnet-beta` or `testnet`.
+#[derive(Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClusterSettings {
+    /// Reference RPC for cluster-wide numbers such as the stake-weighted skip rate.
+    pub rpc: String,
+    /// Rules for the validators of this cluster, between node values and global `defaults`.
+    #[serde(default)]
+    pub defaults: CheckRules,
+}
+
+impl fmt::Debug for ClusterSettings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ClusterSettings")
+            .field("rpc", &Redacted(&self.rpc))
+            .field("defaults", &self.defaults)
+            .finish()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Reports {
+    pub export: Option<ReportExport>,
+    pub full: Option<ReportSchedule>,
+    pub digest: Option<ReportSchedule>,
+    pub daily: Option<ReportSchedule>,
+    pub charts: bool,
+}
+
+impl Default for Reports {
+    fn default() -> Self {
+        Reports {
+            export: None,
+            full: Some(ReportSchedule {
+                cron: "0 0 * * * *".to_string(),
+                timezone: default_timezone(),
+            }),
+            digest: None,
+            daily: None,
+            charts: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReportSchedule {
+    pub cron: String,
+    #[serde(default = "default_timezone")]
+    pub timezone: String,
+}
+
+fn default_timezone() -> String {
+    "UTC".to_string()
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReportExport {
+    pub dir: String,
+    pub formats: Vec<ReportFormat>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RewardsSettings {
+    pub history_path: String,
+    pub trend_epochs: usize,
+    pub alert_drop_percent: f64,
+    /// Epoch checks that look for the rewards of a finished epoch before giving up on it,
+    /// e.g. when the vote account earned none.
+    pub max_attempts: u32,
+}
+
+impl Default for RewardsSettings {
+    fn default() -> Self {
+        RewardsSettings {
+            history_path: "rewards.json".to_string(),
+            trend_epochs: 5,
+            alert_drop_percent: 20.,
+            max_attempts: 60,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RevenueSettings {
+    pub dir: String,
+}
+
+impl Default for RevenueSettings {
+    fn default() -> Self {
+        RevenueSettings {
+            dir: "revenue".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct StorageSettings {
+    pub enabled: bool,
+    pub dir: String,
+    #[serde(with = "humantime_serde")]
+    pub retention: Duration,
+}
+
+impl Default for StorageSettings {
+    fn default() -> Self {
+        StorageSettings {
+            enabled: true,
+            dir: "history".to_string(),
+            retention: Duration::from_secs(30 * 24 * 3600),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HttpSettings {
+    pub listen: String,
+    #[serde(default = "default_true")]
+    pub dashboard: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// Signs of life of the bot itself, so a dead host doesn't look like a quiet night.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HeartbeatSettings {
+    /// Time between two pings and two peer polls.
+    #[serde(with = "humantime_serde", default = "default_heartbeat_interval")]
+    pub interval: Duration,
+    /// URL requested on every heartbeat while all checkers are alive, e.g. a dead man's
+    /// switch service that alerts when the pings stop.
+    #[serde(default)]
+    pub ping_url: Option<Secret>,
+    /// Period of the "still alive" message to the report chat.
+    #[serde(with = "humantime_serde", default)]
+    pub digest: Option<Duration>,
+    /// Another bot instance to watch.
+    #[serde(default)]
+    pub peer: Option<PeerSettings>,
+}
+
+fn default_heartbeat_interval() -> Duration {
+    Duration::from_secs(60)
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PeerSettings {
+    /// The `/health` endpoint of the peer's HTTP API.
+    pub url: String,
+    /// Silence after which the peer is reported.
+    #[serde(with = "humantime_serde", default = "default_peer_timeout")]
+    pub timeout: Duration,
+}
+
+fn default_peer_timeout() -> Duration {
+    Duration::from_secs(300)
+}
+
+/// Several instances watching the same validators, of which only the holder of the
+/// lease in `lockPath` notifies.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HaSettings {
+    /// Lock file on a filesystem shared by the instances.
+    pub lock_path: String,
+    /// Name of this instance in the lock file, the hostname by default.
+    #[serde(default)]
+    pub instance: Option<String>,
+    /// Time the lease stays valid without renewal, the longest a failover takes.
+    #[serde(with = "humantime_serde", default = "default_lease")]
+    pub lease: Duration,
+}
+
+fn default_lease() -> Duration {
+    Duration::from_secs(30)
+}
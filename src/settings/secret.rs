@@ -0,0 +1,280 @@
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
+use std::fmt;
+use std::sync::RwLock;
+
+use crate::settings::SettingsProblem;
+
+const REDACTED: &str = "***";
+
+/// Values that must never reach the logs, filled while the settings are loaded.
+static SECRETS: RwLock<Vec<String>> = RwLock::new(Vec::new());
+
+/// Shortest resolved `${VAR}` or `file:` value taken for a secret. Shorter ones, such
+/// as a port or a name, would blank those characters out of every log line.
+const MIN_RESOLVED_SECRET: usize = 8;
+
+fn register(secret: &str) {
+    if secret.is_empty() {
+        return;
+    }
+    let mut secrets = SECRETS.write().unwrap_or_else(|e| e.into_inner());
+    if !secrets.iter().any(|s| s == secret) {
+        secrets.push(secret.to_string());
+    }
+}
+
+/// Registers a value resolved from the environment or a file, unless it is too short
+/// to be a secret. Values of [`Secret`] fields are registered whatever their length.
+fn register_resolved(value: &str) {
+    if value.chars().count() >= MIN_RESOLVED_SECRET {
+        register(value);
+    }
+}
+
+/// Replaces every known secret in `text` with `***`.
+pub fn redact(text: &str) -> String {
+    let secrets = SECRETS.read().unwrap_or_else(|e| e.into_inner());
+    secrets.iter().fold(text.to_string(), |text, secret| {
+        text.replace(secret, REDACTED)
+    })
+}
+
+/// Debug output of a value with every known secret redacted, for settings whose
+/// strings may hold resolved `${VAR}` or `file:` values.
+pub struct Redacted<'a, T>(pub &'a T);
+
+impl<T: fmt::Debug> fmt::Debug for Redacted<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = if f.alternate() {
+            format!("{:#?}", self.0)
+        } else {
+            format!("{:?}", self.0)
+        };
+        f.write_str(&redact(&text))
+    }
+}
+
+/// A settings string that is hidden from `Debug` output and serialization.
+#[derive(Default, Clone, PartialEq, Eq)]
+pub struct Secret(String);
+
+impl Secret {
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Secret({})", REDACTED)
+    }
+}
+
+impl Serialize for Secret {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(REDACTED)
+    }
+}
+
+impl<'de> Deserialize<'de> for Secret {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        register(&value);
+        Ok(Secret(value))
+    }
+}
+
+/// Resolves `${ENV_VAR}` references and `file:/path` values in every string of the
+/// settings document. Resolved values are treated as secrets and redacted in logs.
+pub fn resolve(value: &mut Value) -> Result<(), Vec<SettingsProblem>> {
+    let mut problems = Vec::new();
+    resolve_value(value, String::new(), &mut problems);
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(problems)
+    }
+}
+
+fn resolve_value(value: &mut Value, path: String, problems: &mut Vec<SettingsProblem>) {
+    match value {
+        Value::String(text) => match resolve_string(text) {
+            Ok(Some(resolved)) => *text = resolved,
+            Ok(None) => {}
+            Err(message) => problems.push(SettingsProblem { path, message }),
+        },
+        Value::Array(items) => {
+            for (index, item) in items.iter_mut().enumerate() {
+                resolve_value(item, format!("{}[{}]", path, index), problems);
+            }
+        }
+        Value::Object(fields) => {
+            for (key, field) in fields.iter_mut() {
+                let path = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", path, key)
+                };
+                resolve_value(field, path, problems);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn resolve_string(text: &str) -> Result<Option<String>, String> {
+    if let Some(file) = text.strip_prefix("file:") {
+        let content = std::fs::read_to_string(file)
+            .map_err(|e| format!("failed to read secret file {}: {}", file, e))?;
+        let content = content.trim_end_matches(['\r', '\n']).to_string();
+        register_resolved(&content);
+        return Ok(Some(content));
+    }
+    if !text.contains("${") {
+        return Ok(None);
+    }
+
+    let mut resolved = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("${") {
+        resolved.push_str(&rest[..start]);
+        let tail = &rest[start + 2..];
+        let end = tail
+            .find('}')
+            .ok_or_else(|| format!("unterminated variable reference in '{}'", text))?;
+        let name = &tail[..end];
+        let variable =
+            std::env::var(name).map_err(|_| format!("environment variable {} is not set", name))?;
+        register_resolved(&variable);
+        resolved.push_str(&variable);
+        rest = &tail[end + 1..];
+    }
+    resolved.push_str(rest);
+    Ok(Some(resolved))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::{ClusterSettings, NodeCheckSettings, Settings, Validator};
+    use serde_json::json;
+
+    fn resolved(mut document: Value) -> Value {
+        resolve(&mut document).expect("resolved");
+        document
+    }
+
+    #[test]
+    fn environment_variables_are_resolved_and_redacted() {
+        std::env::set_var("SOLANA_BOT_TEST_ENV_KEY", "env-key-1f2e");
+        let document = resolved(json!({
+            "nodes": [{"rpc": "https://host/?api-key=${SOLANA_BOT_TEST_ENV_KEY}&x=1"}],
+            "plain": "https://api.mainnet-beta.solana.com",
+        }));
+
+        assert_eq!(
+            document["nodes"][0]["rpc"],
+            "https://host/?api-key=env-key-1f2e&x=1"
+        );
+        assert_eq!(document["plain"], "https://api.mainnet-beta.solana.com");
+        assert_eq!(
+            redact("GET https://host/?api-key=env-key-1f2e failed"),
+            "GET https://host/?api-key=*** failed"
+        );
+        assert_eq!(
+            redact("https://api.mainnet-beta.solana.com"),
+            "https://api.mainnet-beta.solana.com"
+        );
+    }
+
+    #[test]
+    fn short_values_are_resolved_but_not_redacted() {
+        std::env::set_var("SOLANA_BOT_TEST_PORT", "8080");
+        std::env::set_var("SOLANA_BOT_TEST_NAME", "node");
+        let document = resolved(json!({
+            "http": {"listen": "127.0.0.1:${SOLANA_BOT_TEST_PORT}"},
+            "nodes": [{"name": "${SOLANA_BOT_TEST_NAME}"}],
+        }));
+
+        assert_eq!(document["http"]["listen"], "127.0.0.1:8080");
+        assert_eq!(document["nodes"][0]["name"], "node");
+        assert_eq!(
+            redact("node listening on 127.0.0.1:8080"),
+            "node listening on 127.0.0.1:8080"
+        );
+
+        // A short value in a secret field is still redacted.
+        let secret: Secret = serde_json::from_value(json!("k3y")).unwrap();
+        assert_eq!(secret.expose(), "k3y");
+        assert_eq!(redact("key k3y"), "key ***");
+    }
+
+    #[test]
+    fn files_are_read_without_trailing_newlines() {
+        let path =
+            std::env::temp_dir().join(format!("solana-bot-secret-{}.txt", std::process::id()));
+        std::fs::write(&path, "file-token-9a8b\r\n").unwrap();
+        let document = resolved(json!({"telegram": {"token": format!("file:{}", path.display())}}));
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(document["telegram"]["token"], "file-token-9a8b");
+        assert_eq!(redact("token file-token-9a8b"), "token ***");
+    }
+
+    #[test]
+    fn unresolvable_values_are_reported_with_their_path() {
+        let mut document = json!({
+            "telegram": {"token": "${SOLANA_BOT_TEST_UNSET_VARIABLE}"},
+            "nodes": [{}, {"rpc": "file:/nonexistent/solana-bot/key"}],
+            "http": {"listen": "${UNTERMINATED"},
+        });
+        let problems = resolve(&mut document).unwrap_err();
+        let paths: Vec<&str> = problems.iter().map(|p| p.path.as_str()).collect();
+
+        assert_eq!(paths, vec!["http.listen", "nodes[1].rpc", "telegram.token"]);
+        assert!(problems[0].message.contains("unterminated"));
+        assert!(problems[1].message.contains("/nonexistent/solana-bot/key"));
+        assert_eq!(
+            problems[2].message,
+            "environment variable SOLANA_BOT_TEST_UNSET_VARIABLE is not set"
+        );
+    }
+
+    #[test]
+    fn debug_output_redacts_resolved_values() {
+        std::env::set_var("SOLANA_BOT_TEST_DEBUG_KEY", "debug-key-7c6d");
+        let document = resolved(json!({
+            "validator": {
+                "name": "node-1",
+                "identity": "identity",
+                "vote": "vote",
+                "rpc": "https://host/?api-key=${SOLANA_BOT_TEST_DEBUG_KEY}",
+            },
+            "cluster": {"rpc": "https://cluster/${SOLANA_BOT_TEST_DEBUG_KEY}"},
+            "apiUrl": "https://proxy/${SOLANA_BOT_TEST_DEBUG_KEY}",
+        }));
+        let validator: Validator = serde_json::from_value(document["validator"].clone()).unwrap();
+        let cluster: ClusterSettings = serde_json::from_value(document["cluster"].clone()).unwrap();
+        let mut settings = Settings {
+            nodes: vec![NodeCheckSettings {
+                validator: validator.clone(),
+                ..NodeCheckSettings::default()
+            }],
+            ..Settings::default()
+        };
+        settings.clusters.insert("testnet".to_string(), cluster);
+        settings.telegram.api_url = document["apiUrl"].as_str().unwrap().to_string();
+
+        for debug in [
+            format!("{:?}", validator),
+            format!("{:?}", settings),
+            format!("{:#?}", settings),
+        ] {
+            assert!(!debug.contains("debug-key-7c6d"), "{}", debug);
+            assert!(debug.contains("api-key=***"), "{}", debug);
+        }
+        assert!(format!("{:?}", settings).contains("https://proxy/***"));
+        assert!(format!("{:?}", settings).contains("https://cluster/***"));
+    }
+}
@@ -0,0 +1,511 @@
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+use std::fmt;
+use std::net::SocketAddr;
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::settings::secret::redact;
+use crate::settings::{CheckRules, ReportSchedule, Settings};
+
+/// A single invalid value, addressed by its path in the settings JSON.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SettingsProblem {
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for SettingsProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+#[derive(Default)]
+struct Problems(Vec<SettingsProblem>);
+
+impl Problems {
+    fn push(&mut self, path: impl Into<String>, message: impl Into<String>) {
+        self.0.push(SettingsProblem {
+            path: path.into(),
+            message: message.into(),
+        });
+    }
+
+    fn pubkey(&mut self, path: String, value: &str) {
+        if let Err(e) = Pubkey::from_str(value) {
+            self.push(path, format!("invalid pubkey '{}': {}", value, e));
+        }
+    }
+
+    fn url(&mut self, path: String, value: &str) {
+        match url::Url::parse(value) {
+            Ok(url) if url.scheme() == "http" || url.scheme() == "https" => {}
+            Ok(url) => self.push(path, format!("unsupported URL scheme '{}'", url.scheme())),
+            Err(e) => self.push(path, format!("invalid URL '{}': {}", redact(value), e)),
+        }
+    }
+
+    fn duration(&mut self, path: &str, value: Duration) {
+        if value.is_zero() {
+            self.push(path, "must be greater than zero");
+        }
+    }
+
+    fn non_negative(&mut self, path: String, value: f64) {
+        if !value.is_finite() || value < 0. {
+            self.push(
+                path,
+                format!("must be a non-negative number, got {}", value),
+            );
+        }
+    }
+
+    fn not_empty(&mut self, path: &str, value: &str) {
+        if value.trim().is_empty() {
+            self.push(path, "must not be empty");
+        }
+    }
+
+    fn rules(&mut self, path: &str, rules: &CheckRules) {
+        for (key, period) in [
+            ("deliquencyCheckPeriod", rules.deliquency_check_period),
+            ("balanceCheckPeriod", rules.balance_check_period),
+            ("epochCheckPeriod", rules.epoch_check_period),
+            ("healthCheckPeriod", rules.health_check_period),
+        ] {
+            if let Some(period) = period {
+                self.duration(&format!("{}.{}", path, key), period);
+            }
+        }
+        for (key, value) in [
+            ("minBalanceAmount", rules.min_balance_amount),
+            (
+                "criticalExcessOfSkipRate",
+                rules.critical_excess_of_skip_rate,
+            ),
+            ("identityBalanceDelta", rules.identity_balance_delta),
+            ("voteBalanceDelta", rules.vote_balance_delta),
+        ] {
+            if let Some(value) = value {
+                self.non_negative(format!("{}.{}", path, key), value);
+            }
+        }
+    }
+
+    fn schedule(&mut self, path: &str, schedule: &Option<ReportSchedule>) {
+        if let Some(schedule) = schedule {
+            if let Err(e) = schedule.parse() {
+                self.push(path, e);
+            }
+        }
+    }
+}
+
+impl Settings {
+    /// Checks every value that deserialization cannot, collecting all problems at once.
+    pub fn validate(&self) -> Result<(), Vec<SettingsProblem>> {
+        let mut problems = Problems::default();
+
+        let token = self.telegram.token.expose();
+        problems.not_empty("telegram.token", token);
+        if !token.is_empty() && !token.contains(':') {
+            problems.push(
+                "telegram.token",
+                "expected a bot token like '123456:ABC...'",
+            );
+        }
+        problems.url("telegram.apiUrl".to_string(), &self.telegram.api_url);
+        if self.telegram.chat_id == 0 {
+            problems.push("telegram.chatId", "must be set");
+        }
+        if self.telegram.alert_chat_id == 0 {
+            problems.push("telegram.alertChatId", "must be set");
+        }
+        let queue = &self.telegram.queue;
+        if queue.messages_per_second == 0 {
+            problems.push(
+                "telegram.queue.messagesPerSecond",
+                "must be greater than zero",
+            );
+        }
+        problems.duration("telegram.queue.retryBackoff", queue.retry_backoff);
+        if queue.max_backoff < queue.retry_backoff {
+            problems.push(
+                "telegram.queue.maxBackoff",
+                "must not be shorter than retryBackoff",
+            );
+        }
+        if queue.max_attempts == 0 {
+            problems.push("telegram.queue.maxAttempts", "must be greater than zero");
+        }
+        problems.not_empty("telegram.queue.spoolPath", &queue.spool_path);
+
+        problems.duration(
+            "timeouts.deliquencyCheckPeriod",
+            self.timeouts.deliquency_check_period,
+        );
+        problems.duration(
+            "timeouts.balanceCheckPeriod",
+            self.timeouts.balance_check_period,
+        );
+        problems.duration(
+            "timeouts.epochCheckPeriod",
+            self.timeouts.epoch_check_period,
+        );
+        problems.duration(
+            "timeouts.healthCheckPeriod",
+            self.timeouts.health_check_period,
+        );
+
+        problems.duration("rpc.timeout", self.rpc.timeout);
+        if self.rpc.max_concurrent_requests == 0 {
+            problems.push("rpc.maxConcurrentRequests", "must be greater than zero");
+        }
+
+        problems.duration("shutdown.timeout", self.shutdown.timeout);
+
+        problems.rules("defaults", &self.defaults);
+        for (name, cluster) in &self.clusters {
+            let path = format!("clusters.{}", name);
+            problems.url(format!("{}.rpc", path), &cluster.rpc);
+            problems.rules(&format!("{}.defaults", path), &cluster.defaults);
+        }
+
+        if self.nodes.is_empty() {
+            problems.push("nodes", "no validators configured");
+        }
+        let mut names: HashMap<&str, usize> = HashMap::new();
+        for (index, node) in self.nodes.iter().enumerate() {
+            let path = format!("nodes[{}]", index);
+            let validator = &node.validator;
+            problems.not_empty(&format!("{}.validator.name", path), &validator.name);
+            if let Some(first) = names.insert(validator.name.as_str(), index) {
+                problems.push(
+                    format!("{}.validator.name", path),
+                    format!(
+                        "duplicate name '{}', also used by nodes[{}]",
+                        validator.name, first
+                    ),
+                );
+            }
+            problems.pubkey(format!("{}.validator.identity", path), &validator.identity);
+            problems.pubkey(format!("{}.validator.vote", path), &validator.vote);
+            problems.url(format!("{}.validator.rpc", path), &validator.rpc);
+            problems.rules(&path, &node.rules);
+            let cluster = match &validator.cluster {
+                Some(name) => {
+                    let cluster = self.clusters.get(name);
+                    if cluster.is_none() {
+                        problems.push(
+                            format!("{}.validator.cluster", path),
+                            format!("unknown cluster '{}'", name),
+                        );
+                    }
+                    cluster
+                }
+                None => None,
+            };
+            let cluster_defaults = cluster.map(|cluster| &cluster.defaults);
+            if node.rules.min_balance_amount.is_none()
+                && cluster_defaults.is_none_or(|rules| rules.min_balance_amount.is_none())
+                && self.defaults.min_balance_amount.is_none()
+            {
+                problems.push(
+                    format!("{}.minBalanceAmount", path),
+                    "must be set here or in defaults",
+                );
+            }
+            if node.rules.critical_excess_of_skip_rate.is_none()
+                && cluster_defaults.is_none_or(|rules| rules.critical_excess_of_skip_rate.is_none())
+                && self.defaults.critical_excess_of_skip_rate.is_none()
+            {
+                problems.push(
+                    format!("{}.criticalExcessOfSkipRate", path),
+                    "must be set here or in defaults",
+                );
+            }
+        }
+
+        for (index, balance) in self.balances.iter().enumerate() {
+            problems.pubkey(format!("balances[{}]", index), balance);
+        }
+
+        problems.schedule("reports.full", &self.reports.full);
+        problems.schedule("reports.digest", &self.reports.digest);
+        problems.schedule("reports.daily", &self.reports.daily);
+        if let Some(export) = &self.reports.export {
+            problems.not_empty("reports.export.dir", &export.dir);
+            if export.formats.is_empty() {
+                problems.push("reports.export.formats", "must list at least one format");
+            }
+        }
+
+        problems.not_empty("rewards.historyPath", &self.rewards.history_path);
+        if self.rewards.trend_epochs == 0 {
+            problems.push("rewards.trendEpochs", "must be greater than zero");
+        }
+        if !(0. ..=100.).contains(&self.rewards.alert_drop_percent) {
+            problems.push(
+                "rewards.alertDropPercent",
+                format!(
+                    "must be between 0 and 100, got {}",
+                    self.rewards.alert_drop_percent
+                ),
+            );
+        }
+
+        problems.not_empty("revenue.dir", &self.revenue.dir);
+        if self.storage.enabled {
+            problems.not_empty("storage.dir", &self.storage.dir);
+            problems.duration("storage.retention", self.storage.retention);
+        }
+
+        if let Some(http) = &self.http {
+            if let Err(e) = SocketAddr::from_str(&http.listen) {
+                problems.push(
+                    "http.listen",
+                    format!("invalid address '{}': {}", http.listen, e),
+                );
+            }
+        }
+
+        if let Some(heartbeat) = &self.heartbeat {
+            problems.duration("heartbeat.interval", heartbeat.interval);
+            if let Some(ping_url) = &heartbeat.ping_url {
+                problems.url("heartbeat.pingUrl".to_string(), ping_url.expose());
+            }
+            if let Some(digest) = heartbeat.digest {
+                problems.duration("heartbeat.digest", digest);
+            }
+            if let Some(peer) = &heartbeat.peer {
+                problems.url("heartbeat.peer.url".to_string(), &peer.url);
+                problems.duration("heartbeat.peer.timeout", peer.timeout);
+            }
+        }
+
+        if let Some(ha) = &self.ha {
+            problems.not_empty("ha.lockPath", &ha.lock_path);
+            if let Some(instance) = &ha.instance {
+                problems.not_empty("ha.instance", instance);
+            }
+            problems.duration("ha.lease", ha.lease);
+        }
+
+        if problems.0.is_empty() {
+            Ok(())
+        } else {
+            Err(problems.0)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::{ClusterSettings, NodeCheckSettings, Validator};
+    use solana_sdk::pubkey::Pubkey;
+
+    fn node(name: &str) -> NodeCheckSettings {
+        NodeCheckSettings {
+            validator: Validator {
+                name: name.to_string(),
+                identity: Pubkey::new_unique().to_string(),
+                vote: Pubkey::new_unique().to_string(),
+                rpc: "http://127.0.0.1:8899".to_string(),
+                cluster: None,
+            },
+            rules: CheckRules::default(),
+            disabled_reports: vec![],
+        }
+    }
+
+    fn settings() -> Settings {
+        let mut settings = Settings {
+            nodes: vec![node("node-1"), node("node-2")],
+            ..Settings::default()
+        };
+        settings.telegram.token = serde_json::from_str("\"123456:token\"").unwrap();
+        settings.telegram.chat_id = 1;
+        settings.telegram.alert_chat_id = 2;
+        settings.defaults.min_balance_amount = Some(1.);
+        settings.defaults.critical_excess_of_skip_rate = Some(10.);
+        settings
+    }
+
+    /// Paths of the problems of `settings`, none if they are valid.
+    fn problems(settings: &Settings) -> Vec<String> {
+        match settings.validate() {
+            Ok(()) => vec![],
+            Err(problems) => problems.into_iter().map(|problem| problem.path).collect(),
+        }
+    }
+
+    #[test]
+    fn default_settings_with_validators_are_valid() {
+        assert_eq!(settings().validate(), Ok(()));
+    }
+
+    #[test]
+    fn invalid_pubkeys_are_reported() {
+        let mut settings = settings();
+        settings.nodes[1].validator.identity = "not-a-key".to_string();
+        settings.nodes[0].validator.vote = String::new();
+        settings.balances = vec![Pubkey::new_unique().to_string(), "0OIl".to_string()];
+
+        assert_eq!(
+            problems(&settings),
+            [
+                "nodes[0].validator.vote",
+                "nodes[1].validator.identity",
+                "balances[1]"
+            ]
+        );
+        let problem = &settings.validate().unwrap_err()[1];
+        assert!(problem.message.starts_with("invalid pubkey 'not-a-key'"));
+    }
+
+    #[test]
+    fn bad_urls_are_reported() {
+        let mut settings = settings();
+        settings.nodes[0].validator.rpc = "127.0.0.1:8899".to_string();
+        settings.nodes[1].validator.rpc = "ws://127.0.0.1:8900".to_string();
+        settings.telegram.api_url = "not a url".to_string();
+
+        assert_eq!(
+            problems(&settings),
+            [
+                "telegram.apiUrl",
+                "nodes[0].validator.rpc",
+                "nodes[1].validator.rpc"
+            ]
+        );
+        let problem = &settings.validate().unwrap_err()[2];
+        assert_eq!(problem.message, "unsupported URL scheme 'ws'");
+    }
+
+    #[test]
+    fn negative_thresholds_are_reported_and_zero_is_allowed() {
+        let mut settings = settings();
+        settings.defaults.min_balance_amount = Some(0.);
+        settings.defaults.vote_balance_delta = Some(-1.);
+        settings.nodes[1].rules.critical_excess_of_skip_rate = Some(f64::NAN);
+        settings.nodes[0].rules.identity_balance_delta = Some(-0.05);
+
+        assert_eq!(
+            problems(&settings),
+            [
+                "defaults.voteBalanceDelta",
+                "nodes[0].identityBalanceDelta",
+                "nodes[1].criticalExcessOfSkipRate"
+            ]
+        );
+    }
+
+    #[test]
+    fn zero_counts_are_reported() {
+        let mut settings = settings();
+        settings.telegram.queue.messages_per_second = 0;
+        settings.rpc.max_concurrent_requests = 0;
+        settings.rewards.trend_epochs = 0;
+
+        assert_eq!(
+            problems(&settings),
+            [
+                "telegram.queue.messagesPerSecond",
+                "rpc.maxConcurrentRequests",
+                "rewards.trendEpochs"
+            ]
+        );
+    }
+
+    #[test]
+    fn zero_durations_are_reported() {
+        let mut settings = settings();
+        settings.timeouts.balance_check_period = Duration::ZERO;
+        settings.rpc.timeout = Duration::ZERO;
+        settings.defaults.epoch_check_period = Some(Duration::ZERO);
+        settings.nodes[1].rules.health_check_period = Some(Duration::ZERO);
+        settings.storage.retention = Duration::ZERO;
+
+        assert_eq!(
+            problems(&settings),
+            [
+                "timeouts.balanceCheckPeriod",
+                "rpc.timeout",
+                "defaults.epochCheckPeriod",
+                "nodes[1].healthCheckPeriod",
+                "storage.retention"
+            ]
+        );
+    }
+
+    #[test]
+    fn duplicate_and_empty_names_are_reported() {
+        let mut settings = settings();
+        settings.nodes.push(node("node-1"));
+        settings.nodes.push(node(" "));
+
+        assert_eq!(
+            problems(&settings),
+            ["nodes[2].validator.name", "nodes[3].validator.name"]
+        );
+        let problem = &settings.validate().unwrap_err()[0];
+        assert_eq!(
+            problem.message,
+            "duplicate name 'node-1', also used by nodes[0]"
+        );
+    }
+
+    #[test]
+    fn unknown_clusters_are_reported() {
+        let mut settings = settings();
+        settings.clusters.insert(
+            "testnet".to_string(),
+            ClusterSettings {
+                rpc: "https://api.testnet.solana.com".to_string(),
+                defaults: CheckRules::default(),
+            },
+        );
+        settings.nodes[0].validator.cluster = Some("testnet".to_string());
+        settings.nodes[1].validator.cluster = Some("devnet".to_string());
+
+        assert_eq!(problems(&settings), ["nodes[1].validator.cluster"]);
+        let problem = &settings.validate().unwrap_err()[0];
+        assert_eq!(problem.message, "unknown cluster 'devnet'");
+    }
+
+    #[test]
+    fn rules_resolve_through_the_cluster_defaults() {
+        let mut settings = settings();
+        settings.defaults.min_balance_amount = None;
+        settings.clusters.insert(
+            "testnet".to_string(),
+            ClusterSettings {
+                rpc: "https://api.testnet.solana.com".to_string(),
+                defaults: CheckRules {
+                    min_balance_amount: Some(0.5),
+                    ..CheckRules::default()
+                },
+            },
+        );
+        settings.nodes[0].validator.cluster = Some("testnet".to_string());
+
+        assert_eq!(problems(&settings), ["nodes[1].minBalanceAmount"]);
+    }
+
+    #[test]
+    fn missing_telegram_settings_and_validators_are_reported() {
+        let settings = Settings::default();
+
+        assert_eq!(
+            problems(&settings),
+            [
+                "telegram.token",
+                "telegram.chatId",
+                "telegram.alertChatId",
+                "nodes"
+            ]
+        );
+    }
+}
@@ -0,0 +1,49 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::client::RpcLimits;
+use crate::events::EventBus;
+use crate::ha::{ActiveOnly, Leadership};
+use crate::logger::EventLog;
+use crate::notifier::Notifier;
+use crate::revenue::RevenueBook;
+use crate::settings::Settings;
+use crate::status::StatusRegistry;
+use crate::storage::HistoryStore;
+
+/// State shared between checker tasks and the HTTP server.
+#[derive(Clone)]
+pub struct SharedState {
+    pub store: Arc<HistoryStore>,
+    pub revenue: Arc<RevenueBook>,
+    pub status: Arc<StatusRegistry>,
+    pub notifier: Notifier,
+    pub rpc: RpcLimits,
+    pub bus: Arc<EventBus>,
+    pub leadership: Arc<Leadership>,
+}
+
+impl SharedState {
+    /// Opens the stores and subscribes the notifier, the history, the status and the log
+    /// to the bus. With HA the instance starts as a standby, the notifier only hears
+    /// events once it is active.
+    pub fn new(settings: &Settings) -> Self {
+        let state = SharedState {
+            store: Arc::new(HistoryStore::open(&settings.storage, &settings.nodes)),
+            revenue: Arc::new(RevenueBook::open(Path::new(&settings.revenue.dir))),
+            status: Arc::new(StatusRegistry::new(&settings.nodes)),
+            notifier: Notifier::start(&settings.telegram).0,
+            rpc: RpcLimits::new(&settings.rpc),
+            bus: Arc::default(),
+            leadership: Arc::new(Leadership::new(settings.ha.is_none())),
+        };
+        state.bus.subscribe(Arc::new(ActiveOnly::new(
+            &state.leadership,
+            state.notifier.clone(),
+        )));
+        state.bus.subscribe(state.store.clone());
+        state.bus.subscribe(state.status.clone());
+        state.bus.subscribe(Arc::new(EventLog));
+        state
+    }
+}
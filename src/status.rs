@@ -0,0 +1,359 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use crate::events::{Account, Event, Subscriber};
+use crate::report::ValidatorReport;
+use crate::settings::NodeCheckSettings;
+
+const RECENT_ALERTS: usize = 100;
+/// A checker is considered stuck after missing this many periods.
+const MISSED_PERIODS: u32 = 3;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CheckerStatus {
+    pub running: bool,
+    #[serde(with = "humantime_serde")]
+    pub period: Option<Duration>,
+    pub started_at: DateTime<Utc>,
+    pub last_success: Option<DateTime<Utc>>,
+    /// Stopped by returning, e.g. on shutdown, rather than by a panic.
+    #[serde(default)]
+    pub finished: bool,
+}
+
+impl CheckerStatus {
+    pub fn is_alive(&self, now: DateTime<Utc>) -> bool {
+        if !self.running {
+            return false;
+        }
+        let Some(period) = self.period else {
+            return true;
+        };
+        let reference = self.last_success.unwrap_or(self.started_at);
+        match chrono::Duration::from_std(period * MISSED_PERIODS) {
+            Ok(allowed) => now - reference <= allowed,
+            Err(_) => true,
+        }
+    }
+
+    /// Whether the checker should be running but is not, or has missed its periods.
+    /// One that finished on its own is not stuck.
+    pub fn is_stuck(&self, now: DateTime<Utc>) -> bool {
+        !self.finished && !self.is_alive(now)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AlertKind {
+    Delinquent,
+    CriticalSkipRate,
+    LowBalance,
+    IdentityBalanceChanged,
+    VoteBalanceChanged,
+    RewardsBelowTrend,
+    /// The watched peer instance went quiet, `validator` holds its URL.
+    PeerSilent,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Alert {
+    pub id: u64,
+    pub validator: String,
+    pub kind: AlertKind,
+    pub message: String,
+    pub raised_at: DateTime<Utc>,
+    pub resolved_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ValidatorSnapshot {
+    pub name: String,
+    pub cluster: Option<String>,
+    pub delinquent: Option<bool>,
+    pub slot_lag: Option<u64>,
+    pub identity_balance: Option<f64>,
+    pub vote_balance: Option<f64>,
+    pub updated_at: Option<DateTime<Utc>>,
+    pub report: Option<ValidatorReport>,
+}
+
+#[derive(Default)]
+struct AlertLog {
+    next_id: u64,
+    active: Vec<Alert>,
+    recent: VecDeque<Alert>,
+}
+
+/// Live state of the bot shared between checker threads and the HTTP API.
+#[derive(Default)]
+pub struct StatusRegistry {
+    checkers: RwLock<HashMap<String, CheckerStatus>>,
+    validators: RwLock<HashMap<String, ValidatorSnapshot>>,
+    alerts: RwLock<AlertLog>,
+}
+
+/// Marks the checker as stopped when its task exits or panics, and as finished if
+/// it exited without panicking.
+pub struct CheckerGuard {
+    registry: Arc<StatusRegistry>,
+    name: String,
+}
+
+impl Drop for CheckerGuard {
+    fn drop(&mut self) {
+        if let Ok(mut checkers) = self.registry.checkers.write() {
+            if let Some(status) = checkers.get_mut(&self.name) {
+                status.running = false;
+                status.finished = !std::thread::panicking();
+            }
+        }
+    }
+}
+
+impl StatusRegistry {
+    pub fn register_checker(
+        self: &Arc<Self>,
+        name: &str,
+        period: Option<Duration>,
+    ) -> CheckerGuard {
+        self.checkers.write().unwrap().insert(
+            name.to_string(),
+            CheckerStatus {
+                running: true,
+                period,
+                started_at: Utc::now(),
+                last_success: None,
+                finished: false,
+            },
+        );
+        CheckerGuard {
+            registry: self.clone(),
+            name: name.to_string(),
+        }
+    }
+
+    pub fn checker_succeeded(&self, name: &str) {
+        if let Some(status) = self.checkers.write().unwrap().get_mut(name) {
+            status.last_success = Some(Utc::now());
+        }
+    }
+
+    pub fn checkers(&self) -> HashMap<String, CheckerStatus> {
+        self.checkers.read().unwrap().clone()
+    }
+
+    /// Registers the configured validators, so they are listed before their first check.
+    pub fn new(nodes: &[NodeCheckSettings]) -> Self {
+        let registry = StatusRegistry::default();
+        registry
+            .validators
+            .write()
+            .unwrap()
+            .extend(nodes.iter().map(|node| {
+                let snapshot = ValidatorSnapshot {
+                    name: node.validator.name.clone(),
+                    cluster: node.validator.cluster.clone(),
+                    ..Default::default()
+                };
+                (snapshot.name.clone(), snapshot)
+            }));
+        registry
+    }
+
+    pub fn update_validator<F: FnOnce(&mut ValidatorSnapshot)>(&self, name: &str, update: F) {
+        let mut validators = self.validators.write().unwrap();
+        let snapshot = validators
+            .entry(name.to_string())
+            .or_insert_with(|| ValidatorSnapshot {
+                name: name.to_string(),
+                ..Default::default()
+            });
+        update(snapshot);
+        snapshot.updated_at = Some(Utc::now());
+    }
+
+    pub fn validators(&self) -> Vec<ValidatorSnapshot> {
+        let mut validators: Vec<ValidatorSnapshot> =
+            self.validators.read().unwrap().values().cloned().collect();
+        validators.sort_by(|a, b| a.name.cmp(&b.name));
+        validators
+    }
+
+    pub fn validator(&self, name: &str) -> Option<ValidatorSnapshot> {
+        self.validators.read().unwrap().get(name).cloned()
+    }
+
+    /// Opens an alert, or refreshes the message of the active one of the same kind.
+    pub fn raise(&self, validator: &str, kind: AlertKind, message: &str) {
+        let mut log = self.alerts.write().unwrap();
+        if let Some(alert) = log
+            .active
+            .iter_mut()
+            .find(|alert| alert.validator == validator && alert.kind == kind)
+        {
+            alert.message = message.to_string();
+            return;
+        }
+        let alert = log.new_alert(validator, kind, message);
+        log.active.push(alert);
+    }
+
+    pub fn resolve(&self, validator: &str, kind: AlertKind) {
+        let mut log = self.alerts.write().unwrap();
+        let Some(position) = log
+            .active
+            .iter()
+            .position(|alert| alert.validator == validator && alert.kind == kind)
+        else {
+            return;
+        };
+        let mut alert = log.active.remove(position);
+        alert.resolved_at = Some(Utc::now());
+        log.push_recent(alert);
+    }
+
+    /// Records an alert that has no lasting state, such as a balance change.
+    pub fn notify(&self, validator: &str, kind: AlertKind, message: &str) {
+        let mut log = self.alerts.write().unwrap();
+        let mut alert = log.new_alert(validator, kind, message);
+        alert.resolved_at = Some(alert.raised_at);
+        log.push_recent(alert);
+    }
+
+    pub fn is_active(&self, validator: &str, kind: AlertKind) -> bool {
+        self.alerts
+            .read()
+            .unwrap()
+            .active
+            .iter()
+            .any(|alert| alert.validator == validator && alert.kind == kind)
+    }
+
+    pub fn active_alerts(&self) -> Vec<Alert> {
+        self.alerts.read().unwrap().active.clone()
+    }
+
+    pub fn recent_alerts(&self) -> Vec<Alert> {
+        self.alerts.read().unwrap().recent.iter().cloned().collect()
+    }
+}
+
+impl AlertLog {
+    fn new_alert(&mut self, validator: &str, kind: AlertKind, message: &str) -> Alert {
+        self.next_id += 1;
+        Alert {
+            id: self.next_id,
+            validator: validator.to_string(),
+            kind,
+            message: message.to_string(),
+            raised_at: Utc::now(),
+            resolved_at: None,
+        }
+    }
+
+    fn push_recent(&mut self, alert: Alert) {
+        if self.recent.len() == RECENT_ALERTS {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(alert);
+    }
+}
+
+/// Keeps the validator snapshots and the alert log of the HTTP API up to date.
+impl Subscriber for StatusRegistry {
+    fn name(&self) -> &'static str {
+        "status"
+    }
+
+    fn handle(&self, event: &Event) {
+        match event {
+            Event::DelinquencyChecked {
+                validator,
+                delinquent,
+                slot_lag,
+            } => self.update_validator(validator, |snapshot| {
+                snapshot.delinquent = *delinquent;
+                snapshot.slot_lag = *slot_lag;
+            }),
+            Event::BalanceChecked {
+                validator,
+                identity,
+                vote,
+            } => self.update_validator(validator, |snapshot| {
+                snapshot.identity_balance = Some(*identity);
+                snapshot.vote_balance = Some(*vote);
+            }),
+            Event::ReportCollected(report) => self.update_validator(&report.name, |snapshot| {
+                snapshot.report = Some(report.as_ref().clone());
+            }),
+            Event::Delinquent(validator) => self.raise(
+                &validator.name,
+                AlertKind::Delinquent,
+                "Validator is delinquent",
+            ),
+            Event::BalanceChanged {
+                validator,
+                account,
+                previous,
+                current,
+            } => {
+                let (kind, account) = match account {
+                    Account::Identity => (AlertKind::IdentityBalanceChanged, "Identity"),
+                    Account::Vote => (AlertKind::VoteBalanceChanged, "Vote"),
+                };
+                self.notify(
+                    &validator.name,
+                    kind,
+                    &format!(
+                        "{} balance changed {:.3} -> {:.3}",
+                        account, previous, current
+                    ),
+                );
+            }
+            Event::SkipRateCritical {
+                validator,
+                skip_rate,
+                cluster_skip_rate,
+            } => self.raise(
+                &validator.name,
+                AlertKind::CriticalSkipRate,
+                &format!(
+                    "Skip rate {:.2}% exceeds cluster {:.2}%",
+                    skip_rate, cluster_skip_rate
+                ),
+            ),
+            Event::LowBalance {
+                validator, balance, ..
+            } => self.raise(
+                &validator.name,
+                AlertKind::LowBalance,
+                &format!("Identity balance {:.3} is below minimum", balance),
+            ),
+            Event::RewardsBelowTrend {
+                validator,
+                epoch,
+                apy,
+                trend_apy,
+                ..
+            } => self.notify(
+                &validator.name,
+                AlertKind::RewardsBelowTrend,
+                &format!("Epoch {} APY {:.2}% vs trend {:.2}%", epoch, apy, trend_apy),
+            ),
+            Event::PeerSilent { peer, silent_for } => self.raise(
+                peer,
+                AlertKind::PeerSilent,
+                &format!("No heartbeat for {} min", silent_for.as_secs() / 60),
+            ),
+            Event::Recovered { validator, kind } => self.resolve(validator, *kind),
+            _ => {}
+        }
+    }
+}
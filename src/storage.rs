@@ -0,0 +1,214 @@
+use chrono::{DateTime, Duration as ChronoDuration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::events::{Event, Subscriber};
+use crate::settings::{NodeCheckSettings, StorageSettings};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Metric {
+    IdentityBalance,
+    VoteBalance,
+    SkipRate,
+    ClusterSkipRate,
+    Credits,
+    Rank,
+    ActivatedStake,
+    SlotLag,
+    Delinquent,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Sample {
+    #[serde(with = "chrono::serde::ts_seconds")]
+    pub timestamp: DateTime<Utc>,
+    pub validator: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cluster: Option<String>,
+    pub metric: Metric,
+    pub value: f64,
+}
+
+struct Segment {
+    date: NaiveDate,
+    file: File,
+}
+
+/// Append-only time-series store. Samples go to one JSON-lines file per UTC
+/// day, so retention is enforced by deleting whole files.
+pub struct HistoryStore {
+    dir: PathBuf,
+    retention: Duration,
+    enabled: bool,
+    /// Cluster of each validator, stored with its samples.
+    clusters: HashMap<String, String>,
+    segment: Mutex<Option<Segment>>,
+}
+
+impl HistoryStore {
+    pub fn open(settings: &StorageSettings, nodes: &[NodeCheckSettings]) -> Self {
+        let store = HistoryStore {
+            dir: PathBuf::from(&settings.dir),
+            retention: settings.retention,
+            enabled: settings.enabled,
+            clusters: nodes
+                .iter()
+                .filter_map(|node| {
+                    let cluster = node.validator.cluster.clone()?;
+                    Some((node.validator.name.clone(), cluster))
+                })
+                .collect(),
+            segment: Mutex::new(None),
+        };
+        if store.enabled {
+            store.purge();
+        }
+        store
+    }
+
+    fn segment_path(&self, date: NaiveDate) -> PathBuf {
+        self.dir.join(format!("{}.jsonl", date.format("%Y-%m-%d")))
+    }
+
+    fn segment_date(path: &Path) -> Option<NaiveDate> {
+        let stem = path.file_stem()?.to_str()?;
+        NaiveDate::parse_from_str(stem, "%Y-%m-%d").ok()
+    }
+
+    pub fn record(&self, validator: &str, values: &[(Metric, f64)]) {
+        self.record_at(Utc::now(), validator, values);
+    }
+
+    /// Records samples taken at `timestamp`, into the day file of that timestamp.
+    pub fn record_at(&self, timestamp: DateTime<Utc>, validator: &str, values: &[(Metric, f64)]) {
+        if !self.enabled || values.is_empty() {
+            return;
+        }
+        let mut lines = String::new();
+        for (metric, value) in values {
+            let sample = Sample {
+                timestamp,
+                validator: validator.to_string(),
+                cluster: self.clusters.get(validator).cloned(),
+                metric: *metric,
+                value: *value,
+            };
+            match serde_json::to_string(&sample) {
+                Ok(line) => {
+                    lines.push_str(&line);
+                    lines.push('\n');
+                }
+                Err(e) => tracing::error!("Failed to serialize sample {:?}: {}", sample, e),
+            }
+        }
+        if let Err(e) = self.append(timestamp.date_naive(), lines.as_bytes()) {
+            tracing::error!("Failed to write history to {:?}: {}", self.dir, e);
+        }
+    }
+
+    fn append(&self, date: NaiveDate, bytes: &[u8]) -> std::io::Result<()> {
+        let mut segment = self.segment.lock().unwrap();
+        if segment.as_ref().is_none_or(|segment| segment.date != date) {
+            std::fs::create_dir_all(&self.dir)?;
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(self.segment_path(date))?;
+            let rotated = segment.is_some();
+            *segment = Some(Segment { date, file });
+            if rotated {
+                drop(segment);
+                self.purge();
+                segment = self.segment.lock().unwrap();
+            }
+        }
+        segment.as_mut().unwrap().file.write_all(bytes)
+    }
+
+    /// Writes the samples recorded so far through to disk.
+    pub fn flush(&self) {
+        if let Some(segment) = self.segment.lock().unwrap().as_ref() {
+            if let Err(e) = segment.file.sync_all() {
+                tracing::error!("Failed to flush history to {:?}: {}", self.dir, e);
+            }
+        }
+    }
+
+    /// Removes day files that fall completely outside the retention window.
+    pub fn purge(&self) {
+        let Ok(retention) = ChronoDuration::from_std(self.retention) else {
+            return;
+        };
+        let oldest = (Utc::now() - retention).date_naive();
+        let Ok(entries) = std::fs::read_dir(&self.dir) else {
+            return;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if let Some(date) = Self::segment_date(&path) {
+                if date < oldest {
+                    tracing::info!("Remove expired history {:?}", path);
+                    if let Err(e) = std::fs::remove_file(&path) {
+                        tracing::error!("Failed to remove {:?}: {}", path, e);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Samples of `metric` for `validator` within `[since, until]`, oldest first.
+    pub fn query(
+        &self,
+        validator: &str,
+        metric: Metric,
+        since: DateTime<Utc>,
+        until: DateTime<Utc>,
+    ) -> Vec<(DateTime<Utc>, f64)> {
+        let mut points = Vec::new();
+        if !self.enabled {
+            return points;
+        }
+        let mut date = since.date_naive();
+        while date <= until.date_naive() {
+            if let Ok(file) = File::open(self.segment_path(date)) {
+                for line in BufReader::new(file).lines().map_while(Result::ok) {
+                    let Ok(sample) = serde_json::from_str::<Sample>(&line) else {
+                        continue;
+                    };
+                    if sample.metric == metric
+                        && sample.validator == validator
+                        && sample.timestamp >= since
+                        && sample.timestamp <= until
+                    {
+                        points.push((sample.timestamp, sample.value));
+                    }
+                }
+            }
+            let Some(next) = date.succ_opt() else {
+                break;
+            };
+            date = next;
+        }
+        points
+    }
+}
+
+/// Records the metric samples published by the checkers.
+impl Subscriber for HistoryStore {
+    fn name(&self) -> &'static str {
+        "history"
+    }
+
+    fn handle(&self, event: &Event) {
+        if let Event::Measured { validator, samples } = event {
+            self.record(validator, samples);
+        }
+    }
+}
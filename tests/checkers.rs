@@ -0,0 +1,405 @@
+mod common;
+
+use common::mock_rpc::MockRpc;
+use common::*;
+use serde_json::json;
+use solana_bot::checkers::balance_check::BalanceCheck;
+use solana_bot::checkers::deliquency_check::DelinquencyCheck;
+use solana_bot::checkers::epoch_check::EpochCheck;
+use solana_bot::checkers::health_check::HealthCheck;
+use solana_bot::checkers::{node_stats, Checker, ValidatorContext};
+use solana_bot::client::Client;
+use solana_bot::cluster::{ClusterCache, ClusterStats};
+use solana_bot::events::Event;
+use solana_bot::report::epoch::{EpochBaseline, EpochSummary};
+use solana_bot::report::Health;
+use solana_bot::rewards::RewardsHistory;
+use solana_bot::scheduler::Scheduler;
+use solana_bot::state::SharedState;
+use solana_bot::status::{Alert, AlertKind};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+fn active(state: &SharedState, kind: AlertKind) -> Vec<Alert> {
+    state
+        .status
+        .active_alerts()
+        .into_iter()
+        .filter(|alert| alert.kind == kind)
+        .collect()
+}
+
+fn recent(state: &SharedState, kind: AlertKind) -> Vec<Alert> {
+    state
+        .status
+        .recent_alerts()
+        .into_iter()
+        .filter(|alert| alert.kind == kind)
+        .collect()
+}
+
+#[tokio::test]
+async fn delinquency_check_healthy() {
+    let f = Fixture::new("delinquency-healthy");
+    f.publish(DelinquencyCheck.check(&f.context()).await);
+
+    let snapshot = f.state.status.validator("node-1").unwrap();
+    assert_eq!(snapshot.delinquent, Some(false));
+    assert_eq!(snapshot.slot_lag, Some(2));
+    assert!(active(&f.state, AlertKind::Delinquent).is_empty());
+}
+
+#[tokio::test]
+async fn delinquency_check_raises_and_resolves_alert() {
+    let f = Fixture::new("delinquency-alert");
+    let checker = DelinquencyCheck;
+
+    set_vote_accounts(&f.mock, &f.keys, true);
+    f.publish(checker.check(&f.context()).await);
+    assert_eq!(
+        f.state.status.validator("node-1").unwrap().delinquent,
+        Some(true)
+    );
+    assert_eq!(active(&f.state, AlertKind::Delinquent).len(), 1);
+
+    set_vote_accounts(&f.mock, &f.keys, false);
+    f.publish(checker.check(&f.context()).await);
+    assert!(active(&f.state, AlertKind::Delinquent).is_empty());
+}
+
+#[tokio::test]
+async fn delinquency_check_survives_rpc_failure() {
+    let f = Fixture::new("delinquency-failure");
+    f.mock.fail_all(true);
+    f.publish(DelinquencyCheck.check(&f.context()).await);
+
+    let snapshot = f.state.status.validator("node-1").unwrap();
+    assert_eq!(snapshot.delinquent, None);
+    assert_eq!(snapshot.slot_lag, None);
+    assert!(f.state.status.active_alerts().is_empty());
+}
+
+#[tokio::test]
+async fn slow_validator_does_not_delay_others() {
+    let mut f = Fixture::new("delinquency-concurrent");
+    let slow = MockRpc::start();
+    let slow_keys = Keys::new_unique();
+    healthy(&slow, &slow_keys);
+    slow.delay("getVoteAccounts", Duration::from_secs(5));
+    f.settings
+        .nodes
+        .insert(0, node("slow", &slow_keys, &slow.url));
+    for node in f.settings.nodes.iter_mut() {
+        node.rules.deliquency_check_period = Some(Duration::from_millis(100));
+    }
+    let state = SharedState::new(&f.settings);
+
+    let mut scheduler = Scheduler::new(&f.settings.nodes, &state);
+    scheduler.register(Arc::new(DelinquencyCheck));
+    let scheduler = tokio::spawn(scheduler.join());
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    scheduler.abort();
+
+    assert_eq!(
+        state.status.validator("node-1").unwrap().delinquent,
+        Some(false)
+    );
+    assert_eq!(state.status.validator("slow").unwrap().delinquent, None);
+    assert!(f.mock.calls("getVoteAccounts") >= 3);
+}
+
+#[tokio::test]
+async fn balance_check_reports_changes_over_threshold() {
+    let f = Fixture::new("balance-change");
+    let checker = BalanceCheck::default();
+    let context = f.context();
+
+    f.publish(checker.check(&context).await);
+    let snapshot = f.state.status.validator("node-1").unwrap();
+    assert_eq!(snapshot.identity_balance, Some(5.));
+    assert_eq!(snapshot.vote_balance, Some(1.));
+    assert!(f.state.status.recent_alerts().is_empty());
+
+    // 0.01 SOL is below the default identity delta of 0.05 SOL.
+    f.mock.set_balance(
+        &f.keys.identity,
+        5 * LAMPORTS_PER_SOL - LAMPORTS_PER_SOL / 100,
+    );
+    f.publish(checker.check(&context).await);
+    assert!(recent(&f.state, AlertKind::IdentityBalanceChanged).is_empty());
+
+    f.mock.set_balance(&f.keys.identity, 4 * LAMPORTS_PER_SOL);
+    f.mock.set_balance(&f.keys.vote, 2 * LAMPORTS_PER_SOL);
+    f.publish(checker.check(&context).await);
+    assert_eq!(recent(&f.state, AlertKind::IdentityBalanceChanged).len(), 1);
+    assert_eq!(recent(&f.state, AlertKind::VoteBalanceChanged).len(), 1);
+}
+
+#[tokio::test]
+async fn balance_check_uses_per_node_threshold() {
+    let mut f = Fixture::new("balance-threshold");
+    f.settings.nodes[0].rules.identity_balance_delta = Some(2.);
+    let checker = BalanceCheck::default();
+    let context = f.context();
+
+    f.publish(checker.check(&context).await);
+    f.mock.set_balance(&f.keys.identity, 4 * LAMPORTS_PER_SOL);
+    f.publish(checker.check(&context).await);
+    assert!(recent(&f.state, AlertKind::IdentityBalanceChanged).is_empty());
+}
+
+#[tokio::test]
+async fn balance_check_survives_rpc_failure() {
+    let f = Fixture::new("balance-failure");
+    let checker = BalanceCheck::default();
+    let context = f.context();
+
+    f.publish(checker.check(&context).await);
+    f.mock.fail_all(true);
+    f.publish(checker.check(&context).await);
+    f.mock.fail_all(false);
+    f.publish(checker.check(&context).await);
+
+    assert!(f.state.status.recent_alerts().is_empty());
+    let snapshot = f.state.status.validator("node-1").unwrap();
+    assert_eq!(snapshot.identity_balance, Some(5.));
+}
+
+async fn node_report(f: &Fixture) -> solana_bot::report::ValidatorReport {
+    let node = &f.settings.nodes[0];
+    let client = Client::new(&node.validator, &f.state.rpc);
+    let clusters = ClusterCache::new(&f.settings.clusters, &f.state.rpc);
+    let report = node_stats::check(&client, node, &clusters, &f.state).await;
+    assert!(f.state.bus.settle(SETTLE));
+    report
+}
+
+#[tokio::test]
+async fn node_stats_healthy() {
+    let f = Fixture::new("stats-healthy");
+    let report = node_report(&f).await;
+
+    assert_eq!(report.health, Health::Healthy);
+    assert_eq!(report.version, "1.18.26");
+    assert_eq!(report.balances.identity, 5.);
+    assert_eq!(report.credits.rank, 1);
+    assert_eq!(report.credits.credits, 2_000);
+    assert_eq!(report.skip_rate.leader_slots_total, 60);
+    assert_eq!(report.skip_rate.skipped, 0);
+    assert_eq!(report.epoch.epoch, EPOCH.to_string());
+    assert!(f.state.status.active_alerts().is_empty());
+    assert!(f.state.status.validator("node-1").unwrap().report.is_some());
+}
+
+#[tokio::test]
+async fn health_check_raises_and_resolves_low_balance() {
+    let f = Fixture::new("health-low-balance");
+    let checker = HealthCheck::new(&f.settings, &f.state.rpc);
+    f.mock.set_balance(&f.keys.identity, LAMPORTS_PER_SOL / 2);
+    f.publish(checker.check(&f.context()).await);
+    assert_eq!(active(&f.state, AlertKind::LowBalance).len(), 1);
+
+    f.mock.set_balance(&f.keys.identity, 2 * LAMPORTS_PER_SOL);
+    f.publish(checker.check(&f.context()).await);
+    assert!(active(&f.state, AlertKind::LowBalance).is_empty());
+}
+
+#[tokio::test]
+async fn health_check_survives_rpc_failure() {
+    let f = Fixture::new("health-failure");
+    let checker = HealthCheck::new(&f.settings, &f.state.rpc);
+    f.mock.set_balance(&f.keys.identity, LAMPORTS_PER_SOL / 2);
+    f.publish(checker.check(&f.context()).await);
+    assert_eq!(active(&f.state, AlertKind::LowBalance).len(), 1);
+
+    f.mock.fail_all(true);
+    let events = checker.check(&f.context()).await;
+    assert!(!events.iter().any(|event| matches!(
+        event,
+        Event::LowBalance { .. } | Event::SkipRateCritical { .. } | Event::Recovered { .. }
+    )));
+    f.publish(events);
+    assert_eq!(active(&f.state, AlertKind::LowBalance).len(), 1);
+    assert!(active(&f.state, AlertKind::CriticalSkipRate).is_empty());
+}
+
+#[tokio::test]
+async fn node_report_raises_no_alerts() {
+    let f = Fixture::new("stats-no-alerts");
+    f.mock.set_balance(&f.keys.identity, LAMPORTS_PER_SOL / 2);
+    node_report(&f).await;
+    assert!(f.state.status.active_alerts().is_empty());
+}
+
+#[tokio::test]
+async fn node_stats_delinquent() {
+    let f = Fixture::new("stats-delinquent");
+    set_vote_accounts(&f.mock, &f.keys, true);
+    let report = node_report(&f).await;
+    assert_eq!(report.health, Health::Delinquent);
+}
+
+#[tokio::test]
+async fn health_check_critical_skip_rate() {
+    let f = Fixture::new("health-skip-rate");
+    // A second validator with most of the stake keeps the cluster skip rate low.
+    let other = Keys::new_unique();
+    let mut other_account = vote_account(&other, 2_000);
+    other_account["activatedStake"] = json!(9_000 * LAMPORTS_PER_SOL);
+    f.mock.set(
+        "getVoteAccounts",
+        json!({"current": [vote_account(&f.keys, 2_000), other_account], "delinquent": []}),
+    );
+    f.mock.set(
+        "getBlockProduction",
+        json!({
+            "context": {"slot": EPOCH * SLOTS_IN_EPOCH + SLOT_INDEX},
+            "value": {
+                "byIdentity": {
+                    f.keys.identity.clone(): [40, 20],
+                    other.identity.clone(): [400, 400],
+                },
+                "range": {"firstSlot": EPOCH * SLOTS_IN_EPOCH, "lastSlot": EPOCH * SLOTS_IN_EPOCH + SLOT_INDEX},
+            },
+        }),
+    );
+    let report = node_report(&f).await;
+    f.publish(
+        HealthCheck::new(&f.settings, &f.state.rpc)
+            .check(&f.context())
+            .await,
+    );
+
+    assert_eq!(report.health, Health::CriticalSkipRate);
+    assert_eq!(report.skip_rate.skip_rate, 50.);
+    assert_eq!(report.skip_rate.cluster_skip_rate, 5.);
+    assert_eq!(active(&f.state, AlertKind::CriticalSkipRate).len(), 1);
+}
+
+#[tokio::test]
+async fn node_stats_survives_rpc_failure() {
+    let f = Fixture::new("stats-failure");
+    f.mock.fail_all(true);
+    let report = node_report(&f).await;
+
+    assert_eq!(report.version, "?");
+    assert_eq!(report.balances.identity, -1.);
+    assert_eq!(report.credits.rank, 0);
+    assert!(report.skip_rate.cluster_skip_rate.is_nan());
+}
+
+#[tokio::test]
+async fn cluster_stats_are_fetched_once_per_cluster() {
+    let f = Fixture::new("stats-cluster");
+    let reference = MockRpc::start();
+    let second = Keys::new_unique();
+    healthy(&reference, &f.keys);
+    let mut settings = f.settings.clone();
+    settings.clusters.insert(
+        "testnet".to_string(),
+        serde_json::from_value(json!({"rpc": reference.url})).unwrap(),
+    );
+    settings.nodes.push(node("node-2", &second, &f.mock.url));
+    for node in settings.nodes.iter_mut() {
+        node.validator.cluster = Some("testnet".to_string());
+    }
+
+    let clusters = ClusterCache::new(&settings.clusters, &f.state.rpc);
+    for node in &settings.nodes {
+        let client = Client::new(&node.validator, &f.state.rpc);
+        let report = node_stats::check(&client, node, &clusters, &f.state).await;
+        assert_eq!(report.cluster.as_deref(), Some("testnet"));
+    }
+    assert_eq!(reference.calls("getBlockProduction"), 1);
+    assert_eq!(f.mock.calls("getBlockProduction"), 2);
+
+    // The health check shares its cluster stats between the validators of a tick.
+    for index in 3..=5 {
+        let keys = Keys::new_unique();
+        let mut node = node(&format!("node-{}", index), &keys, &f.mock.url);
+        node.validator.cluster = Some("testnet".to_string());
+        settings.nodes.push(node);
+    }
+    let vote_accounts = reference.calls("getVoteAccounts");
+    let checker = HealthCheck::new(&settings, &f.state.rpc);
+    for node in &settings.nodes {
+        let context = ValidatorContext::new(node, &f.state);
+        f.publish(checker.check(&context).await);
+    }
+    assert_eq!(reference.calls("getBlockProduction"), 2);
+    assert_eq!(reference.calls("getVoteAccounts"), 2 * vote_accounts);
+    assert_eq!(f.mock.calls("getBlockProduction"), 2 + settings.nodes.len());
+}
+
+#[tokio::test]
+async fn epoch_check_records_rewards_of_finished_epoch() {
+    let f = Fixture::new("epoch-rewards");
+    f.mock.set(
+        "getInflationReward",
+        json!([{
+            "epoch": EPOCH - 1,
+            "effectiveSlot": EPOCH * SLOTS_IN_EPOCH,
+            "amount": LAMPORTS_PER_SOL,
+            "postBalance": 2 * LAMPORTS_PER_SOL,
+            "commission": 5,
+        }]),
+    );
+    let checker = EpochCheck::new(&f.settings, &f.state.rpc);
+    f.publish(checker.check(&f.context()).await);
+
+    let history = RewardsHistory::load(Path::new(&f.settings.rewards.history_path));
+    assert!(history.contains("node-1", EPOCH - 1));
+    assert!(f.mock.calls("getBlock") > 0);
+}
+
+#[tokio::test]
+async fn epoch_check_gives_up_on_missing_rewards() {
+    let f = Fixture::builder("epoch-missing-rewards")
+        .configure(|settings| settings.rewards.max_attempts = 3)
+        .build();
+    let checker = EpochCheck::new(&f.settings, &f.state.rpc);
+
+    // A failed call is not an attempt.
+    f.mock.fail("getInflationReward", -32000);
+    f.publish(checker.check(&f.context()).await);
+    let failed = f.mock.calls("getInflationReward");
+    assert!(failed > 0);
+
+    f.mock.set("getInflationReward", json!([null]));
+    for _ in 0..5 {
+        f.publish(checker.check(&f.context()).await);
+    }
+    assert_eq!(f.mock.calls("getInflationReward"), failed + 3);
+
+    let history = RewardsHistory::load(Path::new(&f.settings.rewards.history_path));
+    assert!(!history.contains("node-1", EPOCH - 1));
+}
+
+#[tokio::test]
+async fn epoch_summary_without_leader_slots_has_no_skip_rate() {
+    let f = Fixture::new("epoch-no-leader-slots");
+    set_block_production(&f.mock, &f.keys, 0, 0);
+    let client = Client::new(&f.settings.nodes[0].validator, &f.state.rpc);
+    let cluster = ClusterStats::collect(&client, Some(EPOCH - 1)).await;
+    let baseline = EpochBaseline::observe(&client, EPOCH - 1, false).await;
+    let next = EpochBaseline::observe(&client, EPOCH, false).await;
+    let summary = EpochSummary::collect(&client, &cluster, &baseline, &next).await;
+
+    assert_eq!(summary.leader_slots, 0);
+    assert_eq!(summary.skip_rate, 0.);
+    let html = summary.to_html();
+    assert!(html.contains("blocks   0/0 | skip n/a |"), "{}", html);
+}
+
+#[tokio::test]
+async fn epoch_check_survives_rpc_failure() {
+    let f = Fixture::new("epoch-failure");
+    f.mock.fail_all(true);
+    let checker = EpochCheck::new(&f.settings, &f.state.rpc);
+    f.publish(checker.check(&f.context()).await);
+
+    let history = RewardsHistory::load(Path::new(&f.settings.rewards.history_path));
+    assert!(!history.contains("node-1", EPOCH - 1));
+    assert_eq!(f.mock.calls("getBlock"), 0);
+}
@@ -0,0 +1,170 @@
+mod common;
+
+use clap::Parser;
+use common::*;
+use serde_json::{json, Value};
+use solana_bot::cli::{Cli, Command};
+use solana_bot::report::ReportFormat;
+use std::path::PathBuf;
+use std::process::Output;
+
+fn parse(args: &[&str]) -> Result<Cli, clap::Error> {
+    Cli::try_parse_from(std::iter::once("solana-bot").chain(args.iter().copied()))
+}
+
+/// Writes the settings of `f` where the bot reads them, the token included.
+fn write_settings(f: &Fixture) -> PathBuf {
+    let mut document = serde_json::to_value(&f.settings).unwrap();
+    document["telegram"]["token"] = json!(TOKEN);
+    let path = f.dir.join("settings.json");
+    std::fs::write(&path, document.to_string()).unwrap();
+    path
+}
+
+/// Runs the bot executable with `args`.
+fn bot(args: &[&str]) -> Output {
+    std::process::Command::new(env!("CARGO_BIN_EXE_solana-bot"))
+        .args(args)
+        .env_remove("RUST_LOG")
+        .output()
+        .expect("run solana-bot")
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+#[test]
+fn bot_runs_without_a_command() {
+    let cli = parse(&[]).unwrap();
+    assert!(cli.command.is_none());
+    assert!(cli.config.is_empty());
+    assert!(matches!(
+        parse(&["run"]).unwrap().command,
+        Some(Command::Run)
+    ));
+}
+
+#[test]
+fn config_files_are_layered_in_the_order_given() {
+    let cli = parse(&["--config", "a.json", "--config", "b.toml", "check"]).unwrap();
+    assert!(matches!(cli.command, Some(Command::Check)));
+    assert_eq!(
+        cli.config,
+        [PathBuf::from("a.json"), PathBuf::from("b.toml")]
+    );
+    // Options of the bot come before the command, so no file is silently dropped.
+    assert!(parse(&["--config", "a.json", "check", "--config", "b.toml"]).is_err());
+}
+
+#[test]
+fn report_takes_a_validator_and_a_format() {
+    let cli = parse(&["report", "node-1"]).unwrap();
+    assert!(matches!(
+        cli.command,
+        Some(Command::Report { ref validator, format: ReportFormat::Text }) if validator == "node-1"
+    ));
+    let cli = parse(&["report", "node-1", "--format", "csv"]).unwrap();
+    assert!(matches!(
+        cli.command,
+        Some(Command::Report {
+            format: ReportFormat::Csv,
+            ..
+        })
+    ));
+    assert!(parse(&["report"]).is_err());
+    assert!(parse(&["report", "node-1", "--format", "pdf"]).is_err());
+}
+
+#[test]
+fn commands_are_kebab_case() {
+    assert!(matches!(
+        parse(&["validate-config"]).unwrap().command,
+        Some(Command::ValidateConfig)
+    ));
+    assert!(matches!(
+        parse(&["test-notify"]).unwrap().command,
+        Some(Command::TestNotify)
+    ));
+    assert!(parse(&["validate_config"]).is_err());
+    assert!(parse(&["deploy"]).is_err());
+}
+
+#[tokio::test]
+async fn validate_config_lists_the_merged_files() {
+    let f = Fixture::new("cli-validate");
+    let path = write_settings(&f);
+
+    let output = bot(&["--config", path.to_str().unwrap(), "validate-config"]);
+
+    assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+    assert_eq!(
+        stdout(&output),
+        format!("{}: OK, 1 validators configured\n", path.display())
+    );
+}
+
+#[tokio::test]
+async fn invalid_settings_exit_with_2_and_their_problems() {
+    let mut f = Fixture::new("cli-invalid");
+    f.settings.nodes[0].validator.vote = "not-a-key".to_string();
+    let path = write_settings(&f);
+
+    let output = bot(&["--config", path.to_str().unwrap(), "validate-config"]);
+
+    assert_eq!(output.status.code(), Some(2));
+    assert!(stderr(&output).contains("nodes[0].validator.vote: invalid pubkey"));
+    let missing = bot(&["--config", "/nonexistent/settings.json", "check"]);
+    assert_eq!(missing.status.code(), Some(2));
+}
+
+#[tokio::test]
+async fn check_exits_with_1_when_a_validator_needs_attention() {
+    let f = Fixture::new("cli-check");
+    let path = write_settings(&f);
+
+    let output = bot(&["--config", path.to_str().unwrap(), "check"]);
+    assert_eq!(output.status.code(), Some(0), "{}", stdout(&output));
+    assert!(stdout(&output).contains("result: OK"));
+
+    f.mock.set_balance(&f.keys.identity, LAMPORTS_PER_SOL / 10);
+    let output = bot(&["--config", path.to_str().unwrap(), "check"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stdout(&output).contains("result: identity balance 0.100 below 1.000"));
+}
+
+#[tokio::test]
+async fn report_prints_the_report_of_a_validator() {
+    let f = Fixture::new("cli-report");
+    let path = write_settings(&f);
+    let config = path.to_str().unwrap();
+
+    let output = bot(&["--config", config, "report", "node-1", "--format", "json"]);
+    assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+    let report: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["name"], "node-1");
+
+    let output = bot(&["--config", config, "report", "node-2"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stderr(&output).contains("Unknown validator: node-2"));
+}
+
+#[tokio::test]
+async fn test_notify_reaches_both_chats() {
+    let f = Fixture::new("cli-notify");
+    let path = write_settings(&f);
+
+    let output = bot(&["--config", path.to_str().unwrap(), "test-notify"]);
+
+    assert_eq!(output.status.code(), Some(0), "{}", stdout(&output));
+    assert_eq!(f.telegram.sent_to(REPORT_CHAT).len(), 1);
+    assert_eq!(f.telegram.sent_to(ALERT_CHAT).len(), 1);
+
+    f.telegram.reject("chat not found");
+    let output = bot(&["--config", path.to_str().unwrap(), "test-notify"]);
+    assert_eq!(output.status.code(), Some(1));
+}
@@ -0,0 +1,110 @@
+mod common;
+
+use common::mock_rpc::MockRpc;
+use common::*;
+use solana_bot::client::{Client, RpcLimits};
+use solana_bot::settings::RpcSettings;
+use std::time::{Duration, Instant};
+
+fn client(mock: &MockRpc, keys: &Keys) -> Client {
+    Client::new(
+        &node("node-1", keys, &mock.url).validator,
+        &RpcLimits::default(),
+    )
+}
+
+#[tokio::test]
+async fn reads_validator_state() {
+    let mock = MockRpc::start();
+    let keys = Keys::new_unique();
+    healthy(&mock, &keys);
+    let client = client(&mock, &keys);
+
+    assert_eq!(client.get_identity_balance().await, 5.);
+    assert_eq!(client.get_vote_balance().await, 1.);
+    assert_eq!(client.is_delinquent().await, Some(false));
+    assert_eq!(client.activated_stake().await, Some(1_000.));
+    assert_eq!(client.get_version().await, "1.18.26");
+    assert_eq!(client.get_block_production().await, (40, 40));
+    assert_eq!(client.get_slot_count().await, 60);
+    assert_eq!(client.get_average_slot_time_ms().await, Some(400));
+    assert_eq!(client.get_epoch_info().await.0, EPOCH.to_string());
+}
+
+#[tokio::test]
+async fn reports_rpc_failures_as_unknown() {
+    let mock = MockRpc::start();
+    let keys = Keys::new_unique();
+    healthy(&mock, &keys);
+    mock.fail_all(true);
+    let client = client(&mock, &keys);
+
+    assert_eq!(client.get_identity_balance().await, -1.);
+    assert_eq!(client.is_delinquent().await, None);
+    assert_eq!(client.activated_stake().await, None);
+    assert_eq!(client.get_slot_lag().await, None);
+    assert_eq!(client.get_slot_count().await, 0);
+    assert_eq!(client.get_average_slot_time_ms().await, None);
+    assert!(client.get_epoch_credits(None).await.is_empty());
+    assert!(client
+        .get_stake_weighted_skip_rate_in_range(None)
+        .await
+        .1
+        .is_nan());
+}
+
+#[tokio::test]
+async fn unknown_vote_account_has_no_stake() {
+    let mock = MockRpc::start();
+    let keys = Keys::new_unique();
+    healthy(&mock, &keys);
+    mock.set(
+        "getVoteAccounts",
+        serde_json::json!({"current": [], "delinquent": []}),
+    );
+    let client = client(&mock, &keys);
+
+    assert_eq!(client.activated_stake().await, None);
+    assert_eq!(client.get_slot_lag().await, None);
+}
+
+#[tokio::test]
+async fn slow_call_times_out_as_unknown() {
+    let mock = MockRpc::start();
+    let keys = Keys::new_unique();
+    healthy(&mock, &keys);
+    mock.delay("getBalance", Duration::from_secs(5));
+    let limits = RpcLimits::new(&RpcSettings {
+        timeout: Duration::from_millis(200),
+        ..RpcSettings::default()
+    });
+    let client = Client::new(&node("node-1", &keys, &mock.url).validator, &limits);
+
+    let started = Instant::now();
+    assert_eq!(client.get_identity_balance().await, -1.);
+    assert!(started.elapsed() < Duration::from_secs(2));
+    assert_eq!(client.is_delinquent().await, Some(false));
+}
+
+#[tokio::test]
+async fn concurrent_requests_are_limited() {
+    let keys = Keys::new_unique();
+    for (max_concurrent_requests, serialized) in [(1, true), (4, false)] {
+        let mock = MockRpc::start();
+        healthy(&mock, &keys);
+        mock.delay("getBalance", Duration::from_millis(100));
+        let limits = RpcLimits::new(&RpcSettings {
+            max_concurrent_requests,
+            ..RpcSettings::default()
+        });
+        let client = Client::new(&node("node-1", &keys, &mock.url).validator, &limits);
+
+        tokio::join!(
+            client.get_identity_balance(),
+            client.get_vote_balance(),
+            client.get_identity_balance(),
+            client.get_vote_balance()
+        );
+        assert_eq!(mock.max_in_flight() == 1, serialized);
+    }
+}
@@ -0,0 +1,86 @@
+use std::io::{BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+
+/// In-process HTTP endpoint counting the requests it gets, as a dead man's switch
+/// service or the HTTP API of a peer instance would. While `down`, connections are
+/// closed without an answer.
+pub struct MockPing {
+    pub url: String,
+    addr: SocketAddr,
+    stopped: Arc<AtomicBool>,
+    down: Arc<AtomicBool>,
+    hits: Arc<AtomicUsize>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl MockPing {
+    pub fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind mock ping");
+        let addr = listener.local_addr().expect("tcp address");
+        let stopped = Arc::new(AtomicBool::new(false));
+        let down = Arc::new(AtomicBool::new(false));
+        let hits = Arc::new(AtomicUsize::new(0));
+        let thread = {
+            let stopped = stopped.clone();
+            let down = down.clone();
+            let hits = hits.clone();
+            thread::spawn(move || {
+                for stream in listener.incoming() {
+                    if stopped.load(Ordering::SeqCst) {
+                        break;
+                    }
+                    let Ok(stream) = stream else {
+                        continue;
+                    };
+                    if down.load(Ordering::SeqCst) {
+                        continue;
+                    }
+                    hits.fetch_add(1, Ordering::SeqCst);
+                    let _ = answer(stream);
+                }
+            })
+        };
+        MockPing {
+            url: format!("http://{}/ping", addr),
+            addr,
+            stopped,
+            down,
+            hits,
+            thread: Some(thread),
+        }
+    }
+
+    /// Requests answered so far.
+    pub fn hits(&self) -> usize {
+        self.hits.load(Ordering::SeqCst)
+    }
+
+    pub fn set_down(&self, down: bool) {
+        self.down.store(down, Ordering::SeqCst);
+    }
+}
+
+impl Drop for MockPing {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::SeqCst);
+        // Wakes the accept loop up so it sees the flag.
+        let _ = TcpStream::connect(self.addr);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Reads the request head and answers `200 OK`, closing the connection.
+fn answer(stream: TcpStream) -> std::io::Result<()> {
+    let mut writer = stream.try_clone()?;
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    while reader.read_line(&mut line)? > 0 && line != "\r\n" {
+        line.clear();
+    }
+    writer.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n")
+}
@@ -0,0 +1,237 @@
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// How the mock answers a method.
+#[derive(Clone, Debug)]
+pub enum Reply {
+    Result(Value),
+    /// A JSON-RPC error object with this code.
+    Error(i64),
+}
+
+#[derive(Default)]
+struct Script {
+    /// Replies by method, or by `method:first-param` for per-account answers.
+    replies: HashMap<String, Reply>,
+    /// Time to wait before answering a method, as a slow node would.
+    delays: HashMap<String, Duration>,
+    fail_all: bool,
+    calls: Vec<String>,
+    in_flight: usize,
+    max_in_flight: usize,
+}
+
+/// In-process Solana JSON-RPC server answering scripted responses. Every connection
+/// is served on its own thread, so neither kept-alive connections of the async client
+/// nor a delayed method hold up the other requests.
+pub struct MockRpc {
+    pub url: String,
+    addr: SocketAddr,
+    stopped: Arc<AtomicBool>,
+    script: Arc<Mutex<Script>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl MockRpc {
+    pub fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind mock rpc");
+        let addr = listener.local_addr().expect("tcp address");
+        let stopped = Arc::new(AtomicBool::new(false));
+        let script = Arc::new(Mutex::new(Script::default()));
+        let thread = {
+            let stopped = stopped.clone();
+            let script = script.clone();
+            thread::spawn(move || {
+                for stream in listener.incoming() {
+                    if stopped.load(Ordering::SeqCst) {
+                        break;
+                    }
+                    let Ok(stream) = stream else {
+                        continue;
+                    };
+                    let script = script.clone();
+                    thread::spawn(move || serve(stream, &script));
+                }
+            })
+        };
+        MockRpc {
+            url: format!("http://{}", addr),
+            addr,
+            stopped,
+            script,
+            thread: Some(thread),
+        }
+    }
+
+    pub fn set(&self, method: &str, result: Value) {
+        self.script
+            .lock()
+            .unwrap()
+            .replies
+            .insert(method.to_string(), Reply::Result(result));
+    }
+
+    /// Answers `method` only when its first parameter is `param`, e.g. `getBalance` of one account.
+    pub fn set_for(&self, method: &str, param: &str, result: Value) {
+        self.script
+            .lock()
+            .unwrap()
+            .replies
+            .insert(format!("{}:{}", method, param), Reply::Result(result));
+    }
+
+    pub fn fail(&self, method: &str, code: i64) {
+        self.script
+            .lock()
+            .unwrap()
+            .replies
+            .insert(method.to_string(), Reply::Error(code));
+    }
+
+    /// Delays every answer to `method` by `delay`.
+    pub fn delay(&self, method: &str, delay: Duration) {
+        self.script
+            .lock()
+            .unwrap()
+            .delays
+            .insert(method.to_string(), delay);
+    }
+
+    /// Most requests that were being answered at the same time.
+    pub fn max_in_flight(&self) -> usize {
+        self.script.lock().unwrap().max_in_flight
+    }
+
+    /// Makes every method fail, as an overloaded or broken node would.
+    pub fn fail_all(&self, fail: bool) {
+        self.script.lock().unwrap().fail_all = fail;
+    }
+
+    /// Number of requests of `method` served so far.
+    pub fn calls(&self, method: &str) -> usize {
+        self.script
+            .lock()
+            .unwrap()
+            .calls
+            .iter()
+            .filter(|call| *call == method)
+            .count()
+    }
+
+    pub fn set_balance(&self, pubkey: &str, lamports: u64) {
+        self.set_for(
+            "getBalance",
+            pubkey,
+            json!({"context": {"slot": 1}, "value": lamports}),
+        );
+    }
+}
+
+impl Drop for MockRpc {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::SeqCst);
+        // Wakes the accept loop up so it sees the flag.
+        let _ = TcpStream::connect(self.addr);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Answers the HTTP/1.1 requests of one kept-alive connection until the client closes it.
+fn serve(stream: TcpStream, script: &Mutex<Script>) {
+    let Ok(mut writer) = stream.try_clone() else {
+        return;
+    };
+    let mut reader = BufReader::new(stream);
+    loop {
+        let mut content_length = 0;
+        let mut line = String::new();
+        loop {
+            line.clear();
+            match reader.read_line(&mut line) {
+                Ok(0) | Err(_) => return,
+                Ok(_) => {}
+            }
+            let header = line.trim_end();
+            if header.is_empty() {
+                break;
+            }
+            if let Some((name, value)) = header.split_once(':') {
+                if name.eq_ignore_ascii_case("content-length") {
+                    content_length = value.trim().parse().unwrap_or(0);
+                }
+            }
+        }
+        let mut body = vec![0; content_length];
+        if reader.read_exact(&mut body).is_err() {
+            return;
+        }
+        let reply = answer(script, &String::from_utf8_lossy(&body));
+        let response = format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+            reply.len(),
+            reply
+        );
+        if writer.write_all(response.as_bytes()).is_err() {
+            return;
+        }
+    }
+}
+
+fn answer(script: &Mutex<Script>, body: &str) -> String {
+    let request: Value = serde_json::from_str(body).unwrap_or(Value::Null);
+    let id = request.get("id").cloned().unwrap_or(Value::Null);
+    let method = request
+        .get("method")
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string();
+    let first_param = request
+        .get("params")
+        .and_then(|params| params.get(0))
+        .and_then(Value::as_str)
+        .map(str::to_string);
+
+    let delay = {
+        let mut script = script.lock().unwrap();
+        script.calls.push(method.clone());
+        script.in_flight += 1;
+        script.max_in_flight = script.max_in_flight.max(script.in_flight);
+        script.delays.get(&method).cloned()
+    };
+    if let Some(delay) = delay {
+        thread::sleep(delay);
+    }
+
+    let mut script = script.lock().unwrap();
+    script.in_flight -= 1;
+    let reply = if script.fail_all {
+        Some(Reply::Error(-32000))
+    } else {
+        first_param
+            .and_then(|param| script.replies.get(&format!("{}:{}", method, param)))
+            .or_else(|| script.replies.get(&method))
+            .cloned()
+    };
+    let reply = match reply {
+        Some(Reply::Result(result)) => json!({"jsonrpc": "2.0", "id": id, "result": result}),
+        Some(Reply::Error(code)) => json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": {"code": code, "message": format!("mock {} failure", method)}
+        }),
+        None => json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": {"code": -32601, "message": format!("mock has no reply for {}", method)}
+        }),
+    };
+    reply.to_string()
+}
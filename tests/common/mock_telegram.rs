@@ -0,0 +1,171 @@
+use serde_json::{json, Value};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use tiny_http::{Header, Response, Server};
+
+/// A message or photo the bot delivered to the fake Bot API.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Sent {
+    pub token: String,
+    pub method: String,
+    pub chat_id: i64,
+    /// Message text, or the caption of a photo.
+    pub text: String,
+    pub parse_mode: Option<String>,
+}
+
+#[derive(Default)]
+struct Inbox {
+    sent: Vec<Sent>,
+    /// Error replies `(status, body)` for the next requests, before answering `ok` again.
+    errors: VecDeque<(u16, Value)>,
+}
+
+/// In-process fake of the Telegram Bot API that captures every request.
+pub struct MockTelegram {
+    pub url: String,
+    server: Arc<Server>,
+    inbox: Arc<Mutex<Inbox>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl MockTelegram {
+    pub fn start() -> Self {
+        let server = Arc::new(Server::http("127.0.0.1:0").expect("bind mock telegram"));
+        let url = format!(
+            "http://{}",
+            server.server_addr().to_ip().expect("tcp address")
+        );
+        let inbox = Arc::new(Mutex::new(Inbox::default()));
+        let thread = {
+            let server = server.clone();
+            let inbox = inbox.clone();
+            thread::spawn(move || {
+                for mut request in server.incoming_requests() {
+                    let mut body = Vec::new();
+                    let _ = request.as_reader().read_to_end(&mut body);
+                    let (status, reply) = receive(&inbox, request.url(), &body);
+                    let header =
+                        Header::from_bytes("Content-Type", "application/json").expect("header");
+                    let _ = request.respond(
+                        Response::from_string(reply.to_string())
+                            .with_status_code(status)
+                            .with_header(header),
+                    );
+                }
+            })
+        };
+        MockTelegram {
+            url,
+            server,
+            inbox,
+            thread: Some(thread),
+        }
+    }
+
+    /// Everything delivered so far, rejected requests excluded.
+    pub fn sent(&self) -> Vec<Sent> {
+        self.inbox.lock().unwrap().sent.clone()
+    }
+
+    pub fn sent_to(&self, chat_id: i64) -> Vec<Sent> {
+        self.sent()
+            .into_iter()
+            .filter(|sent| sent.chat_id == chat_id)
+            .collect()
+    }
+
+    /// Answers the next request with `429 Too Many Requests`.
+    pub fn rate_limit(&self, retry_after: u64) {
+        self.inbox.lock().unwrap().errors.push_back((
+            429,
+            json!({
+                "ok": false,
+                "error_code": 429,
+                "description": format!("Too Many Requests: retry after {}", retry_after),
+                "parameters": {"retry_after": retry_after},
+            }),
+        ));
+    }
+
+    /// Answers the next request with `502 Bad Gateway`, as a proxy in front of the Bot API would.
+    pub fn unavailable(&self) {
+        self.inbox.lock().unwrap().errors.push_back((
+            502,
+            json!({"ok": false, "error_code": 502, "description": "Bad Gateway"}),
+        ));
+    }
+
+    /// Answers the next request with `400 Bad Request`.
+    pub fn reject(&self, description: &str) {
+        self.inbox.lock().unwrap().errors.push_back((
+            400,
+            json!({
+                "ok": false,
+                "error_code": 400,
+                "description": format!("Bad Request: {}", description),
+            }),
+        ));
+    }
+}
+
+impl Drop for MockTelegram {
+    fn drop(&mut self) {
+        self.server.unblock();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn receive(inbox: &Mutex<Inbox>, url: &str, body: &[u8]) -> (u16, Value) {
+    let mut inbox = inbox.lock().unwrap();
+    if let Some(error) = inbox.errors.pop_front() {
+        return error;
+    }
+    // The path is `/bot<token>/<method>`.
+    let mut path = url.trim_start_matches('/').splitn(2, '/');
+    let token = path
+        .next()
+        .unwrap_or_default()
+        .trim_start_matches("bot")
+        .to_string();
+    let method = path.next().unwrap_or_default().to_string();
+    let (chat_id, text, parse_mode) = match serde_json::from_slice::<Value>(body) {
+        Ok(json) => (
+            json["chat_id"].as_i64().unwrap_or_default(),
+            json["text"].as_str().unwrap_or_default().to_string(),
+            json["parse_mode"].as_str().map(str::to_string),
+        ),
+        Err(_) => {
+            let body = String::from_utf8_lossy(body);
+            (
+                form_field(&body, "chat_id")
+                    .and_then(|id| id.parse().ok())
+                    .unwrap_or_default(),
+                form_field(&body, "caption").unwrap_or_default(),
+                form_field(&body, "parse_mode"),
+            )
+        }
+    };
+    inbox.sent.push(Sent {
+        token,
+        method,
+        chat_id,
+        text,
+        parse_mode,
+    });
+    (
+        200,
+        json!({"ok": true, "result": {"message_id": inbox.sent.len()}}),
+    )
+}
+
+/// Value of a text field in a `multipart/form-data` body.
+fn form_field(body: &str, name: &str) -> Option<String> {
+    let marker = format!("name=\"{}\"\r\n\r\n", name);
+    let start = body.find(&marker)? + marker.len();
+    let end = body[start..].find("\r\n")?;
+    Some(body[start..start + end].to_string())
+}
@@ -0,0 +1,344 @@
+#![allow(dead_code)]
+
+pub mod mock_ping;
+pub mod mock_rpc;
+pub mod mock_telegram;
+
+use serde_json::{json, Value};
+use solana_bot::checkers::ValidatorContext;
+use solana_bot::events::Event;
+use solana_bot::settings::{CheckRules, NodeCheckSettings, Settings, Validator};
+use solana_bot::state::SharedState;
+use solana_sdk::pubkey::Pubkey;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use mock_rpc::MockRpc;
+use mock_telegram::MockTelegram;
+
+pub const EPOCH: u64 = 100;
+pub const SLOTS_IN_EPOCH: u64 = 432_000;
+pub const SLOT_INDEX: u64 = 200_000;
+pub const LAMPORTS_PER_SOL: u64 = 1_000_000_000;
+/// Nothing listens on the discard port, so requests to it fail right away.
+pub const OFFLINE: &str = "http://127.0.0.1:9";
+/// Time given to the outputs to handle the published events.
+pub const SETTLE: Duration = Duration::from_secs(5);
+/// Time a test waits for something to happen before it fails.
+pub const WAIT: Duration = Duration::from_secs(10);
+
+/// Keys of a validator served by the mock.
+pub struct Keys {
+    pub identity: String,
+    pub vote: String,
+}
+
+impl Keys {
+    pub fn new_unique() -> Self {
+        Keys {
+            identity: Pubkey::new_unique().to_string(),
+            vote: Pubkey::new_unique().to_string(),
+        }
+    }
+}
+
+/// A fresh directory under the system temp dir for one test.
+pub fn temp_dir(name: &str) -> PathBuf {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let dir = std::env::temp_dir().join(format!(
+        "solana-bot-test-{}-{}-{}",
+        name,
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::SeqCst)
+    ));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).expect("create temp dir");
+    dir
+}
+
+pub fn node(name: &str, keys: &Keys, rpc: &str) -> NodeCheckSettings {
+    NodeCheckSettings {
+        validator: Validator {
+            name: name.to_string(),
+            identity: keys.identity.clone(),
+            vote: keys.vote.clone(),
+            rpc: rpc.to_string(),
+            cluster: None,
+        },
+        rules: CheckRules {
+            min_balance_amount: Some(1.),
+            critical_excess_of_skip_rate: Some(10.),
+            ..CheckRules::default()
+        },
+        disabled_reports: vec![],
+    }
+}
+
+pub const TOKEN: &str = "123456:test-token";
+pub const REPORT_CHAT: i64 = 1;
+pub const ALERT_CHAT: i64 = 2;
+
+/// Points the bot at `telegram` instead of the real Bot API.
+pub fn use_telegram(settings: &mut Settings, telegram: &MockTelegram) {
+    settings.telegram.token = serde_json::from_value(json!(TOKEN)).expect("token");
+    settings.telegram.api_url = telegram.url.clone();
+}
+
+/// Settings that keep every file of the bot inside `dir`.
+pub fn settings(nodes: Vec<NodeCheckSettings>, dir: &std::path::Path) -> Settings {
+    let mut settings = Settings {
+        nodes,
+        ..Settings::default()
+    };
+    settings.telegram.chat_id = REPORT_CHAT;
+    settings.telegram.alert_chat_id = ALERT_CHAT;
+    // Tests without a fake Bot API stay offline.
+    settings.telegram.api_url = OFFLINE.to_string();
+    settings.reports.charts = false;
+    settings.storage.dir = dir.join("history").to_string_lossy().into_owned();
+    settings.revenue.dir = dir.join("revenue").to_string_lossy().into_owned();
+    // Keep the outbound queue fast, tests that check the limits set their own.
+    settings.telegram.queue.chat_interval = Duration::ZERO;
+    settings.telegram.queue.group_chat_interval = Duration::ZERO;
+    settings.telegram.queue.retry_backoff = Duration::from_millis(50);
+    settings.telegram.queue.max_backoff = Duration::from_millis(200);
+    settings.telegram.queue.spool_path = dir.join("spool.json").to_string_lossy().into_owned();
+    settings.rewards.history_path = dir.join("rewards.json").to_string_lossy().into_owned();
+    settings.apply_defaults();
+    settings
+}
+
+/// A bot watching `node-1`, served by a healthy mock RPC, with its files in a fresh
+/// temp dir and its messages going to a fake Bot API.
+pub struct Fixture {
+    pub mock: MockRpc,
+    pub telegram: MockTelegram,
+    /// Keys of `node-1`.
+    pub keys: Keys,
+    pub dir: PathBuf,
+    pub settings: Settings,
+    pub state: SharedState,
+}
+
+impl Fixture {
+    pub fn new(name: &str) -> Self {
+        Fixture::builder(name).build()
+    }
+
+    pub fn builder(name: &str) -> FixtureBuilder {
+        FixtureBuilder {
+            name: name.to_string(),
+            nodes: vec!["node-1".to_string()],
+            offline: false,
+            configure: Box::new(|_| {}),
+        }
+    }
+
+    /// Context of a check of `node-1`.
+    pub fn context(&self) -> ValidatorContext {
+        ValidatorContext::new(&self.settings.nodes[0], &self.state)
+    }
+
+    /// Publishes the events of a check and waits until the outputs handled them.
+    pub fn publish(&self, events: Vec<Event>) {
+        for event in events {
+            self.state.bus.publish(event);
+        }
+        assert!(self.state.bus.settle(SETTLE));
+    }
+
+    /// Waits until every event is handled and every message delivered.
+    pub fn flush(&self) {
+        assert!(self.state.bus.settle(SETTLE));
+        assert!(self.state.notifier.flush(SETTLE));
+    }
+}
+
+pub struct FixtureBuilder {
+    name: String,
+    nodes: Vec<String>,
+    offline: bool,
+    configure: Box<dyn FnOnce(&mut Settings)>,
+}
+
+impl FixtureBuilder {
+    /// Watches `names` instead of `node-1` alone, the first one gets `keys`.
+    pub fn nodes(mut self, names: &[&str]) -> Self {
+        self.nodes = names.iter().map(|name| name.to_string()).collect();
+        self
+    }
+
+    /// Points the validators at an RPC that never answers.
+    pub fn offline(mut self) -> Self {
+        self.offline = true;
+        self
+    }
+
+    /// Changes the settings before the state is built.
+    pub fn configure(mut self, configure: impl FnOnce(&mut Settings) + 'static) -> Self {
+        self.configure = Box::new(configure);
+        self
+    }
+
+    pub fn build(self) -> Fixture {
+        let mock = MockRpc::start();
+        let telegram = MockTelegram::start();
+        let keys = Keys::new_unique();
+        healthy(&mock, &keys);
+        let rpc = if self.offline { OFFLINE } else { &mock.url };
+        let nodes = self
+            .nodes
+            .iter()
+            .enumerate()
+            .map(|(index, name)| match index {
+                0 => node(name, &keys, rpc),
+                _ => node(name, &Keys::new_unique(), rpc),
+            })
+            .collect();
+        let dir = temp_dir(&self.name);
+        let mut settings = settings(nodes, &dir);
+        use_telegram(&mut settings, &telegram);
+        (self.configure)(&mut settings);
+        let state = SharedState::new(&settings);
+        Fixture {
+            mock,
+            telegram,
+            keys,
+            dir,
+            settings,
+            state,
+        }
+    }
+}
+
+/// Polls `condition` until it holds, failing the test after `WAIT`.
+pub async fn wait_for(condition: impl Fn() -> bool) {
+    let waited = tokio::time::timeout(WAIT, async {
+        while !condition() {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await;
+    assert!(waited.is_ok(), "condition not met within {:?}", WAIT);
+}
+
+pub fn vote_account(keys: &Keys, credits: u64) -> Value {
+    json!({
+        "votePubkey": keys.vote,
+        "nodePubkey": keys.identity,
+        "activatedStake": 1_000 * LAMPORTS_PER_SOL,
+        "commission": 5,
+        "epochVoteAccount": true,
+        "epochCredits": [[EPOCH - 1, 10_000, 0], [EPOCH, 10_000 + credits, 10_000]],
+        "lastVote": EPOCH * SLOTS_IN_EPOCH + SLOT_INDEX - 2,
+        "rootSlot": EPOCH * SLOTS_IN_EPOCH + SLOT_INDEX - 40,
+    })
+}
+
+/// Scripts a healthy validator: voting, funded and producing every leader block.
+pub fn healthy(mock: &MockRpc, keys: &Keys) {
+    let absolute_slot = EPOCH * SLOTS_IN_EPOCH + SLOT_INDEX;
+    mock.set(
+        "getVersion",
+        json!({"solana-core": "1.18.26", "feature-set": 3469865029u32}),
+    );
+    mock.set_balance(&keys.identity, 5 * LAMPORTS_PER_SOL);
+    mock.set_balance(&keys.vote, LAMPORTS_PER_SOL);
+    set_vote_accounts(mock, keys, false);
+    mock.set("getSlot", json!(absolute_slot));
+    mock.set(
+        "getEpochInfo",
+        json!({
+            "absoluteSlot": absolute_slot,
+            "blockHeight": absolute_slot - 1_000,
+            "epoch": EPOCH,
+            "slotIndex": SLOT_INDEX,
+            "slotsInEpoch": SLOTS_IN_EPOCH,
+            "transactionCount": null,
+        }),
+    );
+    mock.set(
+        "getEpochSchedule",
+        json!({
+            "slotsPerEpoch": SLOTS_IN_EPOCH,
+            "leaderScheduleSlotOffset": SLOTS_IN_EPOCH,
+            "warmup": false,
+            "firstNormalEpoch": 0,
+            "firstNormalSlot": 0,
+        }),
+    );
+    set_block_production(mock, keys, 40, 40);
+    mock.set(
+        "getLeaderSchedule",
+        json!({ keys.identity.clone(): (0..60).collect::<Vec<u64>>() }),
+    );
+    mock.set(
+        "getClusterNodes",
+        json!([{
+            "pubkey": keys.identity,
+            "gossip": "127.0.0.1:8001",
+            "version": "1.18.26",
+            "featureSet": 3469865029u32,
+            "shredVersion": 1,
+        }]),
+    );
+    mock.set(
+        "getRecentPerformanceSamples",
+        json!([{
+            "slot": absolute_slot,
+            "numTransactions": 1_000,
+            "numNonVoteTransactions": 100,
+            "numSlots": 150,
+            "samplePeriodSecs": 60,
+        }]),
+    );
+    mock.set("getProgramAccounts", json!([]));
+    mock.set("getInflationReward", json!([null]));
+    mock.set(
+        "getBlock",
+        json!({
+            "blockhash": "11111111111111111111111111111111",
+            "previousBlockhash": "11111111111111111111111111111111",
+            "parentSlot": absolute_slot - 1,
+            "rewards": [{
+                "pubkey": keys.identity,
+                "lamports": 5_000,
+                "postBalance": 5 * LAMPORTS_PER_SOL,
+                "rewardType": "Fee",
+                "commission": null,
+            }],
+            "blockTime": null,
+            "blockHeight": null,
+        }),
+    );
+}
+
+pub fn set_vote_accounts(mock: &MockRpc, keys: &Keys, delinquent: bool) {
+    let account = vote_account(keys, 2_000);
+    let (current, delinquent) = if delinquent {
+        (json!([]), json!([account]))
+    } else {
+        (json!([account]), json!([]))
+    };
+    mock.set(
+        "getVoteAccounts",
+        json!({"current": current, "delinquent": delinquent}),
+    );
+}
+
+pub fn set_block_production(mock: &MockRpc, keys: &Keys, leader_slots: u64, produced: u64) {
+    mock.set(
+        "getBlockProduction",
+        json!({
+            "context": {"slot": EPOCH * SLOTS_IN_EPOCH + SLOT_INDEX},
+            "value": {
+                "byIdentity": { keys.identity.clone(): [leader_slots, produced] },
+                "range": {
+                    "firstSlot": EPOCH * SLOTS_IN_EPOCH,
+                    "lastSlot": EPOCH * SLOTS_IN_EPOCH + SLOT_INDEX,
+                },
+            },
+        }),
+    );
+}
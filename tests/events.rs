@@ -0,0 +1,91 @@
+use solana_bot::events::{Event, EventBus, Subscriber};
+use solana_bot::status::AlertKind;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+const SETTLE: Duration = Duration::from_secs(5);
+
+/// An output written for the tests, e.g. a webhook or a metrics exporter.
+#[derive(Default)]
+struct Recorder {
+    events: Mutex<Vec<Event>>,
+    /// Panics on this event instead of recording it.
+    fails_on: Option<Event>,
+    delay: Duration,
+}
+
+impl Subscriber for Recorder {
+    fn name(&self) -> &'static str {
+        "recorder"
+    }
+
+    fn handle(&self, event: &Event) {
+        std::thread::sleep(self.delay);
+        if self.fails_on.as_ref() == Some(event) {
+            panic!("recorder failed");
+        }
+        self.events.lock().unwrap().push(event.clone());
+    }
+}
+
+fn recovered(validator: &str) -> Event {
+    Event::Recovered {
+        validator: validator.to_string(),
+        kind: AlertKind::Delinquent,
+    }
+}
+
+#[test]
+fn every_subscriber_gets_every_event_in_order() {
+    let bus = EventBus::default();
+    let first = Arc::new(Recorder::default());
+    let second = Arc::new(Recorder::default());
+    bus.subscribe(first.clone());
+    bus.subscribe(second.clone());
+
+    let events = vec![
+        recovered("node-1"),
+        Event::ReportReady("report".to_string()),
+        recovered("node-2"),
+    ];
+    for event in events.clone() {
+        bus.publish(event);
+    }
+    assert!(bus.settle(SETTLE));
+    assert_eq!(*first.events.lock().unwrap(), events);
+    assert_eq!(*second.events.lock().unwrap(), events);
+}
+
+#[test]
+fn failing_subscriber_keeps_receiving_events() {
+    let bus = EventBus::default();
+    let failing = Arc::new(Recorder {
+        fails_on: Some(recovered("node-1")),
+        ..Recorder::default()
+    });
+    bus.subscribe(failing.clone());
+
+    bus.publish(recovered("node-1"));
+    bus.publish(recovered("node-2"));
+    assert!(bus.settle(SETTLE));
+    assert_eq!(*failing.events.lock().unwrap(), vec![recovered("node-2")]);
+}
+
+#[test]
+fn settle_waits_for_slow_subscribers() {
+    let bus = EventBus::default();
+    let slow = Arc::new(Recorder {
+        delay: Duration::from_millis(100),
+        ..Recorder::default()
+    });
+    let fast = Arc::new(Recorder::default());
+    bus.subscribe(slow.clone());
+    bus.subscribe(fast.clone());
+
+    bus.publish(recovered("node-1"));
+    bus.publish(recovered("node-2"));
+    assert!(!bus.settle(Duration::from_millis(50)));
+    assert!(bus.settle(SETTLE));
+    assert_eq!(slow.events.lock().unwrap().len(), 2);
+    assert_eq!(fast.events.lock().unwrap().len(), 2);
+}
@@ -0,0 +1,136 @@
+mod common;
+
+use common::*;
+use solana_bot::ha::LockFile;
+use solana_bot::settings::HaSettings;
+use std::path::Path;
+use std::sync::{Arc, Barrier};
+use std::time::Duration;
+
+fn ha(dir: &Path, instance: &str, lease: Duration) -> HaSettings {
+    HaSettings {
+        lock_path: dir.join("leader.lock").to_string_lossy().into_owned(),
+        instance: Some(instance.to_string()),
+        lease,
+    }
+}
+
+#[test]
+fn only_one_instance_holds_the_lease() {
+    let dir = temp_dir("ha-lease");
+    let first = LockFile::new(&ha(&dir, "bot-1", Duration::from_secs(30)));
+    let second = LockFile::new(&ha(&dir, "bot-2", Duration::from_secs(30)));
+
+    assert!(first.acquire());
+    assert!(!second.acquire());
+    assert!(first.acquire(), "the holder renews its lease");
+
+    first.release();
+    assert!(second.acquire());
+    assert!(!first.acquire());
+}
+
+#[test]
+fn expired_lease_is_taken_over() {
+    let dir = temp_dir("ha-expired");
+    let first = LockFile::new(&ha(&dir, "bot-1", Duration::from_millis(100)));
+    let second = LockFile::new(&ha(&dir, "bot-2", Duration::from_millis(100)));
+
+    assert!(first.acquire());
+    assert!(!second.acquire());
+    std::thread::sleep(Duration::from_millis(150));
+    assert!(second.acquire());
+    assert!(!first.acquire());
+}
+
+#[test]
+fn racing_instances_elect_exactly_one_holder() {
+    const INSTANCES: usize = 16;
+    for round in 0..200 {
+        let dir = temp_dir("ha-race");
+        let barrier = Arc::new(Barrier::new(INSTANCES));
+        let racers: Vec<_> = (0..INSTANCES)
+            .map(|index| {
+                let lock = LockFile::new(&ha(
+                    &dir,
+                    &format!("bot-{}", index),
+                    Duration::from_secs(30),
+                ));
+                let barrier = barrier.clone();
+                std::thread::spawn(move || {
+                    barrier.wait();
+                    lock.acquire()
+                })
+            })
+            .collect();
+        let winners = racers
+            .into_iter()
+            .map(|racer| racer.join().unwrap())
+            .filter(|won| *won)
+            .count();
+        assert_eq!(winners, 1, "round {}", round);
+    }
+}
+
+/// An instance of the bot with its own Bot API, sharing the lock file in `dir`.
+fn instance(name: &str, dir: &Path) -> Fixture {
+    let ha = ha(dir, name, Duration::from_millis(300));
+    Fixture::builder(name)
+        .offline()
+        .configure(|settings| {
+            settings.telegram.lifecycle_notices = true;
+            settings.ha = Some(ha);
+        })
+        .build()
+}
+
+#[tokio::test]
+async fn only_the_active_instance_notifies_and_standby_takes_over() {
+    let dir = temp_dir("ha-failover");
+    let (first, second) = (instance("bot-1", &dir), instance("bot-2", &dir));
+    let (first_telegram, second_telegram) = (&first.telegram, &second.telegram);
+    let (stop_first, first_stopped) = tokio::sync::oneshot::channel::<()>();
+    let (stop_second, second_stopped) = tokio::sync::oneshot::channel::<()>();
+
+    let first_run = solana_bot::run_until(first.settings.clone(), async {
+        let _ = first_stopped.await;
+    });
+    let second_run = async {
+        // The first instance takes the lease before the second one starts.
+        wait_for(|| !first_telegram.sent_to(REPORT_CHAT).is_empty()).await;
+        solana_bot::run_until(second.settings.clone(), async {
+            let _ = second_stopped.await;
+        })
+        .await
+    };
+    let scenario = async {
+        wait_for(|| !first_telegram.sent_to(REPORT_CHAT).is_empty()).await;
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        assert!(second_telegram.sent().is_empty(), "standby stays silent");
+
+        stop_first.send(()).unwrap();
+        wait_for(|| {
+            second_telegram
+                .sent_to(ALERT_CHAT)
+                .iter()
+                .any(|sent| sent.text.contains("bot-2 is now the active instance"))
+        })
+        .await;
+        stop_second.send(()).unwrap();
+    };
+    tokio::join!(first_run, second_run, scenario);
+
+    let first_sent: Vec<String> = first_telegram
+        .sent()
+        .into_iter()
+        .map(|sent| sent.text)
+        .collect();
+    assert!(first_sent.iter().any(|text| text.contains("stopping")));
+    let second_sent: Vec<String> = second_telegram
+        .sent()
+        .into_iter()
+        .map(|sent| sent.text)
+        .collect();
+    assert!(second_sent.iter().any(|text| text.contains("stopping")));
+    assert!(!second_sent.iter().any(|text| text.contains("started")));
+}
@@ -0,0 +1,123 @@
+mod common;
+
+use common::mock_ping::MockPing;
+use common::*;
+use solana_bot::heartbeat;
+use solana_bot::scheduler::Scheduler;
+use solana_bot::settings::{HeartbeatSettings, PeerSettings};
+use solana_bot::status::AlertKind;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::Duration;
+
+const GRACE: Duration = Duration::from_secs(5);
+
+fn fixture(name: &str, heartbeat: HeartbeatSettings) -> Fixture {
+    Fixture::builder(name)
+        .offline()
+        .configure(|settings| settings.heartbeat = Some(heartbeat))
+        .build()
+}
+
+fn heartbeat(interval: Duration) -> HeartbeatSettings {
+    HeartbeatSettings {
+        interval,
+        ping_url: None,
+        digest: None,
+        peer: None,
+    }
+}
+
+/// Runs the heartbeat task until `done` holds, or fails after a few seconds.
+async fn run_until(f: &Fixture, done: impl Fn() -> bool) {
+    let mut scheduler = Scheduler::new(&f.settings.nodes, &f.state);
+    let settings = f.settings.heartbeat.as_ref().unwrap();
+    scheduler.spawn("heartbeat", |shutdown| {
+        heartbeat::run(settings, 1, f.state.clone(), shutdown)
+    });
+    scheduler.run_until(wait_for(done), GRACE).await;
+    f.flush();
+}
+
+#[tokio::test]
+async fn pings_url_on_every_interval() {
+    let ping = MockPing::start();
+    let mut settings = heartbeat(Duration::from_millis(50));
+    settings.ping_url = Some(serde_json::from_value(serde_json::json!(ping.url)).unwrap());
+    let f = fixture("heartbeat-ping", settings);
+    run_until(&f, || ping.hits() >= 3).await;
+}
+
+#[tokio::test]
+async fn ping_is_skipped_while_a_checker_is_stuck() {
+    let ping = MockPing::start();
+    let mut settings = heartbeat(Duration::from_millis(20));
+    settings.ping_url = Some(serde_json::from_value(serde_json::json!(ping.url)).unwrap());
+    let f = fixture("heartbeat-stuck", settings);
+    let _stuck = f
+        .state
+        .status
+        .register_checker("stuck", Some(Duration::from_millis(1)));
+    tokio::time::sleep(Duration::from_millis(10)).await;
+    let polls = AtomicUsize::new(0);
+    run_until(&f, || polls.fetch_add(1, Ordering::SeqCst) >= 20).await;
+    assert_eq!(ping.hits(), 0);
+}
+
+#[tokio::test]
+async fn ping_goes_on_after_a_checker_finished_but_not_after_one_panicked() {
+    let ping = MockPing::start();
+    let mut settings = heartbeat(Duration::from_millis(20));
+    settings.ping_url = Some(serde_json::from_value(serde_json::json!(ping.url)).unwrap());
+    let f = fixture("heartbeat-finished", settings);
+    drop(f.state.status.register_checker("finished", None));
+    run_until(&f, || ping.hits() >= 2).await;
+    assert!(f.state.status.checkers()["finished"].finished);
+
+    let status = f.state.status.clone();
+    let panicked = std::thread::spawn(move || {
+        let _guard = status.register_checker("panicked", None);
+        panic!("checker failed");
+    });
+    assert!(panicked.join().is_err());
+    assert!(!f.state.status.checkers()["panicked"].finished);
+    let hits = ping.hits();
+    let polls = AtomicUsize::new(0);
+    run_until(&f, || polls.fetch_add(1, Ordering::SeqCst) >= 20).await;
+    assert_eq!(ping.hits(), hits);
+}
+
+#[tokio::test]
+async fn still_alive_digest_goes_to_report_chat() {
+    let mut settings = heartbeat(Duration::from_millis(20));
+    settings.digest = Some(Duration::from_millis(100));
+    let f = fixture("heartbeat-digest", settings);
+    run_until(&f, || !f.telegram.sent_to(REPORT_CHAT).is_empty()).await;
+    let digest = &f.telegram.sent_to(REPORT_CHAT)[0].text;
+    assert!(digest.contains("still alive: watching 1 validators, 0 active alerts"));
+}
+
+#[tokio::test]
+async fn silent_peer_is_reported_until_it_is_back() {
+    let peer = MockPing::start();
+    peer.set_down(true);
+    let mut settings = heartbeat(Duration::from_millis(20));
+    settings.peer = Some(PeerSettings {
+        url: peer.url.clone(),
+        timeout: Duration::from_millis(100),
+    });
+    let f = fixture("heartbeat-peer", settings);
+    let reported = AtomicBool::new(false);
+    run_until(&f, || {
+        if !f.state.status.active_alerts().is_empty() {
+            reported.store(true, Ordering::SeqCst);
+            peer.set_down(false);
+        }
+        reported.load(Ordering::SeqCst) && f.state.status.active_alerts().is_empty()
+    })
+    .await;
+    let recent = f.state.status.recent_alerts();
+    assert_eq!(recent[0].kind, AlertKind::PeerSilent);
+    let alerts = f.telegram.sent_to(ALERT_CHAT);
+    assert!(alerts[0].text.contains("NO HEARTBEAT"));
+    assert!(alerts[1].text.contains("is back"));
+}
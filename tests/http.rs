@@ -0,0 +1,212 @@
+mod common;
+
+use chrono::Utc;
+use common::*;
+use serde_json::Value;
+use solana_bot::http::decode;
+use solana_bot::settings::{HttpSettings, Settings};
+use solana_bot::storage::Metric;
+use std::io::Read;
+use std::net::TcpListener;
+use std::time::{Duration, Instant};
+
+/// A local address nothing listens on yet.
+fn free_address() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("bind");
+    listener.local_addr().expect("tcp address").to_string()
+}
+
+fn serve(settings: &mut Settings) -> String {
+    let listen = free_address();
+    settings.http = Some(HttpSettings {
+        listen: listen.clone(),
+        dashboard: true,
+    });
+    format!("http://{}", listen)
+}
+
+/// An answer of the server.
+struct Answer {
+    status: u16,
+    content_type: String,
+    body: Vec<u8>,
+}
+
+impl Answer {
+    fn text(&self) -> String {
+        String::from_utf8_lossy(&self.body).into_owned()
+    }
+}
+
+/// Answer to `GET url`, `None` while nothing answers.
+async fn get(url: String) -> Option<Answer> {
+    tokio::task::spawn_blocking(move || {
+        let response = match ureq::get(&url).call() {
+            Ok(response) | Err(ureq::Error::Status(_, response)) => response,
+            Err(_) => return None,
+        };
+        let status = response.status();
+        let content_type = response
+            .header("Content-Type")
+            .unwrap_or_default()
+            .to_string();
+        let mut body = Vec::new();
+        response.into_reader().read_to_end(&mut body).ok()?;
+        Some(Answer {
+            status,
+            content_type,
+            body,
+        })
+    })
+    .await
+    .expect("request")
+}
+
+/// Waits until the server at `url` answers.
+async fn started(url: &str) {
+    let health = format!("{}/health", url);
+    while get(health.clone()).await.is_none() {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+}
+
+#[tokio::test]
+async fn health_is_ok_without_report_schedules() {
+    let mut f = Fixture::builder("http-no-reports").offline().build();
+    f.settings.reports.full = None;
+    let url = serve(&mut f.settings);
+
+    let check = async {
+        started(&url).await;
+        // Let a report task with nothing to schedule come and go.
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        let answer = get(format!("{}/health", url))
+            .await
+            .expect("health answers");
+        let body: Value = serde_json::from_slice(&answer.body).unwrap();
+        assert_eq!(answer.status, 200, "{}", body);
+        assert_eq!(body["status"], "ok");
+        assert!(body["checkers"].get("node_stats").is_none());
+        assert_eq!(body["checkers"]["delinquency_check"]["alive"], true);
+    };
+    solana_bot::run_until(f.settings.clone(), check).await;
+}
+
+#[tokio::test]
+async fn slow_chart_does_not_hold_up_health() {
+    let mut f = Fixture::new("http-slow-chart");
+    f.mock.delay("getEpochInfo", Duration::from_secs(3));
+    let url = serve(&mut f.settings);
+
+    let check = async {
+        started(&url).await;
+        let chart = tokio::spawn(get(format!("{}/validator/node-1/chart/skip.png", url)));
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        let started = Instant::now();
+        let health = get(format!("{}/health", url))
+            .await
+            .expect("health answers");
+        assert!(
+            started.elapsed() < Duration::from_secs(1),
+            "health took {:?}",
+            started.elapsed()
+        );
+        assert!(health.status == 200 || health.status == 503);
+        let chart = chart.await.unwrap().expect("chart answers");
+        assert!(
+            chart.status == 200 || chart.status == 404,
+            "chart answered {}",
+            chart.status
+        );
+    };
+    solana_bot::run_until(f.settings.clone(), check).await;
+}
+
+#[tokio::test]
+async fn dashboard_serves_pages_and_charts_of_the_validators() {
+    let mut f = Fixture::builder("http-dashboard")
+        .nodes(&["node-1", "node <2>"])
+        .build();
+    for hours in [3, 2, 1] {
+        f.state.store.record_at(
+            Utc::now() - chrono::Duration::hours(hours),
+            "node-1",
+            &[(Metric::Rank, hours as f64)],
+        );
+    }
+    f.state.store.flush();
+    let url = serve(&mut f.settings);
+
+    let check = async {
+        started(&url).await;
+
+        let index = get(format!("{}/", url)).await.unwrap();
+        assert_eq!(index.status, 200);
+        assert_eq!(index.content_type, "text/html; charset=utf-8");
+        let page = index.text();
+        assert!(page.contains("<a href=\"/validator/node-1\">node-1</a>"));
+        assert!(page.contains("<th>identity balance</th><th>vote balance</th>"));
+        assert!(page.contains("<a href=\"/validator/node%20%3C2%3E\">node &lt;2&gt;</a>"));
+
+        let validator = get(format!("{}/validator/node%20%3C2%3E", url))
+            .await
+            .unwrap();
+        assert_eq!(validator.status, 200);
+        assert_eq!(validator.content_type, "text/html; charset=utf-8");
+        let page = validator.text();
+        assert!(page.contains("node &lt;2&gt;"));
+        assert!(page.contains("<img src=\"/validator/node%20%3C2%3E/chart/rank.png\""));
+
+        let chart = get(format!("{}/validator/node-1/chart/rank.png", url))
+            .await
+            .unwrap();
+        assert_eq!(chart.status, 200);
+        assert_eq!(chart.content_type, "image/png");
+        assert!(chart.body.starts_with(b"\x89PNG"));
+
+        let no_data = get(format!("{}/validator/node%20%3C2%3E/chart/rank.png", url)).await;
+        assert_eq!(no_data.unwrap().status, 404);
+
+        for path in [
+            "/validator/node-3",
+            "/validator/node-3/chart/rank.png",
+            "/validator/node-1/chart/votes.png",
+            "/nothing",
+        ] {
+            let answer = get(format!("{}{}", url, path)).await.unwrap();
+            assert_eq!(answer.status, 404, "{}", path);
+        }
+    };
+    solana_bot::run_until(f.settings.clone(), check).await;
+}
+
+#[tokio::test]
+async fn dashboard_is_off_unless_enabled() {
+    let mut f = Fixture::builder("http-no-dashboard").offline().build();
+    let url = serve(&mut f.settings);
+    f.settings.http.as_mut().unwrap().dashboard = false;
+
+    let check = async {
+        started(&url).await;
+        for path in ["/", "/validator/node-1", "/validator/node-1/chart/rank.png"] {
+            let answer = get(format!("{}{}", url, path)).await.unwrap();
+            assert_eq!(answer.status, 404, "{}", path);
+        }
+        let validators = get(format!("{}/validators", url)).await.unwrap();
+        assert_eq!(validators.status, 200);
+        assert_eq!(validators.content_type, "application/json");
+    };
+    solana_bot::run_until(f.settings.clone(), check).await;
+}
+
+#[test]
+fn path_segments_are_percent_decoded() {
+    assert_eq!(decode("node-1"), "node-1");
+    assert_eq!(decode("node%202"), "node 2");
+    assert_eq!(decode("%E2%9C%93%20ok"), "✓ ok");
+    assert_eq!(decode("%3c%3E"), "<>");
+    assert_eq!(decode("100%"), "100%");
+    assert_eq!(decode("%zz%4"), "%zz%4");
+    assert_eq!(decode("%FF"), "\u{FFFD}");
+}
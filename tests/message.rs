@@ -0,0 +1,118 @@
+use solana_bot::message::{escape, split, to_markdown_v2, ParseMode, MAX_MESSAGE_LENGTH};
+
+/// Removes the tags `split` adds around the boundaries, to compare with the original.
+fn rejoin(chunks: &[String]) -> String {
+    let mut text = chunks.join("\u{0}");
+    text = text.replace("</code>\u{0}<code>", "");
+    text.replace('\u{0}', "")
+}
+
+#[test]
+fn escape_html_special_characters() {
+    assert_eq!(
+        escape(r#"<node & "co">"#),
+        "&lt;node &amp; &quot;co&quot;&gt;"
+    );
+    assert_eq!(escape("node-1"), "node-1");
+}
+
+#[test]
+fn short_message_is_not_split() {
+    let text = "<b>node-1</b>\n<code>table</code>";
+    assert_eq!(split(text, MAX_MESSAGE_LENGTH), vec![text.to_string()]);
+}
+
+#[test]
+fn split_on_line_boundaries() {
+    let lines: Vec<String> = (0..10).map(|line| format!("line {}\n", line)).collect();
+    let text = lines.concat();
+    let chunks = split(&text, 20);
+
+    assert_eq!(
+        chunks,
+        vec![
+            "line 0\nline 1\n",
+            "line 2\nline 3\n",
+            "line 4\nline 5\n",
+            "line 6\nline 7\n",
+            "line 8\nline 9\n"
+        ]
+    );
+}
+
+#[test]
+fn split_keeps_code_blocks_balanced() {
+    let table: String = (0..40)
+        .map(|row| format!("row {:>3} | value\n", row))
+        .collect();
+    let text = format!("<b>Report</b>\n<code>{}</code>\nfooter", table);
+    let chunks = split(&text, 100);
+
+    assert!(chunks.len() > 1);
+    for chunk in &chunks {
+        assert!(chunk.chars().count() <= 100, "{:?}", chunk);
+        assert_eq!(
+            chunk.matches("<code>").count(),
+            chunk.matches("</code>").count()
+        );
+        // Rows are never cut in half.
+        for line in chunk.lines().filter(|line| line.contains("row")) {
+            assert!(
+                line.ends_with("value") || line.ends_with("value</code>"),
+                "{:?}",
+                line
+            );
+        }
+    }
+    assert_eq!(rejoin(&chunks), text);
+}
+
+#[test]
+fn split_long_line_without_breaking_entities() {
+    let text = format!("<code>{}</code>", "a&amp;".repeat(100));
+    let chunks = split(&text, 50);
+
+    for chunk in &chunks {
+        assert!(chunk.chars().count() <= 50);
+        assert!(chunk.starts_with("<code>") && chunk.ends_with("</code>"));
+        let body = chunk
+            .trim_start_matches("<code>")
+            .trim_end_matches("</code>");
+        assert!(
+            body.replace("&amp;", "").chars().all(|c| c == 'a'),
+            "{:?}",
+            chunk
+        );
+    }
+    assert_eq!(rejoin(&chunks), text);
+}
+
+#[test]
+fn markdown_v2_escapes_text_and_keeps_formatting() {
+    let html = "<b>node.1 (testnet)</b>\npubkey -> Abc\n<b>SMALL AMOUNT => 0.5!!!</b>";
+    assert_eq!(
+        to_markdown_v2(html),
+        "*node\\.1 \\(testnet\\)*\npubkey \\-\\> Abc\n*SMALL AMOUNT \\=\\> 0\\.5\\!\\!\\!*"
+    );
+    assert_eq!(to_markdown_v2("a &lt;b&gt; &amp; c"), "a <b\\> & c");
+}
+
+#[test]
+fn markdown_v2_turns_code_into_pre_blocks() {
+    let html = "<b>Report</b>\n\n<code>skip 1.5% | `x`\n</code>";
+    assert_eq!(
+        to_markdown_v2(html),
+        "*Report*\n\n```\nskip 1.5% | \\`x\\`\n```"
+    );
+}
+
+#[test]
+fn parse_mode_names() {
+    assert_eq!(ParseMode::Html.as_str(), "HTML");
+    assert_eq!(ParseMode::MarkdownV2.as_str(), "MarkdownV2");
+    assert_eq!(
+        serde_json::from_str::<ParseMode>("\"markdownV2\"").unwrap(),
+        ParseMode::MarkdownV2
+    );
+    assert_eq!(ParseMode::Html.format("<b>a.b</b>"), "<b>a.b</b>");
+}
@@ -0,0 +1,382 @@
+mod common;
+
+use common::*;
+use solana_bot::checkers::balance_check::BalanceCheck;
+use solana_bot::checkers::deliquency_check::DelinquencyCheck;
+use solana_bot::checkers::health_check::HealthCheck;
+use solana_bot::checkers::{node_stats, Checker};
+use solana_bot::cli;
+use solana_bot::client::Client;
+use solana_bot::cluster::ClusterCache;
+use solana_bot::message::ParseMode;
+use solana_bot::report::schedule::ReportKind;
+use solana_bot::send_message;
+use solana_bot::state::SharedState;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+async fn node_report(f: &Fixture) {
+    let node = &f.settings.nodes[0];
+    let client = Client::new(&node.validator, &f.state.rpc);
+    let clusters = ClusterCache::new(&f.settings.clusters, &f.state.rpc);
+    node_stats::check(&client, node, &clusters, &f.state).await;
+    assert!(f.state.bus.settle(SETTLE));
+}
+
+/// Runs one check of the first validator and waits until its messages are queued.
+async fn run_check(f: &Fixture, checker: &impl Checker) {
+    f.publish(checker.check(&f.context()).await);
+}
+
+fn texts(f: &Fixture) -> Vec<String> {
+    f.telegram
+        .sent()
+        .into_iter()
+        .map(|sent| sent.text)
+        .collect()
+}
+
+#[tokio::test]
+async fn delinquent_validator_is_reported_to_alert_chat() {
+    let f = Fixture::new("notify-delinquent");
+    set_vote_accounts(&f.mock, &f.keys, true);
+    run_check(&f, &DelinquencyCheck).await;
+    assert!(f.state.notifier.flush(SETTLE));
+
+    let sent = f.telegram.sent();
+    assert_eq!(sent.len(), 1);
+    assert_eq!(sent[0].token, TOKEN);
+    assert_eq!(sent[0].method, "sendMessage");
+    assert_eq!(sent[0].chat_id, ALERT_CHAT);
+    assert_eq!(
+        sent[0].text,
+        format!(
+            "<b>node-1</b>\npubkey -> {}\n<b>DELINQUENT!!!</b>!!!",
+            &f.keys.identity[..16]
+        )
+    );
+}
+
+#[tokio::test]
+async fn healthy_validator_sends_nothing() {
+    let f = Fixture::new("notify-healthy");
+    run_check(&f, &DelinquencyCheck).await;
+    run_check(&f, &BalanceCheck::default()).await;
+    run_check(&f, &HealthCheck::new(&f.settings, &f.state.rpc)).await;
+    node_report(&f).await;
+    assert!(f.state.notifier.flush(SETTLE));
+
+    assert!(f.telegram.sent().is_empty());
+}
+
+#[tokio::test]
+async fn low_balance_is_reported_to_alert_chat() {
+    let f = Fixture::new("notify-low-balance");
+    f.mock.set_balance(&f.keys.identity, LAMPORTS_PER_SOL / 2);
+    run_check(&f, &HealthCheck::new(&f.settings, &f.state.rpc)).await;
+    assert!(f.state.notifier.flush(SETTLE));
+
+    let sent = f.telegram.sent_to(ALERT_CHAT);
+    assert_eq!(sent.len(), 1);
+    assert_eq!(
+        sent[0].text,
+        format!(
+            "<b>node-1</b>\npubkey -> {}\n<b>SMALL AMOUNT => 0.5!!!</b>!!!",
+            &f.keys.identity[..16]
+        )
+    );
+    assert!(f.telegram.sent_to(REPORT_CHAT).is_empty());
+}
+
+#[tokio::test]
+async fn low_balance_is_reported_with_every_report_disabled() {
+    let f = Fixture::builder("notify-reports-disabled")
+        .configure(|settings| {
+            let node = &mut settings.nodes[0];
+            node.disabled_reports = vec![ReportKind::Full, ReportKind::Digest, ReportKind::Daily];
+            node.rules.health_check_period = Some(Duration::from_millis(100));
+        })
+        .build();
+    f.mock.set_balance(&f.keys.identity, LAMPORTS_PER_SOL / 2);
+
+    let alerted = || {
+        f.telegram
+            .sent_to(ALERT_CHAT)
+            .iter()
+            .any(|sent| sent.text.contains("SMALL AMOUNT"))
+    };
+    solana_bot::run_until(f.settings.clone(), wait_for(alerted)).await;
+    assert!(f.telegram.sent_to(REPORT_CHAT).is_empty());
+}
+
+#[tokio::test]
+async fn balance_change_is_reported_to_alert_chat() {
+    let f = Fixture::new("notify-balance-change");
+    let checker = BalanceCheck::default();
+    run_check(&f, &checker).await;
+    f.mock.set_balance(&f.keys.identity, 4 * LAMPORTS_PER_SOL);
+    run_check(&f, &checker).await;
+    assert!(f.state.notifier.flush(SETTLE));
+
+    let sent = f.telegram.sent_to(ALERT_CHAT);
+    assert_eq!(sent.len(), 1);
+    assert_eq!(
+        sent[0].text,
+        format!(
+            "<b>node-1</b>\npubkey -> {}\n<b>Identity balance changed!!! 5.000;4.000;-1.000</b>!!!",
+            &f.keys.identity[..16]
+        )
+    );
+}
+
+#[test]
+fn test_notify_reaches_both_chats() {
+    let f = Fixture::new("notify-test");
+    assert!(cli::test_notify(&f.settings));
+
+    let chats: Vec<i64> = f.telegram.sent().iter().map(|sent| sent.chat_id).collect();
+    assert_eq!(chats, vec![REPORT_CHAT, ALERT_CHAT]);
+}
+
+#[test]
+fn charts_are_sent_as_photos_to_report_chat() {
+    let f = Fixture::new("notify-charts");
+    let charts = vec![("Skip rate".to_string(), vec![0x89, b'P', b'N', b'G'])];
+    for event in node_stats::chart_events(charts) {
+        f.state.bus.publish(event);
+    }
+    f.flush();
+
+    let sent = f.telegram.sent();
+    assert_eq!(sent.len(), 1);
+    assert_eq!(sent[0].method, "sendPhoto");
+    assert_eq!(sent[0].chat_id, REPORT_CHAT);
+    assert_eq!(sent[0].text, "Skip rate");
+}
+
+#[test]
+fn rate_limit_is_surfaced_as_send_error() {
+    let f = Fixture::new("notify-rate-limit");
+    f.telegram.rate_limit(7);
+    let error = send_message("hello".to_string(), &f.settings.telegram, REPORT_CHAT).unwrap_err();
+
+    assert_eq!(error.status, Some(429));
+    assert_eq!(error.retry_after, Some(Duration::from_secs(7)));
+    assert!(error.is_transient());
+    assert!(error
+        .to_string()
+        .contains("Too Many Requests: retry after 7"));
+    assert!(!error.to_string().contains(TOKEN));
+    assert!(f.telegram.sent().is_empty());
+}
+
+#[test]
+fn rejected_message_is_surfaced_as_send_error() {
+    let f = Fixture::new("notify-rejected");
+    f.telegram.reject("can't parse entities");
+    let error = send_message("<b>".to_string(), &f.settings.telegram, REPORT_CHAT).unwrap_err();
+
+    assert_eq!(error.status, Some(400));
+    assert!(!error.is_transient());
+    assert!(error.to_string().contains("can't parse entities"));
+}
+
+#[test]
+fn rate_limited_alert_is_sent_after_retry_after() {
+    let f = Fixture::new("notify-retry-after");
+    f.telegram.rate_limit(1);
+    let start = Instant::now();
+    f.state.notifier.alert("alert".to_string());
+    assert!(f.state.notifier.flush(SETTLE));
+
+    assert!(start.elapsed() >= Duration::from_secs(1));
+    assert_eq!(texts(&f), vec!["alert"]);
+}
+
+#[test]
+fn transient_failures_are_retried() {
+    let f = Fixture::new("notify-transient");
+    f.telegram.unavailable();
+    f.telegram.unavailable();
+    f.state.notifier.report("report".to_string());
+    assert!(f.state.notifier.flush(SETTLE));
+
+    assert_eq!(texts(&f), vec!["report"]);
+}
+
+#[test]
+fn rejected_message_is_dropped_and_queue_moves_on() {
+    let f = Fixture::new("notify-dropped");
+    f.telegram.reject("can't parse entities");
+    f.state.notifier.report("<b>broken".to_string());
+    f.state.notifier.report("next".to_string());
+    assert!(f.state.notifier.flush(SETTLE));
+
+    assert_eq!(texts(&f), vec!["next"]);
+}
+
+#[test]
+fn report_is_dropped_after_max_attempts() {
+    let f = Fixture::builder("notify-max-attempts")
+        .configure(|settings| {
+            settings.telegram.queue.max_attempts = 2;
+        })
+        .build();
+    f.telegram.unavailable();
+    f.telegram.unavailable();
+    f.state.notifier.report("lost".to_string());
+    f.state.notifier.report("delivered".to_string());
+    assert!(f.state.notifier.flush(SETTLE));
+
+    assert_eq!(texts(&f), vec!["delivered"]);
+}
+
+#[test]
+fn messages_to_one_chat_are_spaced_by_chat_interval() {
+    let f = Fixture::builder("notify-chat-interval")
+        .configure(|settings| {
+            settings.telegram.queue.chat_interval = Duration::from_millis(200);
+        })
+        .build();
+    let start = Instant::now();
+    for text in ["1", "2", "3"] {
+        f.state.notifier.report(text.to_string());
+    }
+    f.state.notifier.alert("alert".to_string());
+    assert!(f.state.notifier.flush(SETTLE));
+
+    assert!(start.elapsed() >= Duration::from_millis(400));
+    // The alert goes to another chat and does not wait behind the reports.
+    assert_eq!(texts(&f), vec!["1", "alert", "2", "3"]);
+}
+
+#[test]
+fn messages_to_a_group_are_spaced_by_group_chat_interval() {
+    let f = Fixture::builder("notify-group-interval")
+        .configure(|settings| {
+            settings.telegram.chat_id = -100;
+            settings.telegram.queue.group_chat_interval = Duration::from_millis(300);
+        })
+        .build();
+    let start = Instant::now();
+    for text in ["1", "2", "3"] {
+        f.state.notifier.report(text.to_string());
+    }
+    f.state.notifier.alert("alert".to_string());
+    assert!(f.state.notifier.flush(SETTLE));
+
+    assert!(start.elapsed() >= Duration::from_millis(600));
+    assert_eq!(f.telegram.sent_to(-100).len(), 3);
+    // The private alert chat keeps the shorter interval.
+    assert_eq!(texts(&f), vec!["1", "alert", "2", "3"]);
+}
+
+#[test]
+fn global_limit_spreads_messages_over_seconds() {
+    let f = Fixture::builder("notify-global-limit")
+        .configure(|settings| {
+            settings.telegram.queue.messages_per_second = 2;
+        })
+        .build();
+    let start = Instant::now();
+    for text in ["1", "2", "3"] {
+        f.state.notifier.report(text.to_string());
+    }
+    assert!(f.state.notifier.flush(SETTLE));
+
+    assert!(start.elapsed() >= Duration::from_secs(1));
+    assert_eq!(texts(&f), vec!["1", "2", "3"]);
+}
+
+#[test]
+fn undelivered_alerts_are_spooled_and_sent_on_start() {
+    let f = Fixture::new("notify-spool");
+    let mut offline = f.settings.clone();
+    offline.telegram.api_url = OFFLINE.to_string();
+    let spool = offline.telegram.queue.spool_path.clone();
+
+    let state = SharedState::new(&offline);
+    state.notifier.alert("while offline".to_string());
+    state.notifier.report("report".to_string());
+    assert!(!state.notifier.flush(Duration::from_millis(300)));
+    let spooled = std::fs::read_to_string(&spool).unwrap();
+    assert!(spooled.contains("while offline"));
+    assert!(!spooled.contains("report"));
+    drop(state);
+
+    let state = SharedState::new(&f.settings);
+    assert!(state.notifier.flush(SETTLE));
+    assert_eq!(texts(&f), vec!["while offline"]);
+    assert_eq!(f.telegram.sent()[0].chat_id, ALERT_CHAT);
+    assert!(!Path::new(&spool).exists());
+}
+
+#[tokio::test]
+async fn validator_name_is_escaped_in_alerts() {
+    let f = Fixture::builder("notify-escape")
+        .configure(|settings| {
+            settings.nodes[0].validator.name = "<node & co>".to_string();
+        })
+        .build();
+    set_vote_accounts(&f.mock, &f.keys, true);
+    run_check(&f, &DelinquencyCheck).await;
+    assert!(f.state.notifier.flush(SETTLE));
+
+    let sent = f.telegram.sent();
+    assert_eq!(sent[0].parse_mode.as_deref(), Some("HTML"));
+    assert!(sent[0].text.starts_with("<b>&lt;node &amp; co&gt;</b>\n"));
+}
+
+#[test]
+fn long_report_is_split_into_several_messages() {
+    let f = Fixture::new("notify-split");
+    let table: String = (0..400)
+        .map(|row| format!("row {:>3} | 12.345 SOL\n", row))
+        .collect();
+    f.state
+        .notifier
+        .report(format!("<b>Report</b>\n\n<code>{}</code>", table));
+    assert!(f.state.notifier.flush(SETTLE));
+
+    let sent = f.telegram.sent_to(REPORT_CHAT);
+    assert!(sent.len() > 1);
+    for message in &sent {
+        assert!(message.text.chars().count() <= 4096);
+        assert_eq!(
+            message.text.matches("<code>").count(),
+            message.text.matches("</code>").count()
+        );
+    }
+    assert!(sent[0].text.starts_with("<b>Report</b>"));
+    let rows: usize = sent
+        .iter()
+        .map(|message| message.text.matches("SOL\n").count())
+        .sum();
+    assert_eq!(rows, 400);
+}
+
+#[tokio::test]
+async fn alert_chat_can_use_markdown_v2() {
+    let f = Fixture::builder("notify-markdown")
+        .configure(|settings| {
+            settings.telegram.alert_parse_mode = ParseMode::MarkdownV2;
+        })
+        .build();
+    f.mock.set_balance(&f.keys.identity, LAMPORTS_PER_SOL / 2);
+    run_check(&f, &HealthCheck::new(&f.settings, &f.state.rpc)).await;
+    f.state.notifier.report("<b>report.</b>".to_string());
+    assert!(f.state.notifier.flush(SETTLE));
+
+    let alert = &f.telegram.sent_to(ALERT_CHAT)[0];
+    assert_eq!(alert.parse_mode.as_deref(), Some("MarkdownV2"));
+    assert_eq!(
+        alert.text,
+        format!(
+            "*node\\-1*\npubkey \\-\\> {}\n*SMALL AMOUNT \\=\\> 0\\.5\\!\\!\\!*\\!\\!\\!",
+            &f.keys.identity[..16]
+        )
+    );
+    let report = &f.telegram.sent_to(REPORT_CHAT)[0];
+    assert_eq!(report.parse_mode.as_deref(), Some("HTML"));
+    assert_eq!(report.text, "<b>report.</b>");
+}
@@ -0,0 +1,186 @@
+mod common;
+
+use chrono::{DateTime, Utc};
+use common::*;
+use solana_bot::report::{
+    render, Balances, Credits, DailyStats, EpochProgress, Health, ReportFormat, SkipRate,
+    ValidatorReport,
+};
+use solana_bot::revenue::EpochRevenue;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+fn report(name: &str) -> ValidatorReport {
+    let keys = Keys::new_unique();
+    ValidatorReport {
+        timestamp: DateTime::parse_from_rfc3339("2024-05-01T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc),
+        name: name.to_string(),
+        identity: keys.identity,
+        vote: keys.vote,
+        version: "1.18.26".to_string(),
+        health: Health::Healthy,
+        balances: Balances {
+            identity: 5.,
+            vote: 12.5,
+        },
+        credits: Credits {
+            rank: 42,
+            credits: 123_456,
+        },
+        skip_rate: SkipRate {
+            leader_slots_total: 60,
+            leader_slots_passed: 40,
+            blocks_produced: 38,
+            skipped: 2,
+            skip_rate: 5.,
+            cluster_skip_rate: 3.25,
+        },
+        epoch: EpochProgress {
+            epoch: "100".to_string(),
+            time_remaining: "1day 2h".to_string(),
+            remaining_ratio: 0.5,
+        },
+        activated_stake: Some(150_000.),
+        fee_revenue: Some(EpochRevenue {
+            blocks: 38,
+            fees: 0.19,
+        }),
+        cluster: None,
+    }
+}
+
+#[test]
+fn html_report_escapes_its_values_and_keeps_the_table_in_a_code_block() {
+    let mut report = report("node <1>");
+    report.cluster = Some("testnet".to_string());
+    report.health = Health::Delinquent;
+    let html = report.render(ReportFormat::Html);
+
+    assert!(html.starts_with("<b>node &lt;1&gt; (testnet) [1.18.26]</b> 🔴\n\n<code>"));
+    assert!(html.ends_with("</code>"));
+    assert!(html.contains(&report.identity[..16]));
+    assert!(html.contains(" place:    42    | credits: 123456 \n"));
+    assert!(html.contains("Fees    38 bl|        0.1900        \n"));
+}
+
+#[test]
+fn text_report_names_the_health() {
+    let mut report = report("node-1");
+    report.health = Health::CriticalSkipRate;
+    report.fee_revenue = None;
+    let text = render::text(&report);
+
+    assert!(text.starts_with("node-1 [1.18.26] 🔴 (critical skip rate)\n\n"));
+    assert!(text.contains("  60/40   |  2   | 5.00  |  3.25   \n"));
+    assert!(!text.contains("Fees"));
+}
+
+#[test]
+fn markdown_report_is_a_table_with_escaped_cells() {
+    let mut report = report("node-1");
+    report.version = "1.18|custom".to_string();
+    let markdown = report.render(ReportFormat::Markdown);
+
+    assert!(markdown.starts_with("### node-1 [1.18|custom] 🟢\n\n| field | value |\n|---|---|\n"));
+    assert!(markdown.contains("| status | healthy |\n"));
+    assert!(markdown.contains(&format!("| identity | `{}` |\n", report.identity)));
+    assert!(markdown.contains("| skip % | 5.00 |\n"));
+    assert!(markdown.contains("| fee revenue | 0.1900 (38 blocks) |\n"));
+    assert!(!markdown.contains("| 1.18|custom |"));
+}
+
+#[test]
+fn json_report_round_trips() {
+    let report = report("node-1");
+    let json = report.render(ReportFormat::Json);
+    let parsed: ValidatorReport = serde_json::from_str(&json).unwrap();
+    assert_eq!(parsed, report);
+    assert!(json.contains("\"skipRate\""));
+}
+
+#[test]
+fn csv_report_quotes_fields_that_need_it() {
+    let reports = [report("node-1"), report("node, \"two\"")];
+    let csv = render::csv(&reports);
+    let lines: Vec<&str> = csv.lines().collect();
+
+    assert_eq!(lines.len(), 3);
+    assert_eq!(lines[0].split(',').count(), 21);
+    assert!(lines[1].starts_with("2024-05-01T12:00:00+00:00,node-1,"));
+    assert!(lines[1].ends_with(",150000,38,0.19"));
+    assert!(lines[2].contains(",\"node, \"\"two\"\"\","));
+}
+
+#[test]
+fn export_keeps_the_latest_report_and_appends_csv_rows() {
+    let dir = temp_dir("report-export");
+    let report = report("node 1");
+    let formats = [ReportFormat::Json, ReportFormat::Text, ReportFormat::Csv];
+    report.export(&dir, &formats).unwrap();
+    report.export(&dir, &formats).unwrap();
+
+    let csv = std::fs::read_to_string(dir.join("node_1.csv")).unwrap();
+    assert_eq!(csv.lines().count(), 3);
+    assert_eq!(csv.lines().next().unwrap(), render::csv_header());
+    let json = std::fs::read_to_string(dir.join("node_1.json")).unwrap();
+    assert_eq!(json, report.render(ReportFormat::Json));
+    assert!(dir.join("node_1.txt").exists());
+    assert!(!dir.join("node_1.html").exists());
+}
+
+#[test]
+fn digest_has_a_line_per_validator() {
+    let mut second = report("node-2");
+    second.health = Health::Delinquent;
+    let digest = render::digest(&[report("node-1"), second]);
+
+    assert_eq!(
+        digest,
+        "<b>Digest</b>\n\
+         \n🟢 <b>node-1</b> skip 5.00%/3.25% | #42 | 5.00 SOL\
+         \n🔴 <b>node-2</b> skip 5.00%/3.25% | #42 | 5.00 SOL"
+    );
+}
+
+#[test]
+fn daily_report_shows_changes_since_the_previous_one() {
+    let mut previous = report("node-1");
+    previous.balances.identity = 5.5;
+    previous.credits.rank = 45;
+    let previous = HashMap::from([("node-1".to_string(), previous)]);
+    let stats = HashMap::from([(
+        "node-1".to_string(),
+        DailyStats {
+            uptime: Some(99.5),
+            max_slot_lag: None,
+        },
+    )]);
+    let current = report("node-1");
+    let daily = render::daily(
+        current.timestamp,
+        &[current, report("node-2")],
+        &previous,
+        &stats,
+    );
+
+    assert!(daily.starts_with("<b>Daily summary 2024-05-01</b>\n"));
+    assert!(daily.contains("identity 5.00 (-0.50)\nvote     12.50 (+0.00)\n"));
+    assert!(daily.contains("credits  123456 | place 42 (▲3)\n"));
+    assert!(daily.contains("uptime   99.50% | max lag ?"));
+    // A validator without a previous report shows its values only.
+    assert!(daily.contains("identity 5.00\nvote     12.50\n"));
+}
+
+#[test]
+fn report_formats_parse_by_name_and_extension() {
+    assert_eq!(ReportFormat::from_str("md"), Ok(ReportFormat::Markdown));
+    assert_eq!(ReportFormat::from_str("TXT"), Ok(ReportFormat::Text));
+    assert_eq!(ReportFormat::from_str("Html"), Ok(ReportFormat::Html));
+    assert_eq!(
+        ReportFormat::from_str("pdf"),
+        Err("Unknown report format: pdf".to_string())
+    );
+    assert_eq!(ReportFormat::Markdown.extension(), "md");
+}
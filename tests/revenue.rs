@@ -0,0 +1,131 @@
+mod common;
+
+use common::*;
+use serde_json::json;
+use solana_bot::client::Client;
+use solana_bot::revenue::{EpochRevenue, RevenueBook};
+use std::path::Path;
+
+/// First slot of the current epoch; the mock's leader slots are offsets from it.
+const FIRST_SLOT: u64 = EPOCH * SLOTS_IN_EPOCH;
+/// Fee the mock pays the identity for every produced block.
+const BLOCK_FEE: u64 = 5_000;
+
+fn book(f: &Fixture) -> RevenueBook {
+    RevenueBook::open(Path::new(&f.settings.revenue.dir))
+}
+
+fn client(f: &Fixture) -> Client {
+    Client::new(&f.settings.nodes[0].validator, &f.state.rpc)
+}
+
+fn revenue(blocks: usize) -> EpochRevenue {
+    EpochRevenue {
+        blocks,
+        fees: (blocks as u64 * BLOCK_FEE) as f64 / LAMPORTS_PER_SOL as f64,
+    }
+}
+
+#[tokio::test]
+async fn fees_accumulate_over_updates_without_fetching_a_block_twice() {
+    let f = Fixture::new("revenue-accumulate");
+    let (book, client) = (book(&f), client(&f));
+
+    assert_eq!(book.update(&client, EPOCH, FIRST_SLOT + 9).await, 0);
+    assert_eq!(book.epoch_revenue("node-1", EPOCH).await, Some(revenue(10)));
+    assert_eq!(f.mock.calls("getBlock"), 10);
+
+    assert_eq!(book.update(&client, EPOCH, u64::MAX).await, 0);
+    assert_eq!(book.update(&client, EPOCH, u64::MAX).await, 0);
+    assert_eq!(book.epoch_revenue("node-1", EPOCH).await, Some(revenue(60)));
+    assert_eq!(f.mock.calls("getBlock"), 60);
+    assert_eq!(f.mock.calls("getLeaderSchedule"), 1);
+    assert_eq!(
+        book.epoch_revenue("node-1", EPOCH - 1).await,
+        Some(revenue(0))
+    );
+    assert_eq!(book.epoch_revenue("node-2", EPOCH).await, None);
+}
+
+#[tokio::test]
+async fn ledger_is_reloaded_after_a_restart() {
+    let f = Fixture::new("revenue-restart");
+    let client = client(&f);
+    {
+        let book = book(&f);
+        assert_eq!(book.update(&client, EPOCH, FIRST_SLOT + 29).await, 0);
+    }
+    let ledger = std::fs::read_to_string(book(&f).ledger_path("node-1")).unwrap();
+    let lines: Vec<&str> = ledger.lines().collect();
+    assert_eq!(lines.len(), 31);
+    assert_eq!(lines[0], "slot,epoch,produced,fee_lamports");
+    assert_eq!(
+        lines[1],
+        format!("{},{},true,{}", FIRST_SLOT, EPOCH, BLOCK_FEE)
+    );
+
+    let book = book(&f);
+    assert_eq!(book.update(&client, EPOCH, u64::MAX).await, 0);
+    assert_eq!(book.epoch_revenue("node-1", EPOCH).await, Some(revenue(60)));
+    assert_eq!(f.mock.calls("getBlock"), 60);
+    let ledger = std::fs::read_to_string(book.ledger_path("node-1")).unwrap();
+    assert_eq!(ledger.lines().count(), 61);
+}
+
+#[tokio::test]
+async fn skipped_slots_are_recorded_without_revenue() {
+    let f = Fixture::new("revenue-skipped");
+    f.mock.fail("getBlock", -32007);
+    let (book, client) = (book(&f), client(&f));
+
+    assert_eq!(book.update(&client, EPOCH, u64::MAX).await, 0);
+    assert_eq!(book.update(&client, EPOCH, u64::MAX).await, 0);
+
+    assert_eq!(book.epoch_revenue("node-1", EPOCH).await, Some(revenue(0)));
+    assert_eq!(f.mock.calls("getBlock"), 60);
+    let ledger = std::fs::read_to_string(book.ledger_path("node-1")).unwrap();
+    assert!(ledger
+        .lines()
+        .skip(1)
+        .all(|line| line.ends_with(",false,0")));
+}
+
+#[tokio::test]
+async fn unavailable_blocks_are_retried_on_the_next_update() {
+    let f = Fixture::new("revenue-unavailable");
+    f.mock.fail("getBlock", -32004);
+    let (book, client) = (book(&f), client(&f));
+
+    assert_eq!(book.update(&client, EPOCH, u64::MAX).await, 60);
+    assert!(!book.ledger_path("node-1").exists());
+
+    healthy(&f.mock, &f.keys);
+    assert_eq!(book.update(&client, EPOCH, u64::MAX).await, 0);
+    assert_eq!(book.epoch_revenue("node-1", EPOCH).await, Some(revenue(60)));
+}
+
+#[tokio::test]
+async fn large_backlogs_are_accounted_over_several_updates() {
+    let f = Fixture::new("revenue-backlog");
+    f.mock.set(
+        "getLeaderSchedule",
+        json!({ f.keys.identity.clone(): (0..250).collect::<Vec<u64>>() }),
+    );
+    let (book, client) = (book(&f), client(&f));
+
+    assert_eq!(book.update(&client, EPOCH, u64::MAX).await, 50);
+    assert_eq!(book.update(&client, EPOCH, u64::MAX).await, 0);
+    assert_eq!(
+        book.epoch_revenue("node-1", EPOCH).await,
+        Some(revenue(250))
+    );
+}
+
+#[test]
+fn ledger_names_are_safe_file_names() {
+    let book = RevenueBook::open(Path::new("revenue"));
+    assert_eq!(
+        book.ledger_path("node 1/main"),
+        Path::new("revenue/node_1_main-revenue.csv")
+    );
+}
@@ -0,0 +1,185 @@
+mod common;
+
+use chrono::{DateTime, Utc};
+use common::*;
+use solana_bot::report::schedule::{ReportKind, ReportScheduler};
+use solana_bot::settings::{ReportSchedule, Reports};
+
+fn at(time: &str) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(time)
+        .unwrap()
+        .with_timezone(&Utc)
+}
+
+fn schedule(cron: &str, timezone: &str) -> Option<ReportSchedule> {
+    Some(ReportSchedule {
+        cron: cron.to_string(),
+        timezone: timezone.to_string(),
+    })
+}
+
+fn full(cron: &str, timezone: &str) -> ReportScheduler {
+    ReportScheduler::new(&Reports {
+        full: schedule(cron, timezone),
+        ..Reports::default()
+    })
+}
+
+/// The next `count` fire times after `now`.
+fn fire_times(scheduler: &ReportScheduler, mut now: DateTime<Utc>, count: usize) -> Vec<String> {
+    let mut times = Vec::new();
+    for _ in 0..count {
+        let (time, _) = scheduler.next_after(now).expect("fire time");
+        times.push(time.to_rfc3339());
+        now = time;
+    }
+    times
+}
+
+#[test]
+fn hourly_report_fires_on_the_next_hour() {
+    let scheduler = full("0 0 * * * *", "UTC");
+    assert_eq!(
+        scheduler.next_after(at("2024-05-01T10:30:00Z")),
+        Some((at("2024-05-01T11:00:00Z"), vec![ReportKind::Full]))
+    );
+    // A report due right now was sent already.
+    assert_eq!(
+        scheduler.next_after(at("2024-05-01T11:00:00Z")).unwrap().0,
+        at("2024-05-01T12:00:00Z")
+    );
+}
+
+#[test]
+fn schedules_follow_the_wall_clock_of_their_time_zone() {
+    let scheduler = full("0 0 9 * * *", "Europe/Berlin");
+    assert_eq!(
+        scheduler.next_after(at("2024-01-15T00:00:00Z")).unwrap().0,
+        at("2024-01-15T08:00:00Z")
+    );
+    assert_eq!(
+        scheduler.next_after(at("2024-07-15T00:00:00Z")).unwrap().0,
+        at("2024-07-15T07:00:00Z")
+    );
+    let scheduler = full("0 0 9 * * *", "America/New_York");
+    assert_eq!(
+        scheduler.next_after(at("2024-01-15T12:00:00Z")).unwrap().0,
+        at("2024-01-15T14:00:00Z")
+    );
+}
+
+#[test]
+fn time_skipped_when_clocks_go_forward_fires_after_the_change() {
+    let scheduler = full("0 30 2 * * *", "Europe/Berlin");
+    assert_eq!(
+        fire_times(&scheduler, at("2024-03-29T12:00:00Z"), 3),
+        [
+            "2024-03-30T01:30:00+00:00",
+            // 02:30 doesn't exist on the 31st, the report comes at 03:30 summer time.
+            "2024-03-31T01:30:00+00:00",
+            "2024-04-01T00:30:00+00:00",
+        ]
+    );
+}
+
+#[test]
+fn time_repeated_when_clocks_go_back_fires_once() {
+    let scheduler = full("0 30 2 * * *", "Europe/Berlin");
+    assert_eq!(
+        fire_times(&scheduler, at("2024-10-25T12:00:00Z"), 3),
+        [
+            "2024-10-26T00:30:00+00:00",
+            "2024-10-27T00:30:00+00:00",
+            "2024-10-28T01:30:00+00:00",
+        ]
+    );
+    let scheduler = full("0 0 * * * *", "Europe/Berlin");
+    assert_eq!(
+        fire_times(&scheduler, at("2024-10-26T23:30:00Z"), 3),
+        [
+            "2024-10-27T00:00:00+00:00",
+            "2024-10-27T02:00:00+00:00",
+            "2024-10-27T03:00:00+00:00",
+        ]
+    );
+}
+
+#[test]
+fn reports_due_together_fire_together() {
+    let scheduler = ReportScheduler::new(&Reports {
+        full: schedule("0 0 * * * *", "UTC"),
+        digest: schedule("0 0 */6 * * *", "UTC"),
+        daily: schedule("0 0 0 * * *", "UTC"),
+        ..Reports::default()
+    });
+    assert_eq!(
+        scheduler.next_after(at("2024-05-01T23:10:00Z")),
+        Some((
+            at("2024-05-02T00:00:00Z"),
+            vec![ReportKind::Full, ReportKind::Digest, ReportKind::Daily]
+        ))
+    );
+    assert_eq!(
+        scheduler.next_after(at("2024-05-02T05:10:00Z")),
+        Some((
+            at("2024-05-02T06:00:00Z"),
+            vec![ReportKind::Full, ReportKind::Digest]
+        ))
+    );
+}
+
+#[test]
+fn invalid_schedules_are_rejected() {
+    let error = schedule("every hour", "UTC").unwrap().parse().unwrap_err();
+    assert!(error.starts_with("invalid cron expression 'every hour'"));
+    let error = schedule("0 0 * * * *", "Mars/Olympus")
+        .unwrap()
+        .parse()
+        .unwrap_err();
+    assert!(error.starts_with("invalid time zone 'Mars/Olympus'"));
+
+    let mut settings = Fixture::builder("schedule-invalid")
+        .offline()
+        .build()
+        .settings;
+    settings.reports.daily = schedule("0 0 25 * * *", "UTC");
+    let problems = settings.validate().unwrap_err();
+    assert_eq!(problems[0].path, "reports.daily");
+
+    // An invalid schedule is left out, the others still run.
+    let scheduler = ReportScheduler::new(&settings.reports);
+    assert_eq!(
+        scheduler.next_after(at("2024-05-01T10:30:00Z")),
+        Some((at("2024-05-01T11:00:00Z"), vec![ReportKind::Full]))
+    );
+    assert!(full("every hour", "UTC").is_empty());
+}
+
+#[tokio::test]
+async fn disabled_reports_are_left_out_per_validator() {
+    let f = Fixture::builder("schedule-disabled")
+        .nodes(&["node-1", "node-2"])
+        .configure(|settings| {
+            settings.reports.full = schedule("* * * * * *", "UTC");
+            settings.reports.digest = schedule("* * * * * *", "UTC");
+            settings.nodes[1].disabled_reports = vec![ReportKind::Full];
+        })
+        .build();
+
+    let digest_sent = || {
+        f.telegram
+            .sent_to(REPORT_CHAT)
+            .iter()
+            .any(|sent| sent.text.starts_with("<b>Digest</b>"))
+    };
+    solana_bot::run_until(f.settings.clone(), wait_for(digest_sent)).await;
+
+    let sent = f.telegram.sent_to(REPORT_CHAT);
+    let digest = sent
+        .iter()
+        .find(|sent| sent.text.starts_with("<b>Digest</b>"))
+        .unwrap();
+    assert!(digest.text.contains("<b>node-1</b>") && digest.text.contains("<b>node-2</b>"));
+    assert!(sent.iter().any(|sent| sent.text.starts_with("<b>node-1 [")));
+    assert!(!sent.iter().any(|sent| sent.text.starts_with("<b>node-2 [")));
+}
@@ -0,0 +1,231 @@
+mod common;
+
+use async_trait::async_trait;
+use common::*;
+use solana_bot::checkers::{Checker, ValidatorContext};
+use solana_bot::events::Event;
+use solana_bot::scheduler::Scheduler;
+use solana_bot::settings::NodeCheckSettings;
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// A checker written for the tests, to show that the scheduler runs any `Checker`.
+#[derive(Default)]
+struct Probe {
+    interval: Duration,
+    /// How long each check takes.
+    duration: Duration,
+    /// Checks that panic before the probe starts to succeed.
+    panics: AtomicUsize,
+    events: Vec<Event>,
+    /// Whether each check asks the RPC of its validator.
+    rpc: bool,
+    runs: AtomicUsize,
+    in_flight: AtomicUsize,
+    max_in_flight: AtomicUsize,
+    validators: Mutex<HashSet<String>>,
+}
+
+#[async_trait]
+impl Checker for Probe {
+    fn name(&self) -> &'static str {
+        "probe"
+    }
+
+    fn interval(&self, _node: &NodeCheckSettings) -> Duration {
+        self.interval
+    }
+
+    async fn check(&self, context: &ValidatorContext) -> Vec<Event> {
+        self.runs.fetch_add(1, Ordering::SeqCst);
+        let in_flight = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+        self.max_in_flight.fetch_max(in_flight, Ordering::SeqCst);
+        tokio::time::sleep(self.duration).await;
+        if self.rpc {
+            context.client.is_delinquent().await;
+        }
+        self.in_flight.fetch_sub(1, Ordering::SeqCst);
+        self.validators
+            .lock()
+            .unwrap()
+            .insert(context.node.validator.name.clone());
+        let panics = self.panics.load(Ordering::SeqCst);
+        if panics > 0 {
+            self.panics.store(panics - 1, Ordering::SeqCst);
+            panic!("probe failed");
+        }
+        self.events.clone()
+    }
+}
+
+fn fixture(name: &str, nodes: &[&str]) -> Fixture {
+    Fixture::builder(name).nodes(nodes).offline().build()
+}
+
+/// Runs `probe` on a scheduler until `done` holds, or fails after a few seconds.
+async fn run_until(f: &Fixture, probe: &Arc<Probe>, done: impl Fn(&Probe) -> bool) {
+    let mut scheduler = Scheduler::new(&f.settings.nodes, &f.state);
+    scheduler.register(probe.clone());
+    let task = tokio::spawn(scheduler.join());
+    wait_for(|| done(probe)).await;
+    task.abort();
+}
+
+#[tokio::test]
+async fn every_validator_is_checked_repeatedly() {
+    let f = fixture("scheduler-validators", &["node-1", "node-2"]);
+    let probe = Arc::new(Probe {
+        interval: Duration::from_millis(20),
+        ..Probe::default()
+    });
+    run_until(&f, &probe, |probe| {
+        probe.runs.load(Ordering::SeqCst) >= 6 && probe.validators.lock().unwrap().len() == 2
+    })
+    .await;
+    let checkers = f.state.status.checkers();
+    assert!(checkers["probe"].last_success.is_some());
+}
+
+#[tokio::test]
+async fn check_without_any_rpc_answer_is_not_a_success() {
+    let f = fixture("scheduler-rpc-down", &["node-1"]);
+    let probe = Arc::new(Probe {
+        interval: Duration::from_millis(20),
+        rpc: true,
+        ..Probe::default()
+    });
+    run_until(&f, &probe, |probe| probe.runs.load(Ordering::SeqCst) >= 4).await;
+    let checkers = f.state.status.checkers();
+    assert_eq!(checkers["probe"].last_success, None);
+}
+
+#[tokio::test]
+async fn check_with_rpc_answers_is_a_success() {
+    let f = Fixture::new("scheduler-rpc-up");
+    let probe = Arc::new(Probe {
+        interval: Duration::from_millis(20),
+        rpc: true,
+        ..Probe::default()
+    });
+    let mut scheduler = Scheduler::new(&f.settings.nodes, &f.state);
+    scheduler.register(probe.clone());
+    let task = tokio::spawn(scheduler.join());
+    wait_for(|| f.state.status.checkers()["probe"].last_success.is_some()).await;
+    task.abort();
+}
+
+#[tokio::test]
+async fn panicking_check_does_not_stop_later_runs() {
+    let f = fixture("scheduler-panic", &["node-1"]);
+    let probe = Arc::new(Probe {
+        interval: Duration::from_millis(20),
+        panics: AtomicUsize::new(2),
+        ..Probe::default()
+    });
+    run_until(&f, &probe, |probe| probe.runs.load(Ordering::SeqCst) >= 4).await;
+    assert_eq!(probe.panics.load(Ordering::SeqCst), 0);
+}
+
+#[tokio::test]
+async fn runs_of_a_validator_never_overlap() {
+    let f = fixture("scheduler-overlap", &["node-1"]);
+    let probe = Arc::new(Probe {
+        interval: Duration::from_millis(10),
+        duration: Duration::from_millis(50),
+        ..Probe::default()
+    });
+    run_until(&f, &probe, |probe| probe.runs.load(Ordering::SeqCst) >= 4).await;
+    assert_eq!(probe.max_in_flight.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn events_are_published_to_the_outputs() {
+    let f = fixture("scheduler-events", &["node-1"]);
+    let probe = Arc::new(Probe {
+        interval: Duration::from_millis(500),
+        events: vec![
+            Event::Delinquent(f.settings.nodes[0].validator.clone()),
+            Event::ReportReady("node-1 report".to_string()),
+        ],
+        ..Probe::default()
+    });
+    run_until(&f, &probe, |_| {
+        !f.telegram.sent_to(ALERT_CHAT).is_empty() && !f.telegram.sent_to(REPORT_CHAT).is_empty()
+    })
+    .await;
+    assert!(f.telegram.sent_to(ALERT_CHAT)[0]
+        .text
+        .contains("DELINQUENT"));
+    assert_eq!(f.telegram.sent_to(REPORT_CHAT)[0].text, "node-1 report");
+    assert_eq!(f.state.status.active_alerts().len(), 1);
+}
+
+#[tokio::test]
+async fn shutdown_waits_for_checks_in_progress() {
+    let f = fixture("scheduler-shutdown", &["node-1"]);
+    let probe = Arc::new(Probe {
+        interval: Duration::from_millis(20),
+        duration: Duration::from_millis(300),
+        ..Probe::default()
+    });
+    let mut scheduler = Scheduler::new(&f.settings.nodes, &f.state);
+    scheduler.register(probe.clone());
+    let stop = {
+        let probe = probe.clone();
+        async move {
+            while probe.in_flight.load(Ordering::SeqCst) == 0 {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        }
+    };
+    assert!(scheduler.run_until(stop, Duration::from_secs(5)).await);
+    assert_eq!(probe.runs.load(Ordering::SeqCst), 1);
+    assert_eq!(probe.in_flight.load(Ordering::SeqCst), 0);
+}
+
+#[tokio::test]
+async fn shutdown_cancels_checks_after_grace_period() {
+    let f = fixture("scheduler-grace", &["node-1"]);
+    let probe = Arc::new(Probe {
+        interval: Duration::from_millis(20),
+        duration: Duration::from_secs(30),
+        ..Probe::default()
+    });
+    let mut scheduler = Scheduler::new(&f.settings.nodes, &f.state);
+    scheduler.register(probe.clone());
+    let started = Instant::now();
+    let stop = {
+        let probe = probe.clone();
+        async move {
+            while probe.in_flight.load(Ordering::SeqCst) == 0 {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        }
+    };
+    assert!(!scheduler.run_until(stop, Duration::from_millis(100)).await);
+    assert!(started.elapsed() < Duration::from_secs(5));
+}
+
+#[tokio::test]
+async fn bot_announces_start_and_stop() {
+    let f = Fixture::builder("scheduler-lifecycle")
+        .configure(|settings| settings.telegram.lifecycle_notices = true)
+        .build();
+
+    let started = || {
+        f.telegram
+            .sent_to(REPORT_CHAT)
+            .iter()
+            .any(|sent| sent.text.contains("started, watching 1 validators"))
+    };
+    tokio::time::timeout(
+        WAIT,
+        solana_bot::run_until(f.settings.clone(), wait_for(started)),
+    )
+    .await
+    .expect("bot stops");
+    let last = f.telegram.sent_to(REPORT_CHAT).pop().expect("stop notice");
+    assert_eq!(last.text, "<b>solana-bot</b> stopping");
+}
@@ -0,0 +1,150 @@
+mod common;
+
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use common::*;
+use serde_json::Value;
+use solana_bot::settings::{NodeCheckSettings, StorageSettings};
+use solana_bot::storage::{HistoryStore, Metric};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+const DAY: Duration = Duration::from_secs(24 * 3600);
+
+fn open(dir: &Path, retention: Duration, nodes: &[NodeCheckSettings]) -> HistoryStore {
+    let settings = StorageSettings {
+        enabled: true,
+        dir: dir.to_string_lossy().into_owned(),
+        retention,
+    };
+    HistoryStore::open(&settings, nodes)
+}
+
+/// Noon of the day `days` before today, so samples never straddle midnight.
+fn days_ago(days: i64) -> DateTime<Utc> {
+    let noon = Utc::now().date_naive().and_hms_opt(12, 0, 0).unwrap();
+    noon.and_utc() - ChronoDuration::days(days)
+}
+
+fn segment(dir: &Path, timestamp: DateTime<Utc>) -> PathBuf {
+    dir.join(format!("{}.jsonl", timestamp.format("%Y-%m-%d")))
+}
+
+#[test]
+fn samples_are_appended_and_queried_by_validator_and_metric() {
+    let dir = temp_dir("storage-append");
+    let mut node = node("node-1", &Keys::new_unique(), OFFLINE);
+    node.validator.cluster = Some("testnet".to_string());
+    let store = open(&dir, 30 * DAY, &[node]);
+    let (first, second) = (days_ago(0) - ChronoDuration::minutes(2), days_ago(0));
+
+    store.record_at(
+        first,
+        "node-1",
+        &[(Metric::SkipRate, 1.5), (Metric::IdentityBalance, 3.)],
+    );
+    store.record_at(second, "node-1", &[(Metric::SkipRate, 2.5)]);
+    store.record_at(second, "node-2", &[(Metric::SkipRate, 9.)]);
+    store.record_at(second, "node-2", &[]);
+    store.flush();
+
+    let points = store.query("node-1", Metric::SkipRate, days_ago(1), days_ago(0));
+    let values: Vec<f64> = points.iter().map(|(_, value)| *value).collect();
+    assert_eq!(values, [1.5, 2.5]);
+    assert_eq!(points[0].0.timestamp(), first.timestamp());
+    assert_eq!(
+        store.query("node-2", Metric::IdentityBalance, days_ago(1), days_ago(0)),
+        []
+    );
+
+    let file = std::fs::read_to_string(segment(&dir, second)).unwrap();
+    let lines: Vec<Value> = file
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(lines.len(), 4);
+    assert_eq!(lines[0]["cluster"], "testnet");
+    assert_eq!(lines[0]["metric"], "skipRate");
+    assert!(lines[3].get("cluster").is_none());
+}
+
+#[test]
+fn disabled_store_records_nothing() {
+    let dir = temp_dir("storage-disabled").join("history");
+    let settings = StorageSettings {
+        enabled: false,
+        dir: dir.to_string_lossy().into_owned(),
+        retention: DAY,
+    };
+    let store = HistoryStore::open(&settings, &[]);
+
+    store.record("node-1", &[(Metric::SkipRate, 1.)]);
+
+    assert!(!dir.exists());
+    assert_eq!(
+        store.query("node-1", Metric::SkipRate, days_ago(1), days_ago(0)),
+        []
+    );
+}
+
+#[test]
+fn query_reads_every_day_segment_in_its_window() {
+    let dir = temp_dir("storage-days");
+    let store = open(&dir, 30 * DAY, &[]);
+    for days in [3, 2, 1, 0] {
+        store.record_at(days_ago(days), "node-1", &[(Metric::Credits, days as f64)]);
+    }
+
+    let values = |since, until| -> Vec<f64> {
+        store
+            .query("node-1", Metric::Credits, since, until)
+            .into_iter()
+            .map(|(_, value)| value)
+            .collect()
+    };
+    assert_eq!(values(days_ago(4), days_ago(0)), [3., 2., 1., 0.]);
+    assert_eq!(
+        values(
+            days_ago(2) - ChronoDuration::minutes(1),
+            days_ago(1) + ChronoDuration::minutes(1)
+        ),
+        [2., 1.]
+    );
+    assert_eq!(values(days_ago(10), days_ago(5)), Vec::<f64>::new());
+    for days in [3, 2, 1, 0] {
+        assert!(segment(&dir, days_ago(days)).exists());
+    }
+}
+
+#[test]
+fn expired_segments_are_purged_on_open() {
+    let dir = temp_dir("storage-purge");
+    for days in [10, 3, 1, 0] {
+        std::fs::write(segment(&dir, days_ago(days)), "").unwrap();
+    }
+    std::fs::write(dir.join("notes.txt"), "kept").unwrap();
+
+    let _store = open(&dir, 2 * DAY, &[]);
+
+    assert!(!segment(&dir, days_ago(10)).exists());
+    assert!(!segment(&dir, days_ago(3)).exists());
+    assert!(segment(&dir, days_ago(1)).exists());
+    assert!(segment(&dir, days_ago(0)).exists());
+    assert!(dir.join("notes.txt").exists());
+}
+
+#[test]
+fn rotating_to_a_new_day_purges_expired_segments() {
+    let dir = temp_dir("storage-rotation");
+    let store = open(&dir, 2 * DAY, &[]);
+
+    store.record_at(days_ago(5), "node-1", &[(Metric::Rank, 5.)]);
+    assert!(segment(&dir, days_ago(5)).exists());
+
+    store.record_at(days_ago(0), "node-1", &[(Metric::Rank, 1.)]);
+    store.flush();
+
+    assert!(!segment(&dir, days_ago(5)).exists());
+    let points = store.query("node-1", Metric::Rank, days_ago(6), days_ago(0));
+    assert_eq!(points.len(), 1);
+    assert_eq!(points[0].1, 1.);
+}